@@ -0,0 +1,49 @@
+//! 1,000,000行の.langの読み込みの比較
+//!
+//! - `lines_then_map`: 以前の読み込み方。行の一覧(LangLine)を作り、容量を確保していないマップに入れ直す
+//! - `preallocated`: 現在の `parse_lang_loaded_with`。行数から容量を確保し、借用した行から直接マップに入れる
+//!
+//! `cargo bench --bench parse` で実行する
+
+use std::hint::black_box;
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use indexmap::IndexMap;
+use json_lang::codec::{parse_lang_lines, parse_lang_loaded_with, LangLine, ReadOptions};
+
+const LINES: usize = 1_000_000;
+
+fn synthetic_lang() -> String {
+    let mut text = String::with_capacity(LINES * 48);
+    for i in 0..LINES {
+        if i % 100 == 0 {
+            text.push_str("# section\n");
+        } else {
+            text.push_str(&format!("item.synthetic.key{}=Synthetic value number {}\n", i, i));
+        }
+    }
+    text
+}
+
+fn lines_then_map(contents: &str) -> IndexMap<String, String> {
+    let mut lang_map = IndexMap::new();
+    for line in parse_lang_lines(contents) {
+        if let LangLine::Entry(key, value) = line {
+            lang_map.insert(key, value);
+        }
+    }
+    lang_map
+}
+
+fn parse(c: &mut Criterion) {
+    let contents = synthetic_lang();
+    let options = ReadOptions::default();
+    let mut group = c.benchmark_group("parse_1m_lines");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(contents.len() as u64));
+    group.bench_function("lines_then_map", |b| b.iter(|| lines_then_map(black_box(&contents))));
+    group.bench_function("preallocated", |b| b.iter(|| parse_lang_loaded_with(black_box(&contents), &options)));
+    group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
@@ -0,0 +1,68 @@
+#ifndef JSON_LANG_H
+#define JSON_LANG_H
+
+/* このファイルはcbindgenで生成しています。直接編集しないでください */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * 変換できた
+ */
+#define JSON_LANG_OK 0
+
+/**
+ * 入力を解析できなかった
+ */
+#define JSON_LANG_PARSE_ERROR 1
+
+/**
+ * 引数がNULL、または入力がUTF-8ではない
+ */
+#define JSON_LANG_INVALID_ARGUMENT 2
+
+/**
+ * 内部でパニックした
+ */
+#define JSON_LANG_PANIC 3
+
+/**
+ * .langのテキストを整形したJSONに変換する。戻り値はJSON_LANG_OKなどの結果コード
+ *
+ * # Safety
+ * `input` はNULL、またはNUL終端の文字列を指すこと。`output` と `error` はNULL、または書き込めるポインタを指すこと
+ */
+int json_lang_lang_to_json(const char *input,
+                           char **output,
+                           char **error);
+
+/**
+ * JSONのテキストを.langに変換する。戻り値はJSON_LANG_OKなどの結果コード
+ *
+ * # Safety
+ * `input` はNULL、またはNUL終端の文字列を指すこと。`output` と `error` はNULL、または書き込めるポインタを指すこと
+ */
+int json_lang_json_to_lang(const char *input,
+                           char **output,
+                           char **error);
+
+/**
+ * エラーメッセージの言語を `"ja"` か `"en"` に切り替える。戻り値はJSON_LANG_OKかJSON_LANG_INVALID_ARGUMENT
+ *
+ * # Safety
+ * `language` はNULL、またはNUL終端の文字列を指すこと
+ */
+int json_lang_set_language(const char *language);
+
+/**
+ * json_lang_lang_to_json/json_lang_json_to_langが返した文字列を解放する。NULLは何もしない
+ *
+ * # Safety
+ * `text` はNULL、またはこのライブラリが返してまだ解放していない文字列であること
+ */
+void json_lang_free(char *text);
+
+#endif  /* JSON_LANG_H */
@@ -0,0 +1,184 @@
+//! ロケールごとの翻訳率をshields.ioのendpoint形式のJSON(`<出力先>/<ロケール>.json`)に書き出す(badgesサブコマンド)
+//!
+//! 翻訳率は参照のキーのうち、ファイルに空でなくキーと同じでもない値があるものの割合(小数点以下は切り捨て)。
+//! 色は--green-at以上ならgreen、--yellow-at以上ならyellow、それ未満はred
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use indexmap::{IndexMap, IndexSet};
+use serde::Serialize;
+use crate::check::echo;
+use crate::check::reference::load_reference_keys;
+use crate::cli::BadgesArgs;
+use crate::convert::files_or_input_dir;
+use crate::format::load_by_extension;
+use crate::i18n::tf;
+
+/// shields.ioのendpointバッジ
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Badge<'a> {
+    schema_version: u8,
+    label: &'a str,
+    message: String,
+    color: &'static str,
+}
+
+/// 参照のキーのうち、翻訳済みのキーの数と、値がキーと同じため除いたキーの数
+fn covered_keys(lang_map: &IndexMap<String, String>, reference: &IndexSet<String>, last_segment: bool) -> (usize, usize) {
+    let mut covered = 0;
+    let mut echoed = 0;
+    for (key, value) in reference.iter().filter_map(|key| lang_map.get(key).map(|value| (key, value))).filter(|(_, value)| !value.is_empty()) {
+        if echo::is_echo(key, value, last_segment) {
+            echoed += 1;
+        } else {
+            covered += 1;
+        }
+    }
+    (covered, echoed)
+}
+
+/// 翻訳率(%、切り捨て)。100%はすべてのキーがある場合だけになる
+fn percent(covered: usize, total: usize) -> usize {
+    covered * 100 / total
+}
+
+/// 翻訳率に対応する色
+fn color(percent: usize, yellow_at: u8, green_at: u8) -> &'static str {
+    if percent >= usize::from(green_at) {
+        "green"
+    } else if percent >= usize::from(yellow_at) {
+        "yellow"
+    } else {
+        "red"
+    }
+}
+
+/// badgesサブコマンドの実行
+pub fn run_badges(args: &BadgesArgs) -> Result<bool, String> {
+    if args.yellow_at > args.green_at {
+        return Err(tf("badges.thresholds", &[&args.yellow_at, &args.green_at]));
+    }
+    let reference = load_reference_keys(&args.reference)?;
+    if reference.is_empty() {
+        return Err(tf("badges.empty_reference", &[&args.reference]));
+    }
+    let reference_path = Path::new(&args.reference).canonicalize().ok();
+    let mut paths = files_or_input_dir(&args.files, &args.input)?;
+    paths.retain(|path| path.canonicalize().ok() != reference_path);
+    paths.sort();
+
+    // 同じロケールの.langと.jsonがあると出力先が重なるため、書き出す前に確かめる
+    let mut locales: HashMap<String, &PathBuf> = HashMap::new();
+    for path in &paths {
+        let locale = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default().to_string();
+        if let Some(first) = locales.insert(locale.clone(), path) {
+            return Err(tf("badges.locale_collision", &[&locale, &first.display(), &path.display()]));
+        }
+    }
+
+    let out_dir = Path::new(&args.out_dir);
+    fs::create_dir_all(out_dir).map_err(|_| tf("io.write_failed", &[&args.out_dir]))?;
+    for path in &paths {
+        let path_str = path.to_string_lossy().to_string();
+        let locale = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default();
+        let lang_map = load_by_extension(&path_str)?;
+        let (covered, echoed) = covered_keys(&lang_map, &reference, args.echo_last_segment);
+        let percent = percent(covered, reference.len());
+        let badge = Badge { schema_version: 1, label: locale, message: format!("{}%", percent), color: color(percent, args.yellow_at, args.green_at) };
+        let badge_path = out_dir.join(format!("{}.json", locale));
+        let badge_path_str = badge_path.to_string_lossy().to_string();
+        let contents = serde_json::to_string_pretty(&badge).map_err(|_| tf("io.write_failed", &[&badge_path_str]))?;
+        fs::write(&badge_path, contents + "\n").map_err(|_| tf("io.write_failed", &[&badge_path_str]))?;
+        println!("{}", tf("badges.file", &[&path_str, &covered, &reference.len(), &percent, &badge.color, &badge_path_str]));
+        if echoed > 0 {
+            println!("{}", tf("badges.echoed", &[&echoed]));
+        }
+    }
+    println!("{}", tf("badges.written", &[&paths.len()]));
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(entries: &[(&str, &str)]) -> IndexMap<String, String> {
+        entries.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn color_changes_exactly_at_the_thresholds() {
+        assert_eq!(color(0, 50, 90), "red");
+        assert_eq!(color(49, 50, 90), "red");
+        assert_eq!(color(50, 50, 90), "yellow");
+        assert_eq!(color(89, 50, 90), "yellow");
+        assert_eq!(color(90, 50, 90), "green");
+        assert_eq!(color(100, 50, 90), "green");
+    }
+
+    #[test]
+    fn color_with_equal_or_extreme_thresholds() {
+        // yellowとgreenが同じならyellowにはならない
+        assert_eq!(color(69, 70, 70), "red");
+        assert_eq!(color(70, 70, 70), "green");
+        // 0ならどの翻訳率も下回らない
+        assert_eq!(color(0, 0, 0), "green");
+        assert_eq!(color(0, 0, 100), "yellow");
+        assert_eq!(color(99, 0, 100), "yellow");
+        assert_eq!(color(100, 0, 100), "green");
+    }
+
+    #[test]
+    fn percent_rounds_down_so_100_means_complete() {
+        assert_eq!(percent(0, 3), 0);
+        assert_eq!(percent(1, 3), 33);
+        assert_eq!(percent(2, 3), 66);
+        assert_eq!(percent(199, 200), 99);
+        assert_eq!(percent(200, 200), 100);
+    }
+
+    #[test]
+    fn covered_keys_skips_missing_empty_and_echoed_values() {
+        let reference: IndexSet<String> = ["a.name", "b.name", "c.name", "d.name"].iter().map(|key| key.to_string()).collect();
+        let lang_map = map(&[("a.name", "A"), ("b.name", ""), ("c.name", "c.name"), ("extra", "x")]);
+        assert_eq!(covered_keys(&lang_map, &reference, false), (1, 1));
+    }
+
+    #[test]
+    fn run_badges_writes_endpoint_json_per_locale() {
+        let dir = std::env::temp_dir().join(format!("json_lang_badges_test_{}", std::process::id()));
+        let input = dir.join("input");
+        fs::create_dir_all(&input).unwrap();
+        let reference = dir.join("en_us.json");
+        fs::write(&reference, r#"{"a":"A","b":"B","c":"C","d":"D"}"#).unwrap();
+        fs::write(input.join("ja_jp.json"), r#"{"a":"ア","b":"イ"}"#).unwrap();
+        fs::write(input.join("de_de.lang"), "a=A2\nb=B2\nc=C2\nd=D2\n").unwrap();
+        let out_dir = dir.join("badges");
+        let mut args = BadgesArgs {
+            reference: reference.to_str().unwrap().to_string(),
+            files: Vec::new(),
+            input: input.to_str().unwrap().to_string(),
+            out_dir: out_dir.to_str().unwrap().to_string(),
+            yellow_at: 50,
+            green_at: 100,
+            echo_last_segment: false,
+        };
+        assert!(run_badges(&args).unwrap());
+        let badge: serde_json::Value = serde_json::from_str(&fs::read_to_string(out_dir.join("ja_jp.json")).unwrap()).unwrap();
+        assert_eq!(badge, serde_json::json!({"schemaVersion": 1, "label": "ja_jp", "message": "50%", "color": "yellow"}));
+        let badge: serde_json::Value = serde_json::from_str(&fs::read_to_string(out_dir.join("de_de.json")).unwrap()).unwrap();
+        assert_eq!(badge["color"], "green");
+
+        args.yellow_at = 60;
+        args.green_at = 59;
+        assert!(run_badges(&args).unwrap_err().contains("60"));
+
+        // 同じロケールの.langと.jsonがあると出力先が重なるため書き出さない
+        args.green_at = 90;
+        fs::write(input.join("ja_jp.lang"), "a=ア\n").unwrap();
+        assert!(run_badges(&args).unwrap_err().contains("ja_jp"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
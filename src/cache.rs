@@ -0,0 +1,73 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use crate::i18n::tf;
+
+/// 出力ディレクトリに置くキャッシュファイルの名前
+pub const CACHE_FILE_NAME: &str = ".json_lang_cache.json";
+
+/// --incremental用のキャッシュ。入力ファイルごとの内容のハッシュと、変換時の設定のハッシュを記録する
+#[derive(Default, Serialize, Deserialize)]
+pub struct Cache {
+    pub options: String,
+    pub files: IndexMap<String, String>,
+}
+
+impl Cache {
+    /// キャッシュを読み込む。存在しない・壊れている場合は空のキャッシュとして扱う
+    pub fn load(output_dir: &str) -> Cache {
+        let path = format!("{}/{}", output_dir, CACHE_FILE_NAME);
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return Cache::default();
+        };
+        serde_json::from_str(&contents).unwrap_or_else(|_| {
+            println!("{}", tf("cache.corrupt", &[&path]));
+            Cache::default()
+        })
+    }
+
+    pub fn save(&self, output_dir: &str) -> Result<(), String> {
+        let path = format!("{}/{}", output_dir, CACHE_FILE_NAME);
+        if let Some(parent_dir) = Path::new(&path).parent() {
+            fs::create_dir_all(parent_dir).map_err(|_| tf("io.mkdir_failed", &[&path]))?;
+        }
+        let contents = serde_json::to_string_pretty(self).map_err(|_| tf("io.write_failed", &[&path]))?;
+        fs::write(&path, contents).map_err(|_| tf("io.write_failed", &[&path]))
+    }
+
+    /// 設定と入力の内容が前回と同じで、出力が残っていれば変換を省略できる
+    pub fn is_fresh(&self, options_hash: &str, input_path: &str, content_hash: &str, output_path: &str) -> bool {
+        self.options == options_hash
+            && self.files.get(input_path).is_some_and(|h| h == content_hash)
+            && Path::new(output_path).exists()
+    }
+}
+
+/// 内容のハッシュ(FNV-1a 64bit)。Rustのバージョンに依存せず常に同じ値になる
+pub fn content_hash(bytes: &[u8]) -> String {
+    format!("{:016x}", fnv1a(0xcbf2_9ce4_8422_2325, bytes))
+}
+
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+/// ファイルの内容のハッシュ。ファイル全体をメモリに読み込まずに計算する(content_hashと同じ値になる)
+pub fn file_hash(path: &str) -> io::Result<String> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut buffer = [0u8; 64 * 1024];
+    let mut hash = 0xcbf2_9ce4_8422_2325;
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            return Ok(format!("{:016x}", hash));
+        }
+        hash = fnv1a(hash, &buffer[..read]);
+    }
+}
@@ -0,0 +1,26 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::i18n::{t, tf};
+
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+
+/// Ctrl+Cで中断した場合の終了コード
+pub const EXIT_INTERRUPTED: i32 = 130;
+
+/// Ctrl+Cで中断フラグを立てるハンドラーを登録する。処理中のファイルは最後まで書き込む
+pub fn install_handler() {
+    let result = ctrlc::set_handler(|| {
+        if CANCELLED.swap(true, Ordering::SeqCst) {
+            // 2回目のCtrl+Cは待たずに終了する
+            std::process::exit(EXIT_INTERRUPTED);
+        }
+        eprintln!("\n{}", t("cancel.waiting"));
+    });
+    if let Err(e) = result {
+        eprintln!("{}", tf("cancel.handler_failed", &[&e]));
+    }
+}
+
+/// 中断が要求されたか
+pub fn is_cancelled() -> bool {
+    CANCELLED.load(Ordering::SeqCst)
+}
@@ -0,0 +1,74 @@
+use crate::i18n::{t, tf};
+
+/// 値の括弧の対応と引用符の数を検査する
+/// placeholder_awareがtrueなら `{0}` 形式のプレースホルダーと `'{'` `'}'` のエスケープを対象外にする
+pub fn check(value: &str, placeholder_aware: bool) -> Vec<String> {
+    let chars: Vec<char> = value.chars().collect();
+    let mut messages = Vec::new();
+    let mut stack: Vec<char> = Vec::new();
+    let mut double_quotes = 0;
+    let mut single_quotes = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if placeholder_aware {
+            if let Some(len) = placeholder_len(&chars[i..]) {
+                i += len;
+                continue;
+            }
+        }
+        match c {
+            '(' | '[' | '{' => stack.push(c),
+            ')' | ']' | '}' => {
+                let open = opening_of(c);
+                match stack.pop() {
+                    Some(top) if top == open => {}
+                    Some(top) => messages.push(tf("lint.bracket.mismatch", &[&c, &top])),
+                    None => messages.push(tf("lint.bracket.unopened", &[&c])),
+                }
+            }
+            '"' => double_quotes += 1,
+            '\'' => {
+                // 単語の途中のアポストロフィ(don't など)は引用符とみなさない
+                let between_letters = i > 0
+                    && chars[i - 1].is_alphanumeric()
+                    && chars.get(i + 1).is_some_and(|n| n.is_alphanumeric());
+                if !between_letters {
+                    single_quotes += 1;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    for open in stack {
+        messages.push(tf("lint.bracket.unclosed", &[&open]));
+    }
+    if double_quotes % 2 != 0 {
+        messages.push(t("lint.bracket.double_quotes"));
+    }
+    if single_quotes % 2 != 0 {
+        messages.push(t("lint.bracket.single_quotes"));
+    }
+    messages
+}
+
+fn opening_of(close: char) -> char {
+    match close {
+        ')' => '(',
+        ']' => '[',
+        _ => '{',
+    }
+}
+
+/// 先頭がプレースホルダー(`{0}`)またはエスケープ(`'{'`, `'}'`)ならその文字数を返す
+fn placeholder_len(chars: &[char]) -> Option<usize> {
+    match chars {
+        ['\'', '{' | '}', '\'', ..] => Some(3),
+        ['{', rest @ ..] => {
+            let digits = rest.iter().take_while(|c| c.is_ascii_digit()).count();
+            (digits > 0 && rest.get(digits) == Some(&'}')).then_some(digits + 2)
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,48 @@
+//! 値がキーそのもの(`block.minecraft.stone=block.minecraft.stone`)になっているエントリの検査
+//!
+//! 空ではないため翻訳済みに見えるが、ゲーム内では生のキーとして表示される。
+//! 指定すればキーの最後の区切り(`stone`)と同じ値も対象にする
+
+use crate::i18n::{t, tf};
+
+/// 値がキーの繰り返しであれば理由を返す
+pub fn check(key: &str, value: &str, last_segment: bool) -> Option<String> {
+    if key.is_empty() {
+        return None;
+    }
+    if value == key {
+        return Some(t("lint.echo.key"));
+    }
+    let segment = key.rsplit('.').next().unwrap_or(key);
+    if last_segment && segment != key && !segment.is_empty() && value == segment {
+        return Some(tf("lint.echo.segment", &[&segment]));
+    }
+    None
+}
+
+/// 値がキーの繰り返しになっているか
+pub fn is_echo(key: &str, value: &str, last_segment: bool) -> bool {
+    check(key, value, last_segment).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn value_equal_to_key_is_an_echo() {
+        assert!(is_echo("block.minecraft.stone", "block.minecraft.stone", false));
+        assert!(!is_echo("block.minecraft.stone", "Stone", false));
+        assert!(!is_echo("", "", false));
+    }
+
+    #[test]
+    fn last_segment_is_checked_only_when_asked() {
+        assert!(!is_echo("block.minecraft.stone", "stone", false));
+        let reason = check("block.minecraft.stone", "stone", true).unwrap();
+        assert!(reason.contains("`stone`"));
+        // 区切りのないキーはキー全体と同じ場合だけ
+        assert!(!is_echo("stone", "Stone", true));
+        assert!(!is_echo("block.", "", true));
+    }
+}
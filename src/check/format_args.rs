@@ -0,0 +1,120 @@
+//! Java版1.13以降の書式指定子(`%s`, `%1$s`)の検査
+//!
+//! 次の値は表示するときに例外になるか、意図しない引数が表示される:
+//! - 番号なし(`%s`)と番号付き(`%1$s`)が1つの値に混ざっている
+//! - 同じ番号が異なる変換文字で使われている(`%1$s` と `%1$d`)
+//! - 番号が飛んでいる(`%1$s` と `%3$s` があり `%2$s` が無い)、または `%0$s` がある
+//!
+//! `%%` と `%n` は引数を使わないため対象外にする
+
+use std::collections::BTreeMap;
+use crate::i18n::{t, tf};
+
+/// 1つの書式指定子
+struct Spec {
+    /// `N$` の番号(番号なしならNone)
+    ordinal: Option<usize>,
+    conversion: char,
+}
+
+/// 値の中の書式指定子を読み取る。ゲームと同じく `%[N$]変換文字` の形式だけを書式指定子とみなし、それ以外の `%` は無視する
+fn parse_specs(value: &str) -> Vec<Spec> {
+    let chars: Vec<char> = value.chars().collect();
+    let mut specs = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] != '%' {
+            i += 1;
+            continue;
+        }
+        let mut j = i + 1;
+        let digits_start = j;
+        while chars.get(j).is_some_and(|c| c.is_ascii_digit()) {
+            j += 1;
+        }
+        let ordinal = if j > digits_start && chars.get(j) == Some(&'$') {
+            let number: String = chars[digits_start..j].iter().collect();
+            j += 1;
+            number.parse().ok()
+        } else {
+            j = digits_start;
+            None
+        };
+        match chars.get(j) {
+            Some('%') | Some('n') if j == i + 1 => i = j + 1,
+            Some(c) if c.is_ascii_alphabetic() => {
+                specs.push(Spec { ordinal, conversion: *c });
+                i = j + 1;
+            }
+            _ => i += 1,
+        }
+    }
+    specs
+}
+
+/// 値の書式指定子を検査し、問題ごとのメッセージを返す
+pub fn check(value: &str) -> Vec<String> {
+    let specs = parse_specs(value);
+    let mut messages = Vec::new();
+    let ordered: Vec<&Spec> = specs.iter().filter(|spec| spec.ordinal.is_some()).collect();
+    if !ordered.is_empty() && ordered.len() < specs.len() {
+        messages.push(t("lint.format_args.mixed"));
+    }
+    let mut conversions: BTreeMap<usize, Vec<char>> = BTreeMap::new();
+    for spec in &ordered {
+        let used = conversions.entry(spec.ordinal.unwrap_or_default()).or_default();
+        if !used.contains(&spec.conversion) {
+            used.push(spec.conversion);
+        }
+    }
+    for (ordinal, used) in &conversions {
+        if used.len() > 1 {
+            let specs: Vec<String> = used.iter().map(|conversion| format!("%{}${}", ordinal, conversion)).collect();
+            messages.push(tf("lint.format_args.conflicting", &[ordinal, &specs.join(", ")]));
+        }
+    }
+    if conversions.contains_key(&0) {
+        messages.push(t("lint.format_args.zero"));
+    }
+    if let Some(&max) = conversions.keys().next_back() {
+        let missing: Vec<String> = (1..max).filter(|ordinal| !conversions.contains_key(ordinal)).map(|ordinal| format!("%{}$", ordinal)).collect();
+        if !missing.is_empty() {
+            messages.push(tf("lint.format_args.gap", &[&missing.join(", ")]));
+        }
+    }
+    messages
+}
+
+/// ゲームが解釈できない `%` の並びを返す。ゲームは `%%`、`%s`、`%N$s` だけを受け付け、
+/// それ以外の `%`(`%d` や末尾の `%` など)があると翻訳文を表示できない
+pub fn unsupported(value: &str) -> Vec<String> {
+    let chars: Vec<char> = value.chars().collect();
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] != '%' {
+            i += 1;
+            continue;
+        }
+        let mut j = i + 1;
+        while chars.get(j).is_some_and(|c| c.is_ascii_digit()) {
+            j += 1;
+        }
+        let len = match (chars.get(j), chars.get(j + 1)) {
+            (Some('%'), _) if j == i + 1 => Some(2),
+            (Some('s'), _) if j == i + 1 => Some(2),
+            (Some('$'), Some('s')) if j > i + 1 => Some(j + 2 - i),
+            _ => None,
+        };
+        match len {
+            Some(len) => i += len,
+            None => {
+                // 問題の箇所が分かるよう、`%` から次の文字までを示す
+                let end = (j + 1).min(chars.len());
+                found.push(chars[i..end].iter().collect());
+                i = end;
+            }
+        }
+    }
+    found
+}
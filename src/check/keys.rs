@@ -0,0 +1,140 @@
+//! ゲームが参照できない不審なキーの検査(lint、convertの--reject-suspicious-keys)
+//!
+//! 空白・`=`・`:`・引用符を含むキーは、値がキーの側に混ざった壊れたファイルであることが多い。
+//! それ以外も `[A-Za-z0-9._-]` と許可した文字(--allow-key-chars)以外を含むキーを報告する
+
+use std::fs;
+use indexmap::IndexMap;
+use crate::format::{parse_entry_with, Format, ReadOptions};
+use crate::i18n::{t, tf};
+
+/// キーに使ってよい文字か
+fn is_allowed(c: char, allowed: &str) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') || allowed.contains(c)
+}
+
+/// キーが不審であれば理由を返す
+pub fn check(key: &str, allowed: &str) -> Option<String> {
+    if key.chars().any(|c| c.is_whitespace() && !allowed.contains(c)) {
+        return Some(t("lint.suspicious.whitespace"));
+    }
+    if let Some(c) = key.chars().find(|c| matches!(c, '=' | ':') && !allowed.contains(*c)) {
+        return Some(tf("lint.suspicious.separator", &[&c]));
+    }
+    if key.chars().any(|c| matches!(c, '"' | '\'') && !allowed.contains(c)) {
+        return Some(t("lint.suspicious.quote"));
+    }
+    let mut others: Vec<char> = Vec::new();
+    for c in key.chars().filter(|c| !is_allowed(*c, allowed)) {
+        if !others.contains(&c) {
+            others.push(c);
+        }
+    }
+    if others.is_empty() {
+        return None;
+    }
+    let listed: Vec<String> = others.iter().map(|c| format!("{:?} (U+{:04X})", c, *c as u32)).collect();
+    Some(tf("lint.suspicious.other", &[&listed.join(", ")]))
+}
+
+/// キーが最初に現れる行(1から数える)。JSONは `"キー":` を含む行を探す
+fn key_lines(path: &str, format: Format, read: &ReadOptions) -> IndexMap<String, usize> {
+    let mut lines = IndexMap::new();
+    let Ok(contents) = fs::read_to_string(path) else {
+        return lines;
+    };
+    for (index, line) in contents.lines().enumerate() {
+        match format {
+            Format::Lang => {
+                if let Some((key, _)) = parse_entry_with(line, read) {
+                    lines.entry(key.to_string()).or_insert(index + 1);
+                }
+            }
+            Format::Json => {
+                let mut rest = line;
+                while let Some(start) = rest.find('"') {
+                    let Some(end) = json_string_end(&rest[start..]) else {
+                        break;
+                    };
+                    let (string, after) = rest[start..].split_at(end);
+                    if after.trim_start().starts_with(':') {
+                        if let Ok(key) = serde_json::from_str::<String>(string) {
+                            lines.entry(key).or_insert(index + 1);
+                        }
+                    }
+                    rest = after;
+                }
+            }
+        }
+    }
+    lines
+}
+
+/// `"` で始まるテキストの、閉じる `"` の直後の位置。閉じていなければNone
+fn json_string_end(text: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (index, c) in text.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return Some(index + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// マップの不審なキーを `キー (N行目): 理由` の形で返す
+/// pathがNoneなら(ストリーミングで読んだ大きなファイルでは全体を読み直さないよう)行番号を探さない
+pub fn find(lang_map: &IndexMap<String, String>, allowed: &str, path: Option<&str>, format: Format, read: &ReadOptions) -> Vec<String> {
+    let found: Vec<(&String, String)> = lang_map.keys().filter_map(|key| check(key, allowed).map(|reason| (key, reason))).collect();
+    if found.is_empty() {
+        return Vec::new();
+    }
+    let lines = path.map(|path| key_lines(path, format, read)).unwrap_or_default();
+    found
+        .into_iter()
+        .map(|(key, reason)| match lines.get(key) {
+            Some(line) => tf("lint.suspicious.at_line", &[&format!("{:?}", key), line, &reason]),
+            None => format!("{:?}: {}", key, reason),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_accepts_plain_keys_and_allowed_characters() {
+        assert_eq!(check("tile.stone_brick-name.0", ""), None);
+        assert_eq!(check("item.name+", "+"), None);
+        assert_eq!(check("item name", " "), None);
+    }
+
+    #[test]
+    fn check_reports_each_kind_of_suspicious_key() {
+        assert!(check("item name", "").is_some());
+        assert!(check("item.name=Stone", "").unwrap().contains('='));
+        assert!(check("item:name", "").unwrap().contains(':'));
+        assert!(check("\"item\"", "").is_some());
+        let other = check("item+name+", "").unwrap();
+        assert!(other.contains("U+002B"));
+        assert_eq!(other.matches("U+002B").count(), 1);
+    }
+
+    #[test]
+    fn find_adds_lines_only_when_a_path_is_given() {
+        let path = std::env::temp_dir().join(format!("json_lang_keys_test_{}.lang", std::process::id()));
+        fs::write(&path, "ok=1\nbad key=2\n").unwrap();
+        let lang_map: IndexMap<String, String> = [("ok", "1"), ("bad key", "2")].iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        let read = ReadOptions::default();
+        let with_lines = find(&lang_map, "", path.to_str(), Format::Lang, &read);
+        assert_eq!(with_lines.len(), 1);
+        assert!(with_lines[0].starts_with("\"bad key\"") && with_lines[0].contains('2'));
+        let without_lines = find(&lang_map, "", None, Format::Lang, &read);
+        assert_eq!(without_lines.len(), 1);
+        assert!(!without_lines[0].contains('('));
+        fs::remove_file(&path).unwrap();
+    }
+}
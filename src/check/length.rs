@@ -0,0 +1,56 @@
+use crate::config::LintConfig;
+use crate::i18n::tf;
+use crate::transform::placeholder::strip_placeholders;
+
+/// 値の最大文字数の設定
+#[derive(Default)]
+pub struct LengthLimits {
+    pub default: Option<usize>,
+    pub prefixes: Vec<(String, usize)>,
+    pub exclude_placeholders: bool,
+}
+
+impl LengthLimits {
+    /// 設定ファイルの値に、コマンドラインで指定された既定の上限を優先して組み合わせる
+    pub fn new(config: &LintConfig, max_value_length: Option<usize>, exclude_placeholders: bool) -> LengthLimits {
+        LengthLimits {
+            default: max_value_length.or(config.max_value_length),
+            prefixes: config.max_value_length_prefixes.iter().map(|(p, n)| (p.clone(), *n)).collect(),
+            exclude_placeholders: exclude_placeholders || config.exclude_placeholders,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.default.is_some() || !self.prefixes.is_empty()
+    }
+
+    /// キーに適用される上限。最も長く一致する接頭辞の設定を優先する
+    pub fn limit_for(&self, key: &str) -> Option<usize> {
+        self.prefixes
+            .iter()
+            .filter(|(prefix, _)| key.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, limit)| *limit)
+            .or(self.default)
+    }
+
+    /// 値の文字数(バイト数ではない)
+    pub fn length_of(&self, value: &str) -> usize {
+        if self.exclude_placeholders {
+            strip_placeholders(value).chars().count()
+        } else {
+            value.chars().count()
+        }
+    }
+}
+
+/// 上限を超えていれば(文字数, 上限)を返す
+pub fn check(key: &str, value: &str, limits: &LengthLimits) -> Option<(usize, usize)> {
+    let limit = limits.limit_for(key)?;
+    let length = limits.length_of(value);
+    (length > limit).then_some((length, limit))
+}
+
+pub fn message(length: usize, limit: usize) -> String {
+    tf("lint.length", &[&length, &limit])
+}
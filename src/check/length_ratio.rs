@@ -0,0 +1,143 @@
+//! 翻訳元と訳の値の文字数の比較(length-ratioサブコマンド)
+//!
+//! 訳が翻訳元の--max-ratio倍(既定は2.5倍)を超えるか、--maxの文字数を超える値を、倍率の大きい順に報告する。
+//! 文字数はプレースホルダーを除いて数える
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use serde::Serialize;
+use unicode_width::UnicodeWidthStr;
+use crate::cli::LengthRatioArgs;
+use crate::convert::files_or_input_dir;
+use crate::format::load_by_extension;
+use crate::i18n::{t, tf};
+use crate::placeholders::ReportFormat;
+use crate::remote;
+use crate::report::{csv_field, pad};
+use crate::transform::placeholder::strip_placeholders;
+
+/// 報告する値
+#[derive(Serialize)]
+struct Overflow {
+    file: String,
+    key: String,
+    source_length: usize,
+    target_length: usize,
+    /// 翻訳元が空(プレースホルダーだけを含む場合も)ならNone
+    ratio: Option<f64>,
+}
+
+impl Overflow {
+    fn ratio_text(&self) -> String {
+        self.ratio.map(|ratio| format!("{:.2}", ratio)).unwrap_or_else(|| "-".to_string())
+    }
+}
+
+/// プレースホルダーを除いた文字数
+fn length_of(value: &str) -> usize {
+    strip_placeholders(value).chars().count()
+}
+
+fn table(overflows: &[Overflow]) -> String {
+    let header = [t("table.file"), t("length_ratio.key"), t("length_ratio.source"), t("length_ratio.target"), t("length_ratio.ratio")];
+    let rows: Vec<[String; 5]> = overflows
+        .iter()
+        .map(|o| [o.file.clone(), o.key.clone(), o.source_length.to_string(), o.target_length.to_string(), o.ratio_text()])
+        .collect();
+    let widths: Vec<usize> = (0..header.len()).map(|column| rows.iter().map(|row| row[column].width()).chain([header[column].width()]).max().unwrap_or(0)).collect();
+    let line = |cells: Vec<&str>| cells.iter().zip(&widths).map(|(cell, width)| pad(cell, *width)).collect::<Vec<_>>().join("  ").trim_end().to_string();
+    let mut out = String::new();
+    let _ = writeln!(out, "{}", line(header.iter().map(String::as_str).collect()));
+    for row in &rows {
+        let _ = writeln!(out, "{}", line(row.iter().map(String::as_str).collect()));
+    }
+    let _ = writeln!(out, "\n{}", tf("length_ratio.total", &[&overflows.len()]));
+    out
+}
+
+fn csv(overflows: &[Overflow]) -> String {
+    let mut out = String::from("file,key,source_length,target_length,ratio\n");
+    for o in overflows {
+        let ratio = o.ratio.map(|ratio| format!("{:.2}", ratio)).unwrap_or_default();
+        let _ = writeln!(out, "{},{},{},{},{}", csv_field(&o.file), csv_field(&o.key), o.source_length, o.target_length, ratio);
+    }
+    out
+}
+
+/// length-ratioサブコマンドの実行。報告する値があればOk(false)を返す
+pub fn run_length_ratio(args: &LengthRatioArgs) -> Result<bool, String> {
+    if args.max_ratio <= 0.0 {
+        return Err(tf("length_ratio.invalid_ratio", &[&args.max_ratio]));
+    }
+    let source_path = remote::resolve(&args.source)?;
+    let source = load_by_extension(&source_path)?;
+    let source_canonical = Path::new(&source_path).canonicalize().ok();
+    let mut paths = files_or_input_dir(&args.files, &args.input)?;
+    paths.retain(|path| path.canonicalize().ok() != source_canonical);
+
+    let mut overflows = Vec::new();
+    for path in &paths {
+        let path_str = path.to_string_lossy().to_string();
+        let lang_map = load_by_extension(&path_str)?;
+        for (key, value) in &lang_map {
+            let Some(source_value) = source.get(key) else {
+                continue;
+            };
+            let source_length = length_of(source_value);
+            let target_length = length_of(value);
+            let ratio = (source_length > 0).then(|| target_length as f64 / source_length as f64);
+            let over_ratio = ratio.is_some_and(|ratio| ratio > args.max_ratio);
+            let over_cap = args.max.is_some_and(|max| target_length > max);
+            if over_ratio || over_cap {
+                overflows.push(Overflow { file: path_str.clone(), key: key.clone(), source_length, target_length, ratio });
+            }
+        }
+    }
+    // 倍率の大きい順。翻訳元が空で倍率の無いものは最後に、訳の文字数の多い順に並べる
+    overflows.sort_by(|a, b| match (a.ratio, b.ratio) {
+        (Some(x), Some(y)) => y.total_cmp(&x),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => b.target_length.cmp(&a.target_length),
+    });
+
+    let contents = match args.format {
+        ReportFormat::Table => table(&overflows),
+        ReportFormat::Csv => csv(&overflows),
+        ReportFormat::Json => serde_json::to_string_pretty(&overflows).map_err(|e| e.to_string())? + "\n",
+    };
+    match &args.output {
+        Some(path) => {
+            fs::write(path, contents).map_err(|_| tf("io.write_failed", &[path]))?;
+            println!("{}", tf("length_ratio.written", &[path, &overflows.len()]));
+        }
+        None => print!("{}", contents),
+    }
+    Ok(overflows.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overflow(key: &str, ratio: Option<f64>) -> Overflow {
+        Overflow { file: "ja_jp.json".to_string(), key: key.to_string(), source_length: 4, target_length: 12, ratio }
+    }
+
+    #[test]
+    fn length_of_ignores_placeholders() {
+        assert_eq!(length_of("Hello %s"), 6);
+        assert_eq!(length_of("%1$s と %2$s"), 3);
+        assert_eq!(length_of("%s"), 0);
+    }
+
+    #[test]
+    fn table_and_csv_list_each_overflow() {
+        let overflows = [overflow("a", Some(3.0)), overflow("b,c", None)];
+        let table = table(&overflows);
+        assert_eq!(table.lines().count(), 5);
+        assert!(table.contains("3.00") && table.contains(" -"));
+        assert_eq!(csv(&overflows), "file,key,source_length,target_length,ratio\nja_jp.json,a,4,12,3.00\nja_jp.json,\"b,c\",4,12,\n");
+    }
+}
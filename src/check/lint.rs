@@ -0,0 +1,127 @@
+use indexmap::IndexMap;
+use std::fs;
+use crate::check::{brackets, echo, format_args, keys, length, mojibake, tags, unicode};
+use crate::check::length::LengthLimits;
+use crate::cli::{LintArgs, ValueLengthArgs};
+use crate::config::Config;
+use crate::convert::files_or_input_dir;
+use crate::format::{load_by_extension, parse_json_map, Format, ReadOptions};
+use crate::i18n::{t, tf};
+
+/// lintで見つかった問題
+pub struct Finding {
+    pub key: String,
+    pub message: String,
+}
+
+/// lintの設定
+#[derive(Default)]
+pub struct LintOptions {
+    /// 括弧の検査で `{0}` 形式のプレースホルダーを対象外にする
+    pub placeholder_aware: bool,
+    /// 山括弧タグ(`<b>...</b>`)を検査する
+    pub tags: bool,
+    /// 許可するタグ名(空なら全て許可)
+    pub allowed_tags: Vec<String>,
+    /// 翻訳元のマップ。同じキーの値とタグが一致するか比較する
+    pub source: Option<IndexMap<String, String>>,
+    /// 値の最大文字数
+    pub lengths: LengthLimits,
+    /// キーの最後の区切りと同じ値もキーの繰り返しとして報告する
+    pub echo_last_segment: bool,
+}
+
+/// マップのすべてのエントリにlint規則を適用する
+pub fn lint_map(lang_map: &IndexMap<String, String>, options: &LintOptions) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for (key, value) in lang_map {
+        let mut messages = unicode::check(key, value);
+        messages.extend(mojibake::check(value));
+        messages.extend(brackets::check(value, options.placeholder_aware));
+        messages.extend(format_args::check(value));
+        messages.extend(echo::check(key, value, options.echo_last_segment));
+        if options.tags {
+            messages.extend(tags::check(value, &options.allowed_tags));
+            if let Some(source_value) = options.source.as_ref().and_then(|s| s.get(key)) {
+                messages.extend(tags::compare(source_value, value));
+            }
+        }
+        if let Some((len, limit)) = length::check(key, value, &options.lengths) {
+            messages.push(length::message(len, limit));
+        }
+        for message in messages {
+            findings.push(Finding { key: key.clone(), message });
+        }
+    }
+    findings
+}
+
+/// lintのためにファイルを読み込む。JSONの対になっていないサロゲートは読み込めないため、
+/// 行ごとに報告してからU+FFFDに置き換えて読み込む
+fn load_for_lint(format: Format, path: &str) -> Result<(IndexMap<String, String>, Vec<Finding>), String> {
+    if format == Format::Json {
+        let contents = fs::read_to_string(path).map_err(|_| tf("io.read_failed", &[&path]))?;
+        let (replaced, lone) = mojibake::replace_lone_surrogates(&contents);
+        if !lone.is_empty() {
+            let findings = lone
+                .into_iter()
+                .map(|(line, escape)| Finding { key: tf("lint.line", &[&(line + 1)]), message: tf("lint.lone_surrogate", &[&escape]) })
+                .collect();
+            let lang_map = parse_json_map(replaced.as_bytes()).map_err(|e| format!("{}: {}", path, e))?;
+            return Ok((lang_map, findings));
+        }
+    }
+    Ok((format.load(path)?, Vec::new()))
+}
+
+/// lintサブコマンドの実行。問題が見つかった場合はOk(false)を返す
+pub fn run_lint(args: &LintArgs, config: &Config) -> Result<bool, String> {
+    let options = LintOptions {
+        placeholder_aware: args.placeholder_aware,
+        tags: args.tags,
+        allowed_tags: args.allowed_tags.clone(),
+        source: args.source.as_deref().map(load_by_extension).transpose()?,
+        lengths: LengthLimits::new(&config.lint, args.max_value_length, args.exclude_placeholders),
+        echo_last_segment: args.echo_last_segment,
+    };
+    let mut clean = true;
+    for path in files_or_input_dir(&args.files, &args.input)? {
+        let path_str = path.to_string_lossy().to_string();
+        let format = Format::from_path(&path).ok_or_else(|| tf("io.unsupported_extension", &[&path_str]))?;
+        let (lang_map, mut findings) = load_for_lint(format, &path_str)?;
+        findings.extend(lint_map(&lang_map, &options));
+        let allowed = args.allow_key_chars.as_deref().unwrap_or(&config.lint.allowed_key_chars);
+        for message in keys::find(&lang_map, allowed, Some(&path_str), format, &ReadOptions::default()) {
+            findings.push(Finding { key: t("lint.key_label"), message });
+        }
+        println!("{}", tf("lint.file", &[&path_str, &findings.len()]));
+        for finding in &findings {
+            println!("  - {}: {}", finding.key, finding.message);
+        }
+        clean &= findings.is_empty();
+    }
+    Ok(clean)
+}
+
+/// value-lengthサブコマンドの実行。上限を超える値があればOk(false)を返す
+pub fn run_value_length(args: &ValueLengthArgs, config: &Config) -> Result<bool, String> {
+    let limits = LengthLimits::new(&config.lint, args.max, args.exclude_placeholders);
+    if !limits.is_enabled() {
+        return Err(t("lint.value_length.no_limit"));
+    }
+    let mut within = true;
+    for path in files_or_input_dir(&args.files, &args.input)? {
+        let path_str = path.to_string_lossy().to_string();
+        let lang_map = load_by_extension(&path_str)?;
+        let over: Vec<(&String, usize, usize)> = lang_map
+            .iter()
+            .filter_map(|(key, value)| length::check(key, value, &limits).map(|(len, limit)| (key, len, limit)))
+            .collect();
+        println!("{}", tf("lint.value_length.file", &[&path_str, &over.len()]));
+        for (key, len, limit) in &over {
+            println!("{}", tf("lint.value_length.item", &[key, len, limit]));
+        }
+        within &= over.is_empty();
+    }
+    Ok(within)
+}
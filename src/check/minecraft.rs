@@ -0,0 +1,137 @@
+//! JSONのlangファイルがゲームの前提を満たしているかの検査(validate-mcサブコマンド)
+//!
+//! 規則:
+//! - `json`: JSONとして読めること
+//! - `top-level`: トップレベルがオブジェクトであること
+//! - `value-type`: 値がすべて文字列であること
+//! - `duplicate-key`: 同じキーが2回現れないこと
+//! - `empty-key` / `key-whitespace`: キーが空でなく、空白文字を含まないこと
+//! - `format`: 値の `%` がゲームの解釈できる並び(`%%`、`%s`、`%N$s`)であること
+//! - `debug-suffix`: --duplicates-to-suffixで書き出した `key__dup1` のようなキーが無いこと(確認用の出力は配布しない)
+//! - `size`: ファイルが--max-sizeを超えないこと(ゲームに決まった上限は無いため、既定の4MiBはバニラのen_us.jsonの数倍を目安にしている)
+
+use std::collections::HashSet;
+use std::fs;
+use std::io::BufReader;
+use std::path::Path;
+use crate::check::format_args;
+use crate::cli::ValidateMcArgs;
+use crate::convert::files_or_input_dir;
+use crate::format::{read_json_entries, JsonValue, DUPLICATE_SUFFIX};
+use crate::i18n::{t, tf};
+use crate::size_guard;
+
+/// 見つかった違反
+struct Violation {
+    /// キーに関係しない違反ではNone
+    key: Option<String>,
+    rule: &'static str,
+    message: String,
+}
+
+impl Violation {
+    fn file(rule: &'static str, message: String) -> Violation {
+        Violation { key: None, rule, message }
+    }
+
+    fn key(key: &str, rule: &'static str, message: String) -> Violation {
+        Violation { key: Some(key.to_string()), rule, message }
+    }
+}
+
+/// `key__dup1` のように重複の接尾辞と番号で終わるキーか
+fn is_debug_suffixed(key: &str) -> bool {
+    key.rsplit_once(DUPLICATE_SUFFIX).is_some_and(|(base, number)| !base.is_empty() && !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// 1つのエントリを検査する
+fn check_entry(key: &str, value: &JsonValue, seen: &mut HashSet<String>, violations: &mut Vec<Violation>) {
+    if !seen.insert(key.to_string()) {
+        violations.push(Violation::key(key, "duplicate-key", t("validate_mc.duplicate_key")));
+    }
+    if key.is_empty() {
+        violations.push(Violation::key(key, "empty-key", t("validate_mc.empty_key")));
+    } else if key.chars().any(char::is_whitespace) {
+        violations.push(Violation::key(key, "key-whitespace", t("validate_mc.key_whitespace")));
+    }
+    if is_debug_suffixed(key) {
+        violations.push(Violation::key(key, "debug-suffix", t("validate_mc.debug_suffix")));
+    }
+    match value {
+        JsonValue::String(text) => {
+            for sequence in format_args::unsupported(text) {
+                violations.push(Violation::key(key, "format", tf("validate_mc.format", &[&sequence])));
+            }
+        }
+        other => violations.push(Violation::key(key, "value-type", tf("validate_mc.value_type", &[&other.type_name()]))),
+    }
+}
+
+/// 1ファイルを検査する
+fn validate_file(path: &Path, max_size: u64) -> Result<Vec<Violation>, String> {
+    let path_str = path.to_string_lossy();
+    let mut violations = Vec::new();
+    let size = fs::metadata(path).map_err(|_| tf("io.read_failed", &[&path_str]))?.len();
+    if size > max_size {
+        violations.push(Violation::file("size", tf("validate_mc.size", &[&size_guard::format_size(size), &size_guard::format_size(max_size)])));
+    }
+    let file = fs::File::open(path).map_err(|_| tf("io.read_failed", &[&path_str]))?;
+    let mut seen = HashSet::new();
+    match read_json_entries(BufReader::new(file), |key, value| check_entry(&key, &value, &mut seen, &mut violations)) {
+        Ok("object") => {}
+        Ok(top_level) => violations.push(Violation::file("top-level", tf("validate_mc.top_level", &[&top_level]))),
+        Err(e) => violations.push(Violation::file("json", tf("validate_mc.json", &[&e]))),
+    }
+    Ok(violations)
+}
+
+/// validate-mcサブコマンドの実行。違反があればOk(false)を返す
+pub fn run_validate_mc(args: &ValidateMcArgs) -> Result<bool, String> {
+    let mut paths = files_or_input_dir(&args.files, &args.input)?;
+    // ディレクトリを指定した場合はJSONだけを対象にする
+    if args.files.is_empty() {
+        paths.retain(|path| path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json")));
+    }
+    paths.sort();
+    let mut total = 0;
+    for path in &paths {
+        let violations = validate_file(path, args.max_size)?;
+        let path_str = path.to_string_lossy();
+        for violation in &violations {
+            match &violation.key {
+                Some(key) => println!("{}: {}: [{}] {}", path_str, key, violation.rule, violation.message),
+                None => println!("{}: [{}] {}", path_str, violation.rule, violation.message),
+            }
+        }
+        total += violations.len();
+    }
+    println!("{}", tf("validate_mc.summary", &[&paths.len(), &total]));
+    Ok(total == 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(contents: &str) -> Vec<&'static str> {
+        let path = std::env::temp_dir().join(format!("json_lang_validate_mc_test_{}_{}.json", std::process::id(), contents.len()));
+        fs::write(&path, contents).unwrap();
+        let violations = validate_file(&path, size_guard::DEFAULT_MAX_FILE_SIZE).unwrap();
+        fs::remove_file(&path).unwrap();
+        violations.iter().map(|violation| violation.rule).collect()
+    }
+
+    #[test]
+    fn duplicates_to_suffix_keys_are_rejected() {
+        assert_eq!(rules(r#"{"tile.stone.name":"Stone","tile.stone.name__dup1":"Rock"}"#), ["debug-suffix"]);
+        assert!(is_debug_suffixed("a__dup12"));
+        assert!(!is_debug_suffixed("__dup1"));
+        assert!(!is_debug_suffixed("a__dup"));
+        assert!(!is_debug_suffixed("a__dupx"));
+    }
+
+    #[test]
+    fn minified_output_passes() {
+        assert!(rules(r#"{"a":"A %s","b":"B"}"#).is_empty());
+    }
+}
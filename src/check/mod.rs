@@ -0,0 +1,13 @@
+pub mod brackets;
+pub mod echo;
+pub mod format_args;
+pub mod keys;
+pub mod length;
+pub mod length_ratio;
+pub mod lint;
+pub mod minecraft;
+pub mod mojibake;
+pub mod reference;
+pub mod tags;
+pub mod trimmed;
+pub mod unicode;
@@ -0,0 +1,122 @@
+//! 文字化けの検出と修正
+//!
+//! UTF-8のバイト列をWindows-1252(Latin-1)として読んで保存し直した文字列(`é` が `Ã©`、`’` が `â€™` になる)を見つけ、
+//! 各文字をバイトに戻してUTF-8として読み直せる並びだけを元に戻す。
+//! JSONの `\uD83D` のような対になっていないサロゲートは文字列として読み込めないため、テキストのまま探す
+
+use std::borrow::Cow;
+use crate::i18n::tf;
+
+/// Windows-1252で0x80〜0x9Fに割り当てられた文字(未定義の位置はLatin-1の制御文字)
+const CP1252_HIGH: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8D}', 'Ž', '\u{8F}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9D}', 'ž', 'Ÿ',
+];
+
+/// Windows-1252で1バイトに戻せる0x80以上の文字のバイト
+fn to_byte(c: char) -> Option<u8> {
+    match c as u32 {
+        0x80..=0xFF => Some(c as u8),
+        _ => CP1252_HIGH.iter().position(|high| *high == c).map(|index| 0x80 + index as u8),
+    }
+}
+
+/// 先頭の数文字をバイトに戻し、UTF-8の1文字として読めればその文字と使った文字数を返す
+fn decode_at(chars: &[char]) -> Option<(char, usize)> {
+    let len = match to_byte(*chars.first()?)? {
+        0xC2..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF4 => 4,
+        _ => return None,
+    };
+    let bytes: Vec<u8> = chars.get(..len)?.iter().map(|c| to_byte(*c)).collect::<Option<_>>()?;
+    let decoded = std::str::from_utf8(&bytes).ok()?;
+    decoded.chars().next().map(|c| (c, len))
+}
+
+/// 文字化けした並びを元に戻した文字列と、見つかった並び(出現順、重複なし)
+fn reverse(text: &str) -> (String, Vec<String>) {
+    let chars: Vec<char> = text.chars().collect();
+    let mut fixed = String::with_capacity(text.len());
+    let mut artifacts: Vec<String> = Vec::new();
+    let mut index = 0;
+    while index < chars.len() {
+        if let Some((decoded, used)) = decode_at(&chars[index..]) {
+            let artifact: String = chars[index..index + used].iter().collect();
+            if !artifacts.contains(&artifact) {
+                artifacts.push(artifact);
+            }
+            fixed.push(decoded);
+            index += used;
+        } else {
+            fixed.push(chars[index]);
+            index += 1;
+        }
+    }
+    (fixed, artifacts)
+}
+
+/// 二重にエンコードされた並びがあれば元に戻した文字列を返す
+pub fn fix_double_encoding(text: &str) -> Option<String> {
+    let (fixed, artifacts) = reverse(text);
+    (!artifacts.is_empty()).then_some(fixed)
+}
+
+/// 値に含まれる文字化けのメッセージ(置換文字U+FFFDはunicode::checkが報告する)
+pub fn check(value: &str) -> Vec<String> {
+    let (fixed, artifacts) = reverse(value);
+    if artifacts.is_empty() {
+        return Vec::new();
+    }
+    vec![tf("lint.mojibake", &[&artifacts.join(", "), &fixed])]
+}
+
+/// JSONのテキストの `\uXXXX` のうち対になっていないサロゲートを `�` に置き換える。
+/// 見つかった行(0始まり)とエスケープの一覧も返す
+pub fn replace_lone_surrogates(json: &str) -> (Cow<'_, str>, Vec<(usize, String)>) {
+    let bytes = json.as_bytes();
+    let surrogate_at = |at: usize| -> Option<u16> {
+        let hex = json.get(at + 2..at + 6)?;
+        let unit = u16::from_str_radix(hex, 16).ok()?;
+        (bytes[at] == b'\\' && bytes[at + 1] == b'u' && (0xD800..=0xDFFF).contains(&unit)).then_some(unit)
+    };
+    let mut lone = Vec::new();
+    let mut line = 0;
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'\n' => line += 1,
+            b'\\' => {
+                match surrogate_at(index) {
+                    Some(0xD800..=0xDBFF) if surrogate_at(index + 6).is_some_and(|low| low >= 0xDC00) => {
+                        index += 12;
+                        continue;
+                    }
+                    Some(_) => {
+                        lone.push((index, line));
+                        index += 6;
+                        continue;
+                    }
+                    // `\\` などの2文字のエスケープは続く文字ごと読み飛ばす
+                    None => index += 1,
+                }
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+    if lone.is_empty() {
+        return (Cow::Borrowed(json), Vec::new());
+    }
+    let mut replaced = String::with_capacity(json.len());
+    let mut last = 0;
+    let mut found = Vec::new();
+    for (at, line) in lone {
+        replaced.push_str(&json[last..at]);
+        replaced.push_str("\\uFFFD");
+        found.push((line, json[at..at + 6].to_string()));
+        last = at + 6;
+    }
+    replaced.push_str(&json[last..]);
+    (Cow::Owned(replaced), found)
+}
@@ -0,0 +1,65 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use indexmap::IndexSet;
+use crate::cli::ValidateKeysArgs;
+use crate::convert::files_or_input_dir;
+use crate::format::Format;
+use crate::i18n::tf;
+use json_lang::lang_map::LangMap;
+use crate::remote;
+
+/// 参照キー一覧を読み込む。.lang/.jsonならそのキーを、それ以外は1行1キーのテキストとして扱う
+pub fn load_reference_keys(path: &str) -> Result<IndexSet<String>, String> {
+    let path = &remote::resolve(path)?;
+    if let Some(format) = Format::from_path(Path::new(path)) {
+        return Ok(format.load(path)?.into_keys().collect());
+    }
+    let contents = fs::read_to_string(path).map_err(|_| tf("io.read_failed", &[path]))?;
+    Ok(contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
+
+/// 参照に無いキー(unknown)と、参照にあってファイルに無いキー(missing)
+pub struct KeyReport {
+    pub unknown: Vec<String>,
+    pub missing: Vec<String>,
+}
+
+/// 除外する接頭辞を持たないキーだけを参照と比較する
+pub fn compare_keys(lang_map: &LangMap, reference: &IndexSet<String>, exclude_prefixes: &[String]) -> KeyReport {
+    let excluded: HashSet<&String> = exclude_prefixes.iter().flat_map(|p| lang_map.keys_with_prefix(p)).collect();
+    let checked = |key: &String| !exclude_prefixes.iter().any(|p| key.starts_with(p.as_str()));
+    let unknown = lang_map.keys().filter(|k| !excluded.contains(k) && !reference.contains(*k)).cloned().collect();
+    let missing = reference.iter().filter(|k| checked(k) && !lang_map.contains_key(*k)).cloned().collect();
+    KeyReport { unknown, missing }
+}
+
+/// validate-keysサブコマンドの実行。参照に無いキーがあればOk(false)を返す
+pub fn run_validate_keys(args: &ValidateKeysArgs) -> Result<bool, String> {
+    let reference = load_reference_keys(&args.reference)?;
+    let mut all_known = true;
+    for path in files_or_input_dir(&args.files, &args.input)? {
+        let path_str = path.to_string_lossy().to_string();
+        let format = Format::from_path(&path).ok_or_else(|| tf("io.unsupported_extension", &[&path_str]))?;
+        let lang_map = LangMap::from(format.load(&path_str)?);
+        let report = compare_keys(&lang_map, &reference, &args.exclude_prefix);
+
+        println!("{}", tf("lint.reference.unknown", &[&path_str, &report.unknown.len()]));
+        for key in &report.unknown {
+            println!("  - {}", key);
+        }
+        if args.missing {
+            println!("{}", tf("lint.reference.missing", &[&path_str, &report.missing.len()]));
+            for key in &report.missing {
+                println!("  - {}", key);
+            }
+        }
+        all_known &= report.unknown.is_empty();
+    }
+    Ok(all_known)
+}
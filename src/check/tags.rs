@@ -0,0 +1,102 @@
+use std::collections::BTreeMap;
+use crate::i18n::{t, tf};
+
+/// 値の中の山括弧タグ
+#[derive(PartialEq, Eq)]
+pub enum Tag {
+    Open(String),
+    Close(String),
+    SelfClosing(String),
+}
+
+impl Tag {
+    fn name(&self) -> &str {
+        match self {
+            Tag::Open(name) | Tag::Close(name) | Tag::SelfClosing(name) => name,
+        }
+    }
+
+    fn display(&self) -> String {
+        match self {
+            Tag::Open(name) => format!("<{}>", name),
+            Tag::Close(name) => format!("</{}>", name),
+            Tag::SelfClosing(name) => format!("<{}/>", name),
+        }
+    }
+}
+
+/// 値からタグを出現順に取り出す。タグ名として読めない `<` (例: `a < b`)は無視する
+pub fn tokenize(value: &str) -> Vec<Tag> {
+    let mut tags = Vec::new();
+    let mut rest = value;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let Some(end) = rest.find('>') else { break };
+        let inner = &rest[..end];
+        let (closing, body) = match inner.strip_prefix('/') {
+            Some(body) => (true, body),
+            None => (false, inner),
+        };
+        let self_closing = !closing && body.ends_with('/');
+        let name: String = body.chars().take_while(|c| c.is_ascii_alphanumeric() || *c == '-').collect();
+        if name.is_empty() || !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            continue;
+        }
+        let name = name.to_ascii_lowercase();
+        tags.push(if closing {
+            Tag::Close(name)
+        } else if self_closing {
+            Tag::SelfClosing(name)
+        } else {
+            Tag::Open(name)
+        });
+        rest = &rest[end + 1..];
+    }
+    tags
+}
+
+/// 閉じていないタグ・対応しないタグ・許可されていないタグを検査する(allowedが空なら全て許可)
+pub fn check(value: &str, allowed: &[String]) -> Vec<String> {
+    let mut messages = Vec::new();
+    let mut stack: Vec<String> = Vec::new();
+    for tag in tokenize(value) {
+        if !allowed.is_empty() && !allowed.iter().any(|a| a.eq_ignore_ascii_case(tag.name())) {
+            messages.push(tf("lint.tag.disallowed", &[&tag.display()]));
+        }
+        match tag {
+            Tag::Open(name) => stack.push(name),
+            Tag::Close(name) => match stack.pop() {
+                Some(open) if open == name => {}
+                Some(open) => messages.push(tf("lint.tag.mismatch", &[&name, &open])),
+                None => messages.push(tf("lint.tag.unopened", &[&name])),
+            },
+            Tag::SelfClosing(_) => {}
+        }
+    }
+    for open in stack {
+        messages.push(tf("lint.tag.unclosed", &[&open]));
+    }
+    messages
+}
+
+fn multiset(value: &str) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for tag in tokenize(value) {
+        *counts.entry(tag.display()).or_insert(0) += 1;
+    }
+    counts
+}
+
+fn describe(counts: &BTreeMap<String, usize>) -> String {
+    if counts.is_empty() {
+        return t("lint.tag.none");
+    }
+    counts.iter().map(|(tag, n)| format!("{}×{}", tag, n)).collect::<Vec<_>>().join(" ")
+}
+
+/// 翻訳元と翻訳でタグの種類と数が一致するか検査する
+pub fn compare(source: &str, translation: &str) -> Option<String> {
+    let expected = multiset(source);
+    let actual = multiset(translation);
+    (expected != actual).then(|| tf("lint.tag.differs", &[&describe(&expected), &describe(&actual)]))
+}
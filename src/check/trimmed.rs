@@ -0,0 +1,64 @@
+//! .langの読み込みで値が変わった行の報告(--trim-report)
+//!
+//! 値の前後の空白は読み込み時に取り除かれ、行末コメントや値の途中の `\r` も取り除かれるため、
+//! エラーにならないままゲームに表示される文字列が変わることがある。空白は `·`(スペース)・`→`(タブ)などで見えるようにする
+
+use std::fs;
+use crate::format::{parse_entry_with, ReadOptions};
+use crate::i18n::tf;
+
+/// 空白と制御文字を見える記号に置き換える
+pub fn visible(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            ' ' => "·".to_string(),
+            '\t' => "→".to_string(),
+            '\r' => "␍".to_string(),
+            c if c.is_whitespace() || c.is_control() => format!("[U+{:04X}]", c as u32),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+/// 読み込んだ値が区切り文字から行末までのテキストと異なる行を `キー (N行目): "元" => "読み込んだ値"` の形で返す。
+/// ファイル全体をもう一度読むため、ストリーミングで読む大きなファイルには使わない
+pub fn find(path: &str, read: &ReadOptions) -> Vec<String> {
+    let Ok(contents) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    let mut found = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let Some((key, value)) = parse_entry_with(line, read) else {
+            continue;
+        };
+        let Some((_, raw)) = read.separator.split(line) else {
+            continue;
+        };
+        if raw != value {
+            found.push(tf("convert.trimmed_line", &[&format!("{:?}", key), &(index + 1), &visible(raw), &visible(&value)]));
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn visible_marks_whitespace_and_controls() {
+        assert_eq!(visible(" a\tb\r"), "·a→b␍");
+        assert_eq!(visible("\u{3000}\u{7}"), "[U+3000][U+0007]");
+    }
+
+    #[test]
+    fn find_reports_only_changed_lines() {
+        let path = std::env::temp_dir().join(format!("json_lang_trimmed_test_{}.lang", std::process::id()));
+        fs::write(&path, "same=value\ntrailing=value  \n# comment\nleading= value\n").unwrap();
+        let found = find(path.to_str().unwrap(), &ReadOptions::default());
+        fs::remove_file(&path).unwrap();
+        assert_eq!(found.len(), 2);
+        assert!(found[0].starts_with("\"trailing\"") && found[0].contains('2') && found[0].contains("\"value··\" => \"value\""));
+        assert!(found[1].starts_with("\"leading\"") && found[1].contains("\"·value\" => \"value\""));
+    }
+}
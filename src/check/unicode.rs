@@ -0,0 +1,47 @@
+use crate::i18n::tf;
+
+/// 目に見えない・表示を乱す文字とその名前
+const SUSPICIOUS: &[(char, &str)] = &[
+    ('\u{FFFD}', "REPLACEMENT CHARACTER"),
+    ('\u{200B}', "ZERO WIDTH SPACE"),
+    ('\u{200C}', "ZERO WIDTH NON-JOINER"),
+    ('\u{200D}', "ZERO WIDTH JOINER"),
+    ('\u{2060}', "WORD JOINER"),
+    ('\u{FEFF}', "ZERO WIDTH NO-BREAK SPACE"),
+    ('\u{061C}', "ARABIC LETTER MARK"),
+    ('\u{200E}', "LEFT-TO-RIGHT MARK"),
+    ('\u{200F}', "RIGHT-TO-LEFT MARK"),
+    ('\u{202A}', "LEFT-TO-RIGHT EMBEDDING"),
+    ('\u{202B}', "RIGHT-TO-LEFT EMBEDDING"),
+    ('\u{202C}', "POP DIRECTIONAL FORMATTING"),
+    ('\u{202D}', "LEFT-TO-RIGHT OVERRIDE"),
+    ('\u{202E}', "RIGHT-TO-LEFT OVERRIDE"),
+    ('\u{2066}', "LEFT-TO-RIGHT ISOLATE"),
+    ('\u{2067}', "RIGHT-TO-LEFT ISOLATE"),
+    ('\u{2068}', "FIRST STRONG ISOLATE"),
+    ('\u{2069}', "POP DIRECTIONAL ISOLATE"),
+];
+
+/// 置換文字・ゼロ幅文字・双方向制御文字を出現順(重複なし)に返す
+pub fn suspicious_chars(text: &str) -> Vec<(char, &'static str)> {
+    let mut found: Vec<(char, &'static str)> = Vec::new();
+    for c in text.chars() {
+        if let Some(&(_, name)) = SUSPICIOUS.iter().find(|(s, _)| *s == c) {
+            if !found.iter().any(|(f, _)| *f == c) {
+                found.push((c, name));
+            }
+        }
+    }
+    found
+}
+
+/// キーと値に含まれる不審な文字ごとのメッセージ
+pub fn check(key: &str, value: &str) -> Vec<String> {
+    let mut messages = Vec::new();
+    for (message, text) in [("lint.unicode.key", key), ("lint.unicode.value", value)] {
+        for (c, name) in suspicious_chars(text) {
+            messages.push(tf(message, &[&format!("{:04X}", c as u32), &name]));
+        }
+    }
+    messages
+}
@@ -0,0 +1,109 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::comments;
+use crate::convert::{plan_jobs_with_mode, ConvertOptions, Mode};
+use crate::format::Format;
+use crate::i18n::{t, tf};
+use crate::run_log;
+
+/// --cleanで削除する範囲
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CleanScope {
+    /// .lang/.jsonのファイルのみ
+    Managed,
+    /// 出力ディレクトリの中身すべて
+    All,
+}
+
+/// 2つのパスが同じファイル・ディレクトリを指しているか(存在しない場合はパスで比較する)
+pub fn same_path(a: &str, b: &str) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => Path::new(a) == Path::new(b),
+    }
+}
+
+/// `dir` が `child` と同じか、その親ディレクトリか
+pub fn contains_dir(dir: &str, child: &str) -> bool {
+    match (fs::canonicalize(dir), fs::canonicalize(child)) {
+        (Ok(dir), Ok(child)) => child.starts_with(dir),
+        _ => Path::new(child).starts_with(dir),
+    }
+}
+
+/// 出力ディレクトリ直下の、このツールが管理する拡張子(.lang/.json)のファイル
+fn managed_outputs(output_dir: &str) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(output_dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && Format::from_path(path).is_some())
+        .filter(|path| path.file_name().is_some_and(|n| n != crate::cache::CACHE_FILE_NAME))
+        .collect()
+}
+
+/// どの入力にも対応しない出力ファイルを削除する(dry_runなら一覧の表示のみ)
+/// 変換方向に関係なく、現在の入力のいずれかから生成される出力は残す。
+/// 入力ディレクトリが読めない場合はすべての出力が古く見えてしまうため、何も削除しない
+pub fn delete_stale(options: &ConvertOptions) -> Vec<String> {
+    let mut expected: HashSet<PathBuf> = HashSet::new();
+    let Ok((jobs, _)) = plan_jobs_with_mode(options, Mode::All) else {
+        return Vec::new();
+    };
+    for job in jobs {
+        if options.comments_sidecar && job.target == Format::Json {
+            expected.insert(PathBuf::from(comments::sidecar_path(&job.output_path)));
+        }
+        expected.extend(job.output_paths().map(PathBuf::from));
+    }
+    let stale: Vec<PathBuf> = managed_outputs(&options.output_dir).into_iter().filter(|path| !expected.contains(path)).collect();
+
+    let mut deleted = Vec::new();
+    if stale.is_empty() {
+        return deleted;
+    }
+    println!("\n{}", t("cleanup.stale_header"));
+    for path in &stale {
+        println!("- {}", path.display());
+    }
+    if options.dry_run {
+        println!("{}", t("cleanup.stale_dry_run"));
+        return deleted;
+    }
+    for path in stale {
+        match fs::remove_file(&path) {
+            Ok(()) => deleted.push(path.display().to_string()),
+            Err(e) => println!("{}", tf("cleanup.delete_failed", &[&path.display(), &e])),
+        }
+    }
+    deleted
+}
+
+/// 変換の前に出力ディレクトリを空にし、削除した件数を返す(dry_runなら件数の表示のみ)
+pub fn clean_output(options: &ConvertOptions, scope: CleanScope) -> usize {
+    let targets: Vec<PathBuf> = match scope {
+        CleanScope::Managed => managed_outputs(&options.output_dir),
+        CleanScope::All => match fs::read_dir(&options.output_dir) {
+            Ok(entries) => entries.flatten().map(|entry| entry.path()).filter(|path| !run_log::is_run_log(path)).collect(),
+            Err(_) => Vec::new(),
+        },
+    };
+    if options.dry_run {
+        println!("{}", tf("cleanup.clean_dry_run", &[&targets.len()]));
+        return 0;
+    }
+
+    let mut removed = 0;
+    for path in targets {
+        let result = if path.is_dir() { fs::remove_dir_all(&path) } else { fs::remove_file(&path) };
+        match result {
+            Ok(()) => removed += 1,
+            Err(e) => println!("{}", tf("cleanup.delete_failed", &[&path.display(), &e])),
+        }
+    }
+    println!("{}", tf("cleanup.cleaned", &[&removed]));
+    removed
+}
@@ -0,0 +1,936 @@
+use clap::parser::ValueSource;
+use clap::{ArgMatches, Args, Parser, Subcommand, ValueEnum, ValueHint};
+use std::time::Duration;
+use clap_complete::Shell;
+use glob::Pattern;
+use regex::Regex;
+use crate::cleanup::CleanScope;
+use crate::concat::DuplicatePolicy;
+use crate::convert::{ConvertOptions, Mode, Stamp, DEFAULT_MAX_FILES};
+use crate::existing::ExistingPolicy;
+use crate::export::Export;
+use crate::ext_map::ExtMap;
+use crate::filter::{parse_glob, FileFilter};
+use crate::format::{Format, InputSeparator, LineEnding, NullPolicy, OutputEncoding, ReadOptions, Separator, WriteOptions, DEFAULT_JSON_INDENT};
+use crate::i18n::{t, tf, Language};
+use crate::layout::CategoryPrefix;
+use crate::manifest::ManifestFormat;
+use crate::namespaces::UnknownNamespaces;
+use crate::new_locale::FillPolicy;
+use crate::output_name::{NameCollisions, OutputName};
+use crate::placeholders::ReportFormat;
+use crate::route::Route;
+use crate::schema::{parse_schema, Schema};
+use crate::size_guard::{parse_size, LargeFiles};
+use crate::trace::TraceFormat;
+use crate::transform::empty::EmptyPolicy;
+use crate::transform::escape::Escape;
+use crate::transform::external::DEFAULT_TIMEOUT_SECS;
+use crate::transform::formatting::FormattingCodes;
+use crate::transform::key_case::KeyCase;
+use crate::transform::key_map::{parse_key_map, KeyMap, KeyMapDirection, UnmappedKeys};
+use crate::transform::key_filter::{parse_regex, KeyFilter};
+use crate::transform::normalize::Normalization;
+use crate::transform::placeholder::PlaceholderStyle;
+use crate::transform::sort::SortOrder;
+use crate::transform::vars::{parse_vars, MissingVars, Vars};
+
+/// 入力ディレクトリを指定する環境変数
+pub const ENV_INPUT: &str = "JSON_LANG_INPUT";
+/// 出力ディレクトリを指定する環境変数
+pub const ENV_OUTPUT: &str = "JSON_LANG_OUTPUT";
+/// 変換方向を指定する環境変数
+pub const ENV_MODE: &str = "JSON_LANG_MODE";
+/// 色付けを無効にする環境変数(1/true/yes/onで有効)
+pub const ENV_NO_COLOR: &str = "JSON_LANG_NO_COLOR";
+
+/// Jsonからlangまたは，langからJsonに変換する簡易ツール
+/// サブコマンドを省略した場合は対話メニューを起動する
+#[derive(Parser)]
+#[command(name = "json_lang", version)]
+pub struct Cli {
+    /// 設定ファイル(省略時は ./json_lang.toml があれば読み込む)
+    #[arg(long, global = true, value_hint = ValueHint::FilePath)]
+    pub config: Option<String>,
+    /// メッセージの言語(省略時は設定ファイルのlang、環境変数LANGの順に決め、既定は日本語)
+    #[arg(long, global = true)]
+    pub lang: Option<Language>,
+    /// 色付けせずに表示する(環境変数NO_COLORや出力先が端末でない場合も色付けしない)
+    #[arg(long, global = true, env = ENV_NO_COLOR, value_parser = clap::builder::BoolishValueParser::new())]
+    pub no_color: bool,
+    /// ファイルごとの読み込み・書き込みの構造化ログを標準エラー出力に書き出す(jsonは1行に1つのJSON)
+    #[arg(long, global = true, value_enum)]
+    pub trace_format: Option<TraceFormat>,
+    /// 参照ファイルに指定したhttps://のURLを取得せず、前回取得したキャッシュ(.json_lang_remote)だけを使う
+    #[arg(long, global = true)]
+    pub offline: bool,
+    /// 実際に使う設定値とその出どころ(コマンドライン・環境変数・設定ファイル・既定値)を表示する
+    #[arg(long, global = true)]
+    pub verbose: bool,
+    /// 変換するファイルまたはディレクトリ(ドラッグ&ドロップ用)。ファイルは同じ場所に、ディレクトリの中身は出力ディレクトリに変換する
+    #[arg(value_hint = ValueHint::AnyPath)]
+    pub paths: Vec<String>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// inputディレクトリのファイルを一括変換する(対話メニューの1と2に相当)
+    Convert(ConvertArgs),
+    /// convertと同じ変換をメモリ上で行い、出力ディレクトリが最新か確かめる(CI向け、書き込みは行わない)
+    Check(ConvertArgs),
+    /// 共通の祖先(base)をもとに2つの編集結果を3方向マージする
+    Merge3(Merge3Args),
+    /// テンプレートのロケールから新しいロケールのファイルを作成する
+    NewLocale(NewLocaleArgs),
+    /// ファイルのキーを参照キー一覧(バニラのen_us.jsonなど)と照合する
+    ValidateKeys(ValidateKeysArgs),
+    /// 複数のファイルを指定した順に1つのファイルへ連結する
+    Concat(ConcatArgs),
+    /// 1つのファイルをキーの接頭辞ごとに複数のファイルへ分割する
+    Split(SplitArgs),
+    /// 1つのファイルをカテゴリ(キーの最初の区切り)ごとのファイルに分け、<出力先>/<ロケール>/<カテゴリ>.jsonに書き出す
+    Explode(ExplodeArgs),
+    /// explodeで書き出したディレクトリのファイルを1つのファイルにまとめる
+    Implode(ImplodeArgs),
+    /// 値やキーの問題(不審なUnicode文字、括弧の対応など)を検査する
+    Lint(LintArgs),
+    /// 値の文字数が上限を超えていないか検査する
+    ValueLength(ValueLengthArgs),
+    /// 訳の値が翻訳元の値より大きく長くなっていないか(倍率・文字数の上限)を検査する
+    LengthRatio(LengthRatioArgs),
+    /// .lang⇄JSONの往復変換をメモリ上で行い、失われる内容を報告する(書き込みは行わない)
+    Verify(VerifyArgs),
+    /// 値の中のプレースホルダーを形式(%s, %d, %1$s, {0}, {{name}})ごとに集計する
+    Placeholders(PlaceholdersArgs),
+    /// 同じ値をまとめ、回数と使っているキーを回数の多い順にCSVで書き出す(用語集向け)
+    Glossary(GlossaryArgs),
+    /// JSONのlangファイルがゲームの前提(文字列だけのオブジェクト、重複の無いキー、解釈できる%など)を満たしているか検査する
+    ValidateMc(ValidateMcArgs),
+    /// ロケールごとの翻訳率(参照のキーに対する割合)をshields.ioのバッジ用JSONに書き出す
+    Badges(BadgesArgs),
+    /// キーを名前空間(block.<名前空間>.<パス>の2番目、旧形式のキーは最初の区切り)ごとに数え、許可していない名前空間を報告する
+    Namespaces(NamespacesArgs),
+    /// .lang⇄JSONの変換をローカルのHTTPサーバーで提供する(POST /lang2json, POST /json2lang, GET /health)
+    Serve(ServeArgs),
+    /// 貼り付けた.lang/JSONをその場で変換して表示する(対話メニューの4に相当)
+    Repl,
+    /// シェルの補完スクリプトを標準出力に書き出す
+    Completions(CompletionsArgs),
+}
+
+#[derive(Args)]
+pub struct ConvertArgs {
+    /// 変換方向
+    #[arg(long, value_enum, default_value_t = Mode::All, env = ENV_MODE)]
+    pub mode: Mode,
+    /// 入力ディレクトリ
+    #[arg(long, default_value = "./input", value_hint = ValueHint::DirPath, env = ENV_INPUT)]
+    pub input: String,
+    /// 出力ディレクトリ
+    #[arg(long, default_value = "./output", value_hint = ValueHint::DirPath, env = ENV_OUTPUT)]
+    pub output: String,
+    /// キーと値にUnicode正規化を適用する(キーの重複検出より前に行う)
+    #[arg(long, value_enum)]
+    pub normalize: Option<Normalization>,
+    /// キーの大文字・小文字をそろえる(絞り込みと並べ替えより前に行う)
+    #[arg(long, value_enum, default_value = "preserve")]
+    pub key_case: KeyCase,
+    /// --key-case lowerと同じ
+    #[arg(long, conflicts_with = "key_case")]
+    pub lowercase_keys: bool,
+    /// --key-caseで同じキーになったときの扱い
+    #[arg(long, value_enum, default_value_t = DuplicatePolicy::Error)]
+    pub key_case_duplicates: DuplicatePolicy,
+    /// Java版と統合版のキーの対応表(`Java版のキー,統合版のキー` の行のファイル、`builtin` で同梱の対応表)。絞り込みより前に変換する
+    #[arg(long, value_parser = parse_key_map, value_hint = ValueHint::FilePath)]
+    pub key_map: Option<KeyMap>,
+    /// --key-mapで変換する向き
+    #[arg(long, value_enum, default_value = "java-to-bedrock", requires = "key_map")]
+    pub key_map_direction: KeyMapDirection,
+    /// --key-mapの対応表に無いキーの扱い
+    #[arg(long, value_enum, default_value = "keep", requires = "key_map")]
+    pub unmapped_keys: UnmappedKeys,
+    /// 空のキー(`=value`、JSONの `""`)の扱い(warnは残して報告する)
+    #[arg(long, value_enum, default_value = "warn")]
+    pub empty_keys: EmptyPolicy,
+    /// 空の値(`key=`、JSONの `""`)の扱い
+    #[arg(long, value_enum, default_value = "warn")]
+    pub empty_values: EmptyPolicy,
+    /// 値の `Ã©` のような二重にエンコードされた文字化けを元に戻す(--dry-runで変更を確認できる)
+    #[arg(long)]
+    pub fix_double_encoding: bool,
+    /// 値の中のプレースホルダーを指定した形式に変換する
+    #[arg(long, value_enum)]
+    pub placeholder_style: Option<PlaceholderStyle>,
+    /// 値の中の§書式コードの扱い
+    #[arg(long, value_enum)]
+    pub formatting_codes: Option<FormattingCodes>,
+    /// 保存時に値をエスケープする方式。既にある文字参照は二重にエスケープしない
+    #[arg(long, value_enum, default_value = "none")]
+    pub escape_profile: Escape,
+    /// 読み込み時に値のエスケープを解除する方式
+    #[arg(long, value_enum, default_value = "none")]
+    pub unescape_profile: Escape,
+    /// キーの先頭から取り除く接頭辞
+    #[arg(long)]
+    pub strip_prefix: Option<String>,
+    /// キーの先頭に付与する接頭辞(除去の後に適用)
+    #[arg(long)]
+    pub add_prefix: Option<String>,
+    /// --add-prefixの対象をこの文字列で始まるキーに限定する
+    #[arg(long, requires = "add_prefix")]
+    pub prefix_match: Option<String>,
+    /// 値の `{{name}}` を置き換える変数のJSONファイル(`\{{` は置き換えない)
+    #[arg(long, value_parser = parse_vars, value_hint = ValueHint::FilePath)]
+    pub vars: Option<Vars>,
+    /// 変数ファイルに無い変数の扱い
+    #[arg(long, value_enum, default_value = "error", requires = "vars")]
+    pub missing_vars: MissingVars,
+    /// 値を加工する外部コマンド(シェルで実行する)。ファイルごとに `キー<TAB>値` の行を標準入力に渡し、標準出力の同じ形式の行で置き換える。
+    /// 任意のコマンドを実行するため、コマンドラインでのみ指定でき、設定ファイルには書けない
+    #[arg(long)]
+    pub transform_cmd: Option<String>,
+    /// --transform-cmdの1回の実行の制限時間(秒)
+    #[arg(long, default_value_t = DEFAULT_TIMEOUT_SECS, requires = "transform_cmd")]
+    pub transform_timeout: u64,
+    /// .lang出力でキーの先頭からN個の区切りごとに空行と見出しコメントを入れる
+    #[arg(long, value_parser = clap::value_parser!(u8).range(1..=2))]
+    pub sections: Option<u8>,
+    /// .lang入力のキーと値の区切り文字。autoでは行ごとに最初に現れた `=`・`:`・タブで区切る
+    #[arg(long, value_enum, default_value = "equals")]
+    pub separator: InputSeparator,
+    /// .lang出力のキーと値の区切り文字
+    #[arg(long, value_enum, default_value = "equals")]
+    pub output_separator: Separator,
+    /// .langの値の末尾の ` #...` を行末コメントとして取り除く(値の中の `#` は `\#` と書く)。.lang出力では空白の後の `#` を `\#` にする
+    #[arg(long)]
+    pub inline_comments: bool,
+    /// .lang/JSON出力の改行コード
+    #[arg(long, value_enum, default_value = "lf")]
+    pub line_ending: LineEnding,
+    /// .lang/JSON出力の文字コード
+    #[arg(long, value_enum, default_value = "utf-8")]
+    pub output_encoding: OutputEncoding,
+    /// JSON入力の数値と真偽値を文字列にする(既定では読み飛ばして報告する)
+    #[arg(long)]
+    pub coerce: bool,
+    /// --coerceでのJSONのnullの扱い
+    #[arg(long, value_enum, default_value = "empty", requires = "coerce")]
+    pub coerce_null: NullPolicy,
+    /// キーも値も同じ重複を1つにまとめて件数を報告し、値が異なる重複はエラーにする
+    #[arg(long)]
+    pub dedup_identical: bool,
+    /// 確認用: 値が異なる重複を置き換えず、2つ目以降を `key__dup1` のような別のキーとして書き出す(配布する出力には使わない)
+    #[arg(long, conflicts_with_all = ["dedup_identical", "key_case_duplicates", "merge_into_existing", "in_place", "project", "stamp", "stamp_no_time", "minify", "indent"])]
+    pub duplicates_to_suffix: bool,
+    /// 拡張子ではなく内容から.langかJSONかを決めて読み込む(決めた形式はファイルごとに表示する)
+    #[arg(long)]
+    pub auto_detect: bool,
+    /// 空白・`=`・引用符や `[A-Za-z0-9._-]` 以外の文字を含むキーがあるファイルを、警告ではなく読み込みの失敗にする
+    #[arg(long)]
+    pub reject_suspicious_keys: bool,
+    /// キーの検査で `[A-Za-z0-9._-]` のほかに許可する文字(例: ":/")
+    #[arg(long, value_name = "CHARS", default_value = "")]
+    pub allow_key_chars: String,
+    /// 書き出す内容(.langから変換する場合も同じキーと値のJSON)をこのJSON Schemaで検証する
+    #[arg(long, value_parser = parse_schema, value_hint = ValueHint::FilePath)]
+    pub schema: Option<Schema>,
+    /// スキーマに違反するファイルを、警告ではなく変換の失敗にする
+    #[arg(long, requires = "schema")]
+    pub schema_strict: bool,
+    /// .langの読み込みで値が変わった行(前後の空白や行末コメントを取り除いたもの)を、空白を記号にして表示する
+    #[arg(long)]
+    pub trim_report: bool,
+    /// 前回から入力と設定が変わっていないファイルの変換を省略する
+    #[arg(long)]
+    pub incremental: bool,
+    /// --incrementalのキャッシュを無視してすべて変換する
+    #[arg(long, requires = "incremental")]
+    pub rebuild: bool,
+    /// 出力するキーの並び順
+    #[arg(long, value_enum, default_value = "none")]
+    pub sort: SortOrder,
+    /// --sortで大文字と小文字を区別せずに比べる
+    #[arg(long)]
+    pub sort_ignore_case: bool,
+    /// --sort valuesでも出力ファイル名に .byvalue を付けない
+    #[arg(long)]
+    pub force: bool,
+    /// JSON出力の字下げの空白数
+    #[arg(long, default_value_t = DEFAULT_JSON_INDENT)]
+    pub indent: usize,
+    /// JSON出力を改行と空白の無い1行にする(配布用の出力)
+    #[arg(long, conflicts_with = "indent")]
+    pub minify: bool,
+    /// この正規表現に一致するキーだけを出力する
+    #[arg(long, value_parser = parse_regex)]
+    pub key_include: Option<Regex>,
+    /// この正規表現に一致するキーを出力しない(--key-includeの後に適用)
+    #[arg(long, value_parser = parse_regex)]
+    pub key_exclude: Option<Regex>,
+    /// 変換する入力ファイルのglobパターン(複数指定可、入力ディレクトリからの相対パス)
+    #[arg(long, value_parser = parse_glob)]
+    pub include: Vec<Pattern>,
+    /// 変換しない入力ファイルのglobパターン(複数指定可)
+    #[arg(long, value_parser = parse_glob)]
+    pub exclude: Vec<Pattern>,
+    /// 出力ファイル名のテンプレート。{name}(入力ファイル名), {ext}(出力の拡張子), {dir}(入力ディレクトリからの相対ディレクトリ)を使える
+    #[arg(long, value_parser = OutputName::parse, default_value = "{dir}/{name}.{ext}")]
+    pub output_name: OutputName,
+    /// 複数の入力が同じ出力先になったときの扱い(mirror: 入力と同じ構成のサブディレクトリ、prefix: ファイル名の前に相対ディレクトリ、error: 何も書き込まない)
+    #[arg(long, value_enum, default_value = "mirror")]
+    pub name_collisions: NameCollisions,
+    /// 接頭辞に当てはまるキーを別の出力ファイルに書き出す(`gui.*=assets/lang/{name}.{ext}`、複数指定可)
+    #[arg(long, value_parser = Route::parse)]
+    pub route: Vec<Route>,
+    /// 基準のロケール(en_usなど)。ほかのロケールの不足するキーを同じディレクトリの基準のファイルの値で補う
+    #[arg(long, value_name = "LOCALE")]
+    pub with_fallback: Option<String>,
+    /// 出力ファイル名を作る前に入力ファイル名の末尾から取り除く中間の拡張子(カンマ区切り、例: min,backup)
+    #[arg(long, value_delimiter = ',')]
+    pub strip_suffixes: Vec<String>,
+    /// .lang/.json以外の拡張子を既存の形式として読み込む(カンマ区切り、例: txt=lang,locale=lang)
+    #[arg(long, value_parser = ExtMap::parse)]
+    pub ext_map: Option<ExtMap>,
+    /// 出力ファイルを ./output ではなく各入力ファイルと同じディレクトリに書き出す
+    #[arg(long, conflicts_with_all = ["output", "delete_stale", "clean", "clean_all"])]
+    pub in_place: bool,
+    /// Forge/Fabricのmodのリポジトリのルート。src/*/resources/assets/<modid>/lang をmodごとに変換し、同じディレクトリに書き戻す
+    #[arg(long, value_hint = ValueHint::DirPath, conflicts_with_all = ["in_place", "delete_stale", "clean", "clean_all"])]
+    pub project: Option<String>,
+    /// --projectの出力を書き戻さず、このディレクトリにルートからの相対パスのまま書き出す
+    #[arg(long, value_hint = ValueHint::DirPath, requires = "project")]
+    pub project_output: Option<String>,
+    /// --projectでデータ生成の出力(src/generated)も対象にする
+    #[arg(long, requires = "project")]
+    pub include_generated: bool,
+    /// 出力ファイルの更新日時を入力ファイルに合わせる
+    #[arg(long)]
+    pub preserve_mtime: bool,
+    /// .lang出力の先頭にツールのバージョン、入力ファイル名とそのハッシュ、生成日時をコメントとして書く
+    #[arg(long)]
+    pub stamp: bool,
+    /// --stampと同じだが生成日時を書かない(同じ入力からは同じ出力になる)
+    #[arg(long, conflicts_with = "stamp")]
+    pub stamp_no_time: bool,
+    /// .lang→JSONでコメントを<名前>.comments.jsonに書き出し、JSON→.langでは入力の隣の<名前>.comments.jsonからコメントを付け直す
+    #[arg(long, conflicts_with_all = ["sections", "export"])]
+    pub comments_sidecar: bool,
+    /// 変換がすべて成功した後、どの入力にも対応しない出力ファイル(.lang/.json)を削除する
+    #[arg(long)]
+    pub delete_stale: bool,
+    /// 変換の前に出力ディレクトリの.lang/.jsonファイルを削除する
+    #[arg(long, conflicts_with = "clean_all")]
+    pub clean: bool,
+    /// 変換の前に出力ディレクトリの中身をすべて削除する
+    #[arg(long)]
+    pub clean_all: bool,
+    /// ファイルの書き込み・削除を行わず、行う予定の処理だけを表示する
+    #[arg(long)]
+    pub dry_run: bool,
+    /// 出力ディレクトリの conversion.log に実行ごとの結果を追記しない
+    #[arg(long)]
+    pub no_run_log: bool,
+    /// 変換に成功したファイルは表示せず(端末では進み具合を1行で表示する)、省略・失敗したファイルと合計だけを表示する
+    #[arg(long)]
+    pub only_failures: bool,
+    /// 処理時間の長いファイルをN件(省略時は10件)表示し、合計時間を表示する
+    #[arg(long, num_args = 0..=1, default_missing_value = "10")]
+    pub timings: Option<usize>,
+    /// ファイルごとの結果と処理時間をJSONで書き出す
+    #[arg(long, value_hint = ValueHint::FilePath)]
+    pub report: Option<String>,
+    /// 変換後に出力ファイルの相対パスとSHA-256ハッシュ・サイズの一覧を書き出す
+    #[arg(long, value_hint = ValueHint::FilePath)]
+    pub manifest: Option<String>,
+    /// --manifestの書式
+    #[arg(long, value_enum, default_value = "json", requires = "manifest")]
+    pub manifest_format: ManifestFormat,
+    /// 変換は行わず、既存のマニフェストを出力ディレクトリの内容と照合する
+    #[arg(long, value_hint = ValueHint::FilePath, conflicts_with = "manifest")]
+    pub verify_manifest: Option<String>,
+    /// 既存の出力ファイルを置き換えず、既存のキーの順序を保ったまま新しいキーをマージする
+    #[arg(long)]
+    pub merge_into_existing: bool,
+    /// --merge-into-existingで既存の出力と値が異なるキーの扱い
+    #[arg(long, value_enum, default_value = "overwrite", requires = "merge_into_existing")]
+    pub merge_conflict: ExistingPolicy,
+    /// 変換の代わりに、キーだけ(keys)または値だけ(values)を1行ずつ書き出す(拡張子は.keys.txt/.values.txt)
+    #[arg(long, value_enum, conflicts_with = "merge_into_existing")]
+    pub export: Option<Export>,
+    /// --exportの一覧を入力ファイルすべてについて1つのファイルにまとめる(キーは重複を除き、--sort keysで並べ替える)
+    #[arg(long, requires = "export", conflicts_with = "incremental", value_hint = ValueHint::FilePath)]
+    pub export_to: Option<String>,
+    /// すべての出力を一時ディレクトリに書き出し、失敗が無かった場合だけ出力先へ移動する(失敗時は出力先を変更しない)
+    #[arg(long, conflicts_with_all = ["clean", "clean_all", "dry_run"])]
+    pub transactional: bool,
+    /// 最初の失敗で残りのファイルを処理せずに中断する(--max-failures 0と同じ)
+    #[arg(long, conflicts_with = "max_failures")]
+    pub fail_fast: bool,
+    /// 失敗がN件を超えたら残りのファイルを処理せずに中断する
+    #[arg(long)]
+    pub max_failures: Option<usize>,
+    /// 入力ディレクトリのサブディレクトリもたどる(出力は{dir}で同じ構成になる)
+    #[arg(long)]
+    pub recursive: bool,
+    /// --recursiveでたどるサブディレクトリの深さの上限(0なら入力ディレクトリ直下のみ)
+    #[arg(long, requires = "recursive")]
+    pub max_depth: Option<usize>,
+    /// 処理する入力ファイル数の上限。超えた分はたどらずに警告する
+    #[arg(long, default_value_t = DEFAULT_MAX_FILES)]
+    pub max_files: usize,
+    /// 処理するファイル数の上限(パスの順)。最後に処理したファイルを結果に表示し、--start-afterで続けられる
+    #[arg(long, value_name = "N")]
+    pub limit: Option<usize>,
+    /// このパスより後(パスの順)のファイルだけを処理する。前回の結果に表示されたパスを渡す
+    #[arg(long, value_name = "PATH")]
+    pub start_after: Option<String>,
+    /// この大きさを超える入力ファイルは--large-filesに従って扱う(例: 512K, 256M, 2G)
+    #[arg(long, value_parser = parse_size, default_value = "256M")]
+    pub max_file_size: u64,
+    /// --max-file-sizeを超えたファイルを省略する(skip)か、少しずつ読みながら変換する(stream)か
+    #[arg(long, value_enum, default_value = "skip")]
+    pub large_files: LargeFiles,
+    /// 設定ファイルの[profile.名前]のオプションを[convert]に重ねて使う
+    #[arg(long)]
+    pub profile: Option<String>,
+}
+
+/// オプションの値の出どころ(優先順位の高い順)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OptionSource {
+    CommandLine,
+    Env(&'static str),
+    Config,
+    Default,
+}
+
+impl OptionSource {
+    /// コマンドラインか環境変数で指定されたか
+    pub fn of(matches: &ArgMatches, id: &str, env: &'static str) -> OptionSource {
+        match matches.value_source(id) {
+            Some(ValueSource::CommandLine) => OptionSource::CommandLine,
+            Some(ValueSource::EnvVariable) => OptionSource::Env(env),
+            _ => OptionSource::Default,
+        }
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            OptionSource::CommandLine => t("source.command_line"),
+            OptionSource::Env(name) => tf("source.env", &[&name]),
+            OptionSource::Config => t("source.config"),
+            OptionSource::Default => t("source.default"),
+        }
+    }
+}
+
+/// 設定ファイルで値が入ったか分かるよう、--verboseで常に表示するオプションと対応する環境変数
+pub const CORE_OPTIONS: [(&str, &str); 3] = [("mode", ENV_MODE), ("input", ENV_INPUT), ("output", ENV_OUTPUT)];
+
+/// 設定ファイルの値を、コマンドラインと環境変数で指定されていないオプションの引数(`--sort keys`など)に変換する。
+/// 引数として解釈させることで、対応するフラグと同じ検証とエラーになる
+pub fn config_args(table: &toml::Table, matches: &ArgMatches, command: &clap::Command) -> Result<Vec<(String, Vec<String>)>, String> {
+    let mut injected = Vec::new();
+    for (key, value) in table {
+        let id = key.replace('-', "_");
+        let Some(arg) = command.get_arguments().find(|arg| arg.get_id() == id.as_str() && arg.get_long().is_some() && id != "profile") else {
+            return Err(tf("config.unknown_option", &[key]));
+        };
+        if matches!(matches.value_source(&id), Some(ValueSource::CommandLine | ValueSource::EnvVariable)) {
+            continue;
+        }
+        let flag = format!("--{}", arg.get_long().unwrap());
+        let values = match value {
+            toml::Value::Array(items) => items.iter().collect(),
+            value => vec![value],
+        };
+        let mut args = Vec::new();
+        for value in values {
+            match value {
+                toml::Value::Boolean(enabled) if !arg.get_action().takes_values() => {
+                    if *enabled {
+                        args.push(flag.clone());
+                    }
+                }
+                toml::Value::String(text) if arg.get_action().takes_values() => args.extend([flag.clone(), text.clone()]),
+                toml::Value::Integer(number) if arg.get_action().takes_values() => args.extend([flag.clone(), number.to_string()]),
+                // `[convert.route]` のような表は `キー=値` を1つずつ渡す
+                toml::Value::Table(entries) if arg.get_action().takes_values() => {
+                    for (name, value) in entries {
+                        let toml::Value::String(text) = value else {
+                            return Err(tf("config.invalid_value", &[key]));
+                        };
+                        args.extend([flag.clone(), format!("{}={}", name, text)]);
+                    }
+                }
+                _ => return Err(tf("config.invalid_value", &[key])),
+            }
+        }
+        injected.push((id, args));
+    }
+    Ok(injected)
+}
+
+impl ConvertArgs {
+    pub fn into_options(self) -> ConvertOptions {
+        ConvertOptions {
+            mode: self.mode,
+            input_dir: self.input,
+            output_dir: self.output,
+            normalize: self.normalize,
+            key_case: if self.lowercase_keys { KeyCase::Lower } else { self.key_case },
+            key_case_duplicates: self.key_case_duplicates,
+            key_map: self.key_map,
+            key_map_direction: self.key_map_direction,
+            unmapped_keys: self.unmapped_keys,
+            empty_keys: self.empty_keys,
+            empty_values: self.empty_values,
+            fix_double_encoding: self.fix_double_encoding,
+            placeholder_style: self.placeholder_style,
+            formatting_codes: self.formatting_codes,
+            escape_profile: self.escape_profile,
+            unescape_profile: self.unescape_profile,
+            strip_prefix: self.strip_prefix,
+            add_prefix: self.add_prefix,
+            prefix_match: self.prefix_match,
+            vars: self.vars,
+            missing_vars: self.missing_vars,
+            transform_cmd: self.transform_cmd,
+            transform_timeout: Duration::from_secs(self.transform_timeout),
+            write: WriteOptions {
+                section_depth: self.sections.map(usize::from),
+                json_indent: self.indent,
+                minify: self.minify,
+                separator: self.output_separator,
+                inline_comments: self.inline_comments,
+                line_ending: self.line_ending,
+                encoding: self.output_encoding,
+                ..WriteOptions::default()
+            },
+            incremental: self.incremental,
+            rebuild: self.rebuild,
+            sort: self.sort,
+            sort_ignore_case: self.sort_ignore_case,
+            force: self.force,
+            keys: KeyFilter { include: self.key_include, exclude: self.key_exclude },
+            files: FileFilter { include: self.include, exclude: self.exclude },
+            output_name: self.output_name,
+            name_collisions: self.name_collisions,
+            routes: self.route,
+            with_fallback: self.with_fallback,
+            dedup_identical: self.dedup_identical,
+            auto_detect: self.auto_detect,
+            reject_suspicious_keys: self.reject_suspicious_keys,
+            allow_key_chars: self.allow_key_chars,
+            schema: self.schema,
+            schema_strict: self.schema_strict,
+            trim_report: self.trim_report,
+            read: ReadOptions {
+                separator: self.separator,
+                inline_comments: self.inline_comments,
+                coerce: self.coerce,
+                coerce_null: self.coerce_null,
+                duplicates_to_suffix: self.duplicates_to_suffix,
+            },
+            strip_suffixes: self.strip_suffixes,
+            ext_map: self.ext_map.unwrap_or_default(),
+            in_place: self.in_place,
+            project: self.project,
+            project_output: self.project_output,
+            include_generated: self.include_generated,
+            preserve_mtime: self.preserve_mtime,
+            stamp: if self.stamp_no_time {
+                Some(Stamp::NoTime)
+            } else {
+                self.stamp.then_some(Stamp::WithTime)
+            },
+            comments_sidecar: self.comments_sidecar,
+            delete_stale: self.delete_stale,
+            clean: if self.clean_all {
+                Some(CleanScope::All)
+            } else if self.clean {
+                Some(CleanScope::Managed)
+            } else {
+                None
+            },
+            dry_run: self.dry_run,
+            run_log: !self.no_run_log,
+            only_failures: self.only_failures,
+            timings: self.timings,
+            report: self.report,
+            manifest: self.manifest.map(|path| (path, self.manifest_format)),
+            verify_manifest: self.verify_manifest,
+            merge_into_existing: self.merge_into_existing.then_some(self.merge_conflict),
+            export: self.export,
+            export_to: self.export_to,
+            transactional: self.transactional,
+            max_failures: if self.fail_fast { Some(0) } else { self.max_failures },
+            recursive: self.recursive,
+            max_depth: self.max_depth,
+            max_files: self.max_files,
+            limit: self.limit,
+            start_after: self.start_after,
+            max_file_size: self.max_file_size,
+            large_files: self.large_files,
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct Merge3Args {
+    /// 共通の祖先となるファイル(base/ours/theirsはhttps://のURLも指定できる)
+    #[arg(value_hint = ValueHint::FilePath)]
+    pub base: String,
+    /// 自分側の編集結果
+    #[arg(value_hint = ValueHint::FilePath)]
+    pub ours: String,
+    /// 相手側の編集結果
+    #[arg(value_hint = ValueHint::FilePath)]
+    pub theirs: String,
+    /// 出力先(省略時は ./output/<oursのファイル名>)
+    #[arg(short, long, value_hint = ValueHint::FilePath)]
+    pub output: Option<String>,
+    /// 衝突したキーの扱い
+    #[arg(long, value_enum, default_value_t = ConflictStyle::Markers)]
+    pub conflict: ConflictStyle,
+}
+
+/// merge3で衝突したキーの扱い
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ConflictStyle {
+    /// git形式の衝突マーカーを.langに書き込む
+    Markers,
+    /// 衝突一覧を表示し、baseの値を残す
+    Report,
+}
+
+#[derive(Args)]
+pub struct NewLocaleArgs {
+    /// 作成するロケール名(例: de_de)
+    pub locale: String,
+    /// テンプレートのロケール名、ファイルパスまたはhttps://のURL
+    #[arg(long)]
+    pub from: String,
+    /// 値の埋め方
+    #[arg(long, value_enum, default_value_t = FillPolicy::Empty)]
+    pub fill: FillPolicy,
+    /// テンプレートを探し、新しいファイルを作成するディレクトリ
+    #[arg(long, default_value = "./input", value_hint = ValueHint::DirPath)]
+    pub dir: String,
+    /// 既存のファイルを上書きする
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Args)]
+pub struct ValidateKeysArgs {
+    /// 参照キー一覧(.lang/.json、または1行1キーのテキスト)。https://のURLも指定できる
+    #[arg(long, value_hint = ValueHint::FilePath)]
+    pub reference: String,
+    /// 検査するファイル(省略時は--inputのディレクトリ内のすべて)
+    #[arg(value_hint = ValueHint::FilePath)]
+    pub files: Vec<String>,
+    /// filesを省略した場合に検査するディレクトリ
+    #[arg(long, default_value = "./input", value_hint = ValueHint::DirPath)]
+    pub input: String,
+    /// 参照にあってファイルに無いキーも表示する
+    #[arg(long)]
+    pub missing: bool,
+    /// この接頭辞で始まるキーは照合しない(複数指定可)
+    #[arg(long)]
+    pub exclude_prefix: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct ConcatArgs {
+    /// 連結するファイル(指定した順に連結する)
+    #[arg(required = true, value_hint = ValueHint::FilePath)]
+    pub inputs: Vec<String>,
+    /// 出力先(拡張子で形式を判定する)
+    #[arg(short, long, value_hint = ValueHint::FilePath)]
+    pub output: String,
+    /// 複数のファイルに同じキーがあった場合の扱い
+    #[arg(long, value_enum, default_value_t = DuplicatePolicy::Error)]
+    pub duplicates: DuplicatePolicy,
+    /// .langのコメントと空行を保持する(.lang出力のみ)
+    #[arg(long)]
+    pub preserve_comments: bool,
+    /// 値を採用したファイルが同じキーのまとまりごとに `# from: ファイル` のコメントを前に書く(.lang出力のみ、キーの順は変えない)
+    #[arg(long)]
+    pub provenance: bool,
+}
+
+#[derive(Args)]
+pub struct SplitArgs {
+    /// 分割するファイル
+    #[arg(value_hint = ValueHint::FilePath)]
+    pub input: String,
+    /// 出力ディレクトリ
+    #[arg(long, default_value = "./output", value_hint = ValueHint::DirPath)]
+    pub out_dir: String,
+    /// 接頭辞=ファイル名 の対応表(省略時はキーの最初の区切りで分割する)
+    #[arg(long, value_hint = ValueHint::FilePath)]
+    pub mapping: Option<String>,
+    /// どの規則にも当てはまらないキーの出力先ファイル名
+    #[arg(long, default_value = "misc")]
+    pub misc: String,
+    /// 出力形式(省略時は入力と同じ)
+    #[arg(long, value_enum)]
+    pub format: Option<Format>,
+}
+
+#[derive(Args)]
+pub struct ExplodeArgs {
+    /// 分けるファイル(en_us.jsonなど)
+    #[arg(value_hint = ValueHint::FilePath)]
+    pub input: String,
+    /// 出力ディレクトリ。この中のロケール名のディレクトリに書き出す
+    #[arg(long, default_value = "./output", value_hint = ValueHint::DirPath)]
+    pub out_dir: String,
+    /// カテゴリのファイルでキーの最初の区切りを取り除くか残すか
+    #[arg(long, value_enum, default_value = "strip")]
+    pub category_prefix: CategoryPrefix,
+    /// カテゴリに振り分けないキー(区切りが無いなど)の出力先ファイル名
+    #[arg(long, default_value = "misc")]
+    pub misc: String,
+    /// 出力形式(省略時は入力と同じ)
+    #[arg(long, value_enum)]
+    pub format: Option<Format>,
+}
+
+#[derive(Args)]
+pub struct ImplodeArgs {
+    /// explodeで書き出したロケールのディレクトリ(./output/en_usなど)
+    #[arg(value_hint = ValueHint::DirPath)]
+    pub input: String,
+    /// 書き出すファイル(省略時はディレクトリ名に拡張子を付けたもの)
+    #[arg(long, value_hint = ValueHint::FilePath)]
+    pub output: Option<String>,
+    /// explodeで指定したキーの最初の区切りの扱い。stripならファイル名を接頭辞として戻す
+    #[arg(long, value_enum, default_value = "strip")]
+    pub category_prefix: CategoryPrefix,
+    /// explodeで指定した、カテゴリに振り分けないキーのファイル名(このファイルのキーには接頭辞を付けない)
+    #[arg(long, default_value = "misc")]
+    pub misc: String,
+    /// 出力形式(省略時は--outputの拡張子、--outputも省略した場合はjson)
+    #[arg(long, value_enum)]
+    pub format: Option<Format>,
+}
+
+#[derive(Args)]
+pub struct LintArgs {
+    /// 検査するファイル(省略時は--inputのディレクトリ内のすべて)
+    #[arg(value_hint = ValueHint::FilePath)]
+    pub files: Vec<String>,
+    /// filesを省略した場合に検査するディレクトリ
+    #[arg(long, default_value = "./input", value_hint = ValueHint::DirPath)]
+    pub input: String,
+    /// 括弧の検査で `{0}` 形式のプレースホルダーと `'{'` のエスケープを対象外にする
+    #[arg(long)]
+    pub placeholder_aware: bool,
+    /// 山括弧タグ(`<b>...</b>`)の対応を検査する
+    #[arg(long)]
+    pub tags: bool,
+    /// 許可するタグ名(カンマ区切り、省略時は全て許可)
+    #[arg(long, value_delimiter = ',', requires = "tags")]
+    pub allowed_tags: Vec<String>,
+    /// 翻訳元のファイル。同じキーの値と比較する
+    #[arg(long, value_hint = ValueHint::FilePath)]
+    pub source: Option<String>,
+    /// キーの検査で `[A-Za-z0-9._-]` のほかに許可する文字(例: ":/"。省略時は設定ファイルの [lint] allowed_key_chars)
+    #[arg(long, value_name = "CHARS")]
+    pub allow_key_chars: Option<String>,
+    /// 値の最大文字数(接頭辞ごとの上限は設定ファイルで指定する)
+    #[arg(long)]
+    pub max_value_length: Option<usize>,
+    /// 文字数からプレースホルダーを除く
+    #[arg(long)]
+    pub exclude_placeholders: bool,
+    /// キーの最後の区切り(block.minecraft.stoneならstone)と同じ値も、キーと同じ値として報告する
+    #[arg(long)]
+    pub echo_last_segment: bool,
+}
+
+#[derive(Args)]
+pub struct ValueLengthArgs {
+    /// 検査するファイル(省略時は--inputのディレクトリ内のすべて)
+    #[arg(value_hint = ValueHint::FilePath)]
+    pub files: Vec<String>,
+    /// filesを省略した場合に検査するディレクトリ
+    #[arg(long, default_value = "./input", value_hint = ValueHint::DirPath)]
+    pub input: String,
+    /// 値の最大文字数(接頭辞ごとの上限は設定ファイルで指定する)
+    #[arg(long)]
+    pub max: Option<usize>,
+    /// 文字数からプレースホルダーを除く
+    #[arg(long)]
+    pub exclude_placeholders: bool,
+}
+
+#[derive(Args)]
+pub struct LengthRatioArgs {
+    /// 翻訳元のファイル(en_us.jsonなど)。https://のURLも指定できる
+    #[arg(long, value_hint = ValueHint::FilePath)]
+    pub source: String,
+    /// 検査するファイル(省略時は--inputのディレクトリ内のすべて。翻訳元のファイル自身は除く)
+    #[arg(value_hint = ValueHint::FilePath)]
+    pub files: Vec<String>,
+    /// filesを省略した場合に検査するディレクトリ
+    #[arg(long, default_value = "./input", value_hint = ValueHint::DirPath)]
+    pub input: String,
+    /// 翻訳元の文字数に対する倍率がこれを超える値を報告する
+    #[arg(long, default_value_t = 2.5)]
+    pub max_ratio: f64,
+    /// 文字数がこれを超える値も報告する
+    #[arg(long)]
+    pub max: Option<usize>,
+    /// 出力の書式
+    #[arg(long, value_enum, default_value = "table")]
+    pub format: ReportFormat,
+    /// 標準出力ではなくこのファイルに書き出す
+    #[arg(long, value_hint = ValueHint::FilePath)]
+    pub output: Option<String>,
+}
+
+#[derive(Args)]
+pub struct VerifyArgs {
+    /// 検査するファイル(省略時は--inputのディレクトリ内のすべて)
+    #[arg(value_hint = ValueHint::FilePath)]
+    pub files: Vec<String>,
+    /// filesを省略した場合に検査するディレクトリ
+    #[arg(long, default_value = "./input", value_hint = ValueHint::DirPath)]
+    pub input: String,
+}
+
+#[derive(Args)]
+pub struct PlaceholdersArgs {
+    /// 集計するファイル(省略時は--inputのディレクトリ内のすべて)
+    #[arg(value_hint = ValueHint::FilePath)]
+    pub files: Vec<String>,
+    /// filesを省略した場合に集計するディレクトリ
+    #[arg(long, default_value = "./input", value_hint = ValueHint::DirPath)]
+    pub input: String,
+    /// 出力の書式
+    #[arg(long, value_enum, default_value = "table")]
+    pub format: ReportFormat,
+    /// 標準出力ではなくこのファイルに書き出す
+    #[arg(long, value_hint = ValueHint::FilePath)]
+    pub output: Option<String>,
+}
+
+#[derive(Args)]
+pub struct GlossaryArgs {
+    /// 集計するファイル(省略時は--inputのディレクトリ内のすべて)
+    #[arg(value_hint = ValueHint::FilePath)]
+    pub files: Vec<String>,
+    /// filesを省略した場合に集計するディレクトリ
+    #[arg(long, default_value = "./input", value_hint = ValueHint::DirPath)]
+    pub input: String,
+    /// 翻訳元のファイル。--targetと同じキーの値を組にする
+    #[arg(long, requires = "target", value_hint = ValueHint::FilePath)]
+    pub source: Option<String>,
+    /// 翻訳先のファイル
+    #[arg(long, requires = "source", value_hint = ValueHint::FilePath)]
+    pub target: Option<String>,
+    /// この文字数より短い値を除く(前後の空白は数えない)
+    #[arg(long, default_value_t = 1)]
+    pub min_length: usize,
+    /// 数字(と小数点・符号・%)だけの値を除く
+    #[arg(long)]
+    pub skip_numeric: bool,
+    /// 標準出力ではなくこのファイルに書き出す
+    #[arg(long, value_hint = ValueHint::FilePath)]
+    pub output: Option<String>,
+}
+
+#[derive(Args)]
+pub struct ValidateMcArgs {
+    /// 検査するJSONファイル(省略時は--inputのディレクトリ内の.jsonすべて)
+    #[arg(value_hint = ValueHint::FilePath)]
+    pub files: Vec<String>,
+    /// filesを省略した場合に検査するディレクトリ
+    #[arg(long, default_value = "./output", value_hint = ValueHint::DirPath)]
+    pub input: String,
+    /// ファイルの大きさの上限(例: 512K, 4M)
+    #[arg(long, value_parser = parse_size, default_value = "4M")]
+    pub max_size: u64,
+}
+
+#[derive(Args)]
+pub struct BadgesArgs {
+    /// 翻訳率の基準にするファイル(en_us.jsonなど)。https://のURLも指定できる
+    #[arg(long, value_hint = ValueHint::FilePath)]
+    pub reference: String,
+    /// 翻訳率を求めるファイル(省略時は--inputのディレクトリ内のすべて。参照のファイル自身は除く)
+    #[arg(value_hint = ValueHint::FilePath)]
+    pub files: Vec<String>,
+    /// filesを省略した場合に使うディレクトリ
+    #[arg(long, default_value = "./output", value_hint = ValueHint::DirPath)]
+    pub input: String,
+    /// バッジ(<ロケール>.json)を書き出すディレクトリ
+    #[arg(long, default_value = "./output/badges", value_hint = ValueHint::DirPath)]
+    pub out_dir: String,
+    /// この翻訳率(%)以上をyellowにする(未満はred)
+    #[arg(long, default_value_t = 50, value_parser = clap::value_parser!(u8).range(0..=100))]
+    pub yellow_at: u8,
+    /// この翻訳率(%)以上をgreenにする
+    #[arg(long, default_value_t = 90, value_parser = clap::value_parser!(u8).range(0..=100))]
+    pub green_at: u8,
+    /// キーの最後の区切りと同じ値も、キーと同じ値として未翻訳に数える
+    #[arg(long)]
+    pub echo_last_segment: bool,
+}
+
+#[derive(Args)]
+pub struct NamespacesArgs {
+    /// 集計するファイル(省略時は--inputのディレクトリ内のすべて)
+    #[arg(value_hint = ValueHint::FilePath)]
+    pub files: Vec<String>,
+    /// filesを省略した場合に集計するディレクトリ
+    #[arg(long, default_value = "./input", value_hint = ValueHint::DirPath)]
+    pub input: String,
+    /// 許可する名前空間(カンマ区切り、例: minecraft,mymod。省略時はすべて許可)
+    #[arg(long, value_delimiter = ',')]
+    pub allowed_namespaces: Vec<String>,
+    /// 許可されていない名前空間の扱い
+    #[arg(long, value_enum, default_value = "warn", requires = "allowed_namespaces")]
+    pub unknown_namespaces: UnknownNamespaces,
+}
+
+#[derive(Args)]
+pub struct ServeArgs {
+    /// 待ち受けるポート
+    #[arg(long, default_value_t = 8080)]
+    pub port: u16,
+    /// 待ち受けるアドレス(既定ではこのPCからの接続のみ受け付ける)
+    #[arg(long, default_value = "127.0.0.1")]
+    pub host: String,
+}
+
+#[derive(Args)]
+pub struct CompletionsArgs {
+    /// 補完スクリプトを生成するシェル
+    pub shell: Shell,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+        Cli::try_parse_from(["json_lang", "convert"].iter().chain(args))
+    }
+
+    #[test]
+    fn duplicates_to_suffix_conflicts_with_duplicate_and_shipping_options() {
+        assert!(parse(&["--duplicates-to-suffix"]).is_ok());
+        for other in [&["--dedup-identical"][..], &["--key-case-duplicates", "first-wins"], &["--merge-into-existing"], &["--in-place"], &["--project", "."], &["--stamp"], &["--stamp-no-time"], &["--minify"], &["--indent", "0"]] {
+            let args: Vec<&str> = std::iter::once("--duplicates-to-suffix").chain(other.iter().copied()).collect();
+            let error = parse(&args).err().unwrap_or_else(|| panic!("{:?} should conflict", other));
+            assert_eq!(error.kind(), clap::error::ErrorKind::ArgumentConflict);
+        }
+    }
+}
@@ -0,0 +1,837 @@
+//! ファイルを扱わない.lang/JSONのテキストの解析と書き出し
+//!
+//! 標準入出力やファイルシステムを使わないため、wasm32-unknown-unknownでもビルドできる。
+//! ファイルの読み込み・保存はバイナリ側(format.rs)で行う
+
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{self, Write};
+use indexmap::IndexMap;
+use serde::de::{self, Deserialize, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde_json::to_writer;
+use clap::ValueEnum;
+use crate::diagnostic::{parse_lang_strict, Diagnostic};
+use crate::i18n::{t, tf};
+
+/// 出力時の書式設定
+#[derive(Clone, Debug)]
+pub struct WriteOptions {
+    /// .lang出力の先頭に書くコメント行(`# `は自動で付く)
+    pub header: Vec<String>,
+    /// .lang出力で、キーの先頭からこの数の区切りが変わるたびに空行と`# 接頭辞`の見出しを入れる
+    pub section_depth: Option<usize>,
+    /// JSON出力の字下げの空白数
+    pub json_indent: usize,
+    /// JSON出力を改行と空白の無い1行にする(json_indentは使わない)
+    pub minify: bool,
+    /// .lang出力のキーと値の区切り文字
+    pub separator: Separator,
+    /// .lang出力で、空白の後の `#` を `\#` と書いて行末コメントと区別する
+    pub inline_comments: bool,
+    /// .lang/JSON出力の改行コード
+    pub line_ending: LineEnding,
+    /// .lang/JSON出力の文字コード
+    pub encoding: OutputEncoding,
+}
+
+/// JSON出力の既定の字下げ
+pub const DEFAULT_JSON_INDENT: usize = 2;
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        WriteOptions { header: Vec::new(), section_depth: None, json_indent: DEFAULT_JSON_INDENT, minify: false, separator: Separator::Equals, inline_comments: false, line_ending: LineEnding::Lf, encoding: OutputEncoding::Utf8 }
+    }
+}
+
+impl WriteOptions {
+    /// この設定で書き出した.langを読み直すときの設定
+    pub fn read_back(&self) -> ReadOptions {
+        ReadOptions { separator: self.separator.into(), inline_comments: self.inline_comments, ..ReadOptions::default() }
+    }
+}
+
+/// 入力の読み込み設定
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReadOptions {
+    /// .langのキーと値の区切り文字
+    pub separator: InputSeparator,
+    /// .langの値の末尾の ` #...` を行末コメントとして取り除き、`\#` を `#` に戻す
+    pub inline_comments: bool,
+    /// JSONの数値と真偽値を文字列にする
+    pub coerce: bool,
+    /// coerceのときのJSONのnullの扱い
+    pub coerce_null: NullPolicy,
+    /// 値が異なる重複を後の値で置き換えず、`key__dup1` のように別のキーとして残す(確認用)
+    pub duplicates_to_suffix: bool,
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        ReadOptions { separator: InputSeparator::Equals, inline_comments: false, coerce: false, coerce_null: NullPolicy::Empty, duplicates_to_suffix: false }
+    }
+}
+
+/// --coerceでのJSONのnullの扱い
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum NullPolicy {
+    /// 空文字列にする
+    Empty,
+    /// 読み飛ばして報告する
+    Skip,
+}
+
+/// 読み込んだ内容
+pub struct Loaded {
+    pub lang_map: IndexMap<String, String>,
+    /// 文字列にできずに読み飛ばしたJSONのキーとその型
+    pub skipped: Vec<(String, &'static str)>,
+    /// 重複したキー(後の値を使っている)
+    pub duplicates: Duplicates,
+}
+
+impl From<IndexMap<String, String>> for Loaded {
+    fn from(lang_map: IndexMap<String, String>) -> Self {
+        Loaded { lang_map, skipped: Vec::new(), duplicates: Duplicates::default() }
+    }
+}
+
+/// 入力の中で重複したキー
+#[derive(Debug, Default)]
+pub struct Duplicates {
+    /// キーも値も同じ重複の件数
+    pub identical: usize,
+    /// 値が異なる重複のキー(最初に重複した順)
+    pub conflicting: Vec<String>,
+    /// duplicates_to_suffixで値が異なる重複を書き出した別のキー(現れた順)
+    pub suffixed: Vec<String>,
+    /// 空のキーが現れた回数(マップには1つしか残らない)
+    pub empty_keys: usize,
+}
+
+/// duplicates_to_suffixで重複に付ける接尾辞(後ろに番号が付く)
+pub const DUPLICATE_SUFFIX: &str = "__dup";
+
+impl Loaded {
+    /// キーと値を追加する。すでにあるキーは後の値で置き換え(duplicates_to_suffixなら別のキーにし)、重複として記録する
+    pub fn insert_entry(&mut self, key: String, value: String, options: &ReadOptions) {
+        self.duplicates.insert(&mut self.lang_map, key, value, options.duplicates_to_suffix);
+    }
+}
+
+impl Duplicates {
+    /// キーと値をマップに入れ、すでにあれば重複として記録する
+    fn insert(&mut self, lang_map: &mut IndexMap<String, String>, key: String, value: String, to_suffix: bool) {
+        if key.is_empty() {
+            self.empty_keys += 1;
+        }
+        let Some(previous) = lang_map.get_mut(&key) else {
+            lang_map.insert(key, value);
+            return;
+        };
+        if *previous == value {
+            self.identical += 1;
+            return;
+        }
+        if to_suffix {
+            self.insert_suffixed(lang_map, &key, value);
+            return;
+        }
+        *previous = value;
+        if !self.conflicting.contains(&key) {
+            self.conflicting.push(key);
+        }
+    }
+
+    /// 重複した値を `key__dupN` の空いている番号に入れる(元のキーの後ろに並べる)。同じ値がすでにあれば重複として数えるだけにする
+    fn insert_suffixed(&mut self, lang_map: &mut IndexMap<String, String>, key: &str, value: String) {
+        let index = lang_map.get_index_of(key).unwrap_or(lang_map.len());
+        let mut number = 1;
+        loop {
+            let suffixed = format!("{}{}{}", key, DUPLICATE_SUFFIX, number);
+            match lang_map.get(&suffixed) {
+                Some(existing) if *existing == value => {
+                    self.identical += 1;
+                    return;
+                }
+                Some(_) => number += 1,
+                None => {
+                    lang_map.shift_insert((index + number).min(lang_map.len()), suffixed.clone(), value);
+                    self.suffixed.push(suffixed);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// 出力の改行コード
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+    /// 実行しているOSの改行コード(WindowsではCRLF、それ以外はLF)
+    Native,
+}
+
+impl LineEnding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+            LineEnding::Native if cfg!(windows) => "\r\n",
+            LineEnding::Native => "\n",
+        }
+    }
+}
+
+/// 出力の文字コード
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OutputEncoding {
+    /// UTF-8(BOMなし)
+    #[value(name = "utf-8")]
+    Utf8,
+    /// 先頭にBOMを付けたUTF-8
+    #[value(name = "utf-8-bom")]
+    Utf8Bom,
+    /// 先頭にBOMを付けたUTF-16(リトルエンディアン)
+    #[value(name = "utf-16le")]
+    Utf16le,
+}
+
+impl OutputEncoding {
+    /// --output-encodingで指定する名前
+    pub fn name(self) -> &'static str {
+        match self {
+            OutputEncoding::Utf8 => "utf-8",
+            OutputEncoding::Utf8Bom => "utf-8-bom",
+            OutputEncoding::Utf16le => "utf-16le",
+        }
+    }
+
+    /// 先頭に書くBOM
+    fn bom(self) -> &'static [u8] {
+        match self {
+            OutputEncoding::Utf8 => b"",
+            OutputEncoding::Utf8Bom => b"\xEF\xBB\xBF",
+            OutputEncoding::Utf16le => b"\xFF\xFE",
+        }
+    }
+}
+
+/// writeが書き出すUTF-8の内容を文字コードに従って書き込む。BOMは先頭に書き、UTF-16はメモリ上に書き出してから変換する。
+/// UTF-8/UTF-16ではすべての文字を表せるため、変換できない文字は無い
+pub fn write_encoded<W: Write>(writer: &mut W, encoding: OutputEncoding, write: impl FnOnce(&mut dyn Write) -> io::Result<()>) -> io::Result<()> {
+    writer.write_all(encoding.bom())?;
+    if encoding != OutputEncoding::Utf16le {
+        return write(writer);
+    }
+    let mut buffer = Vec::new();
+    write(&mut buffer)?;
+    let text = String::from_utf8(buffer).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let bytes: Vec<u8> = text.encode_utf16().flat_map(u16::to_le_bytes).collect();
+    writer.write_all(&bytes)
+}
+
+/// .lang出力のキーと値の区切り文字
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Separator {
+    /// `key=value`
+    #[value(alias = "=")]
+    Equals,
+    /// `key:value`
+    #[value(alias = ":")]
+    Colon,
+    /// キーと値をタブで区切る
+    Tab,
+}
+
+impl Separator {
+    pub fn as_char(self) -> char {
+        match self {
+            Separator::Equals => '=',
+            Separator::Colon => ':',
+            Separator::Tab => '\t',
+        }
+    }
+}
+
+/// .lang入力のキーと値の区切り文字
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum InputSeparator {
+    #[value(alias = "=")]
+    Equals,
+    #[value(alias = ":")]
+    Colon,
+    Tab,
+    /// 行ごとに `=`・`:`・タブのうち最初に現れたものを区切りとみなす
+    Auto,
+}
+
+impl InputSeparator {
+    /// 行を最初に現れた区切り文字でキーと値に分ける。値に区切り文字が含まれていてもそのまま残る
+    pub fn split(self, line: &str) -> Option<(&str, &str)> {
+        match self {
+            InputSeparator::Equals => line.split_once('='),
+            InputSeparator::Colon => line.split_once(':'),
+            InputSeparator::Tab => line.split_once('\t'),
+            InputSeparator::Auto => line.split_once(['=', ':', '\t']),
+        }
+    }
+}
+
+impl From<Separator> for InputSeparator {
+    fn from(separator: Separator) -> Self {
+        match separator {
+            Separator::Equals => InputSeparator::Equals,
+            Separator::Colon => InputSeparator::Colon,
+            Separator::Tab => InputSeparator::Tab,
+        }
+    }
+}
+
+/// キーの見出しに使う接頭辞。最後の区切りは含めない
+fn section_of(key: &str, depth: usize) -> String {
+    let segments: Vec<&str> = key.split('.').collect();
+    let take = depth.min(segments.len().saturating_sub(1));
+    segments[..take].join(".")
+}
+
+/// コメントと空行を保持した.langファイルの1行
+pub enum LangLine {
+    Entry(String, String),
+    Comment(String),
+    Blank,
+}
+
+/// 1行がキーと値の行であれば、前後の空白を除いたキーと値を返す
+pub fn parse_entry_with<'a>(line: &'a str, options: &ReadOptions) -> Option<(&'a str, Cow<'a, str>)> {
+    if line.starts_with('#') {
+        return None;
+    }
+    let (key, value) = options.separator.split(line)?;
+    let value = if options.inline_comments { strip_inline_comment(value) } else { Cow::Borrowed(value.trim()) };
+    Some((key.trim(), remove_carriage_returns(value)))
+}
+
+/// 改行コードの混在などで値の途中に残った `\r` を取り除く
+fn remove_carriage_returns(value: Cow<'_, str>) -> Cow<'_, str> {
+    if value.contains('\r') {
+        Cow::Owned(value.replace('\r', ""))
+    } else {
+        value
+    }
+}
+
+/// 空白の後の `#` から行末までを取り除き、エスケープを戻して前後の空白を除く。
+/// `#` の直前に続く `\` は2つで1つの `\` に戻し、余った1つは `#` のエスケープとみなす(`\#` は `#`、`\\#` は `\#`)。
+/// 取り除いたコメントの内容は保持しない
+fn strip_inline_comment(value: &str) -> Cow<'_, str> {
+    if !value.contains('#') {
+        return Cow::Borrowed(value.trim());
+    }
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+    let mut previous = None;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                let mut backslashes = 1;
+                while chars.next_if_eq(&'\\').is_some() {
+                    backslashes += 1;
+                }
+                if chars.next_if_eq(&'#').is_some() {
+                    out.extend(std::iter::repeat_n('\\', backslashes / 2));
+                    out.push('#');
+                    previous = Some('#');
+                } else {
+                    out.extend(std::iter::repeat_n('\\', backslashes));
+                    previous = Some('\\');
+                }
+                continue;
+            }
+            '#' if previous.is_some_and(char::is_whitespace) => break,
+            c => out.push(c),
+        }
+        previous = Some(c);
+    }
+    Cow::Owned(out.trim().to_string())
+}
+
+/// strip_inline_commentで元の値に戻るよう、`#` の直前に続く `\` を2つずつにし、空白の後の `#` を `\#` にする
+fn escape_inline_comment(value: &str) -> Cow<'_, str> {
+    if !value.contains('#') {
+        return Cow::Borrowed(value);
+    }
+    let mut out = String::with_capacity(value.len() + 1);
+    let mut chars = value.chars().peekable();
+    let mut previous = None;
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            let mut backslashes = 1;
+            while chars.next_if_eq(&'\\').is_some() {
+                backslashes += 1;
+            }
+            let doubled = if chars.peek() == Some(&'#') { backslashes * 2 } else { backslashes };
+            out.extend(std::iter::repeat_n('\\', doubled));
+            previous = Some('\\');
+            continue;
+        }
+        if c == '#' && previous.is_some_and(char::is_whitespace) {
+            out.push('\\');
+        }
+        out.push(c);
+        previous = Some(c);
+    }
+    Cow::Owned(out)
+}
+
+/// .langのテキストをコメント・空行を保持したまま行単位に分解する
+/// 区切り文字を含まない行は読み飛ばす
+pub fn parse_lang_lines(contents: &str) -> Vec<LangLine> {
+    parse_lang_lines_with(contents, &ReadOptions::default())
+}
+
+/// parse_lang_linesを読み込み設定に従って行う
+pub fn parse_lang_lines_with(contents: &str, options: &ReadOptions) -> Vec<LangLine> {
+    let mut lines = Vec::new();
+    for line in contents.lines() {
+        if line.trim().is_empty() {
+            lines.push(LangLine::Blank);
+        } else if line.starts_with('#') {
+            lines.push(LangLine::Comment(line.trim_end_matches('\r').to_string()));
+        } else if let Some((key, value)) = parse_entry_with(line, options) {
+            lines.push(LangLine::Entry(key.to_string(), value.into_owned()));
+        }
+    }
+    lines
+}
+
+/// キーと値だけをマップに読み込む。コメントや空行は保持しないため、行の一覧を作らずに直接マップへ入れる
+pub fn parse_lang_map(contents: &str) -> IndexMap<String, String> {
+    parse_lang_map_with(contents, &ReadOptions::default())
+}
+
+/// parse_lang_mapを読み込み設定に従って行う
+pub fn parse_lang_map_with(contents: &str, options: &ReadOptions) -> IndexMap<String, String> {
+    parse_lang_loaded_with(contents, options).lang_map
+}
+
+/// parse_lang_map_withと同じ規則で読み、重複したキーも返す
+pub fn parse_lang_loaded_with(contents: &str, options: &ReadOptions) -> Loaded {
+    // 行数を上限として先に確保し、読み込み中の再確保を避ける
+    let line_count = contents.bytes().filter(|b| *b == b'\n').count() + 1;
+    let mut loaded = Loaded::from(IndexMap::with_capacity(line_count));
+    for (key, value) in contents.lines().filter_map(|line| parse_entry_with(line, options)) {
+        loaded.insert_entry(key.to_string(), value.into_owned(), options);
+    }
+    loaded
+}
+
+/// JSONをマップとして読み、読み飛ばしたキーとその型、トップレベルの値の型名も返す
+pub fn read_json_map<R: io::Read>(reader: R, options: &ReadOptions) -> serde_json::Result<(Loaded, &'static str)> {
+    let mut lang_map = IndexMap::new();
+    let mut skipped = IndexMap::new();
+    let mut duplicates = Duplicates::default();
+    let top_level = read_json_entries(reader, |key, value| {
+        let type_name = value.type_name();
+        match value.into_string(options) {
+            Some(value) => {
+                skipped.shift_remove(&key);
+                duplicates.insert(&mut lang_map, key, value, options.duplicates_to_suffix);
+            }
+            // 重複したキーは後の値が優先される。文字列にしない値で上書きされた場合は取り除く
+            None => {
+                lang_map.shift_remove(&key);
+                skipped.insert(key, type_name);
+            }
+        }
+    })?;
+    Ok((Loaded { lang_map, skipped: skipped.into_iter().collect(), duplicates }, top_level))
+}
+
+/// トップレベルのオブジェクトのキーと値をファイル内の順に渡す
+/// 戻り値はトップレベルの値のJSONの型名(オブジェクトなら"object")
+pub fn read_json_entries<R: io::Read>(reader: R, on_entry: impl FnMut(String, JsonValue)) -> serde_json::Result<&'static str> {
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    let top_level = deserializer.deserialize_any(JsonEntries(on_entry))?;
+    deserializer.end()?;
+    Ok(top_level)
+}
+
+/// トップレベルのオブジェクトの各要素をコールバックに渡すVisitor
+struct JsonEntries<F>(F);
+
+impl<'de, F: FnMut(String, JsonValue)> Visitor<'de> for JsonEntries<F> {
+    type Value = &'static str;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(&t("codec.expecting_value"))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(mut self, mut access: A) -> Result<&'static str, A::Error> {
+        while let Some(key) = access.next_key::<String>()? {
+            let value = access.next_value()?;
+            (self.0)(key, value);
+        }
+        Ok("object")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut access: A) -> Result<&'static str, A::Error> {
+        while access.next_element::<IgnoredAny>()?.is_some() {}
+        Ok("array")
+    }
+
+    fn visit_str<E: de::Error>(self, _: &str) -> Result<&'static str, E> {
+        Ok("string")
+    }
+
+    fn visit_bool<E: de::Error>(self, _: bool) -> Result<&'static str, E> {
+        Ok("boolean")
+    }
+
+    fn visit_i64<E: de::Error>(self, _: i64) -> Result<&'static str, E> {
+        Ok("number")
+    }
+
+    fn visit_u64<E: de::Error>(self, _: u64) -> Result<&'static str, E> {
+        Ok("number")
+    }
+
+    fn visit_f64<E: de::Error>(self, _: f64) -> Result<&'static str, E> {
+        Ok("number")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<&'static str, E> {
+        Ok("null")
+    }
+}
+
+/// トップレベルのオブジェクトの値。オブジェクトと配列は中身を読み飛ばす
+pub enum JsonValue {
+    String(String),
+    /// JSONに書かれていたのと同じ表記の数値
+    Number(String),
+    Bool(bool),
+    Null,
+    Object,
+    Array,
+}
+
+impl JsonValue {
+    /// 報告に使うJSONの型名
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            JsonValue::String(_) => "string",
+            JsonValue::Number(_) => "number",
+            JsonValue::Bool(_) => "boolean",
+            JsonValue::Null => "null",
+            JsonValue::Object => "object",
+            JsonValue::Array => "array",
+        }
+    }
+
+    /// 読み込み設定に従って文字列にする。文字列にしない値はNone
+    fn into_string(self, options: &ReadOptions) -> Option<String> {
+        match self {
+            JsonValue::String(value) => Some(value),
+            JsonValue::Number(value) if options.coerce => Some(value),
+            JsonValue::Bool(value) if options.coerce => Some(value.to_string()),
+            JsonValue::Null if options.coerce && options.coerce_null == NullPolicy::Empty => Some(String::new()),
+            _ => None,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for JsonValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ValueVisitor;
+
+        impl<'de> Visitor<'de> for ValueVisitor {
+            type Value = JsonValue;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str(&t("codec.expecting_value"))
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<JsonValue, E> {
+                Ok(JsonValue::String(value.to_string()))
+            }
+
+            fn visit_string<E: de::Error>(self, value: String) -> Result<JsonValue, E> {
+                Ok(JsonValue::String(value))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<JsonValue, A::Error> {
+                while access.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {}
+                Ok(JsonValue::Object)
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut access: A) -> Result<JsonValue, A::Error> {
+                while access.next_element::<IgnoredAny>()?.is_some() {}
+                Ok(JsonValue::Array)
+            }
+
+            fn visit_bool<E: de::Error>(self, value: bool) -> Result<JsonValue, E> {
+                Ok(JsonValue::Bool(value))
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<JsonValue, E> {
+                Ok(JsonValue::Number(value.to_string()))
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<JsonValue, E> {
+                Ok(JsonValue::Number(value.to_string()))
+            }
+
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<JsonValue, E> {
+                // serde_jsonの表記に合わせる(1.0は"1.0"のまま)
+                Ok(JsonValue::Number(serde_json::Number::from_f64(value).map_or_else(|| value.to_string(), |number| number.to_string())))
+            }
+
+            fn visit_unit<E: de::Error>(self) -> Result<JsonValue, E> {
+                Ok(JsonValue::Null)
+            }
+        }
+
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+/// 整形したJSONを書き込む
+/// マップ全体を中間表現にせず、1件ずつ書き出す(serde_jsonの整形出力と同じ形式)
+pub fn write_json<W: Write>(writer: &mut W, lang_map: &IndexMap<String, String>, options: &WriteOptions) -> io::Result<()> {
+    if lang_map.is_empty() {
+        return writer.write_all(b"{}");
+    }
+    let (indent, newline, colon) = if options.minify { (String::new(), "", ":") } else { (" ".repeat(options.json_indent), options.line_ending.as_str(), ": ") };
+    writer.write_all(b"{")?;
+    for (index, (key, value)) in lang_map.iter().enumerate() {
+        if index > 0 {
+            writer.write_all(b",")?;
+        }
+        writer.write_all(newline.as_bytes())?;
+        writer.write_all(indent.as_bytes())?;
+        to_writer(&mut *writer, key)?;
+        writer.write_all(colon.as_bytes())?;
+        to_writer(&mut *writer, value)?;
+    }
+    writer.write_all(newline.as_bytes())?;
+    writer.write_all(b"}")
+}
+
+/// 書式設定に従って.lang形式で書き込む
+pub fn write_lang<W: Write>(writer: &mut W, lang_map: &IndexMap<String, String>, options: &WriteOptions) -> io::Result<()> {
+    let newline = options.line_ending.as_str();
+    for line in &options.header {
+        write!(writer, "# {}{}", line, newline)?;
+    }
+    let mut current_section: Option<String> = None;
+    for (key, value) in lang_map {
+        if let Some(depth) = options.section_depth {
+            let section = section_of(key, depth);
+            if current_section.as_ref() != Some(&section) {
+                if current_section.is_some() {
+                    write!(writer, "{}", newline)?;
+                }
+                if !section.is_empty() {
+                    write!(writer, "# {}{}", section, newline)?;
+                }
+                current_section = Some(section);
+            }
+        }
+        let value = if options.inline_comments { escape_inline_comment(value) } else { Cow::Borrowed(value.as_str()) };
+        write!(writer, "{}{}{}{}", key, options.separator.as_char(), value, newline)?;
+    }
+    Ok(())
+}
+
+/// 行単位のデータを.lang形式で書き込む
+pub fn write_lang_lines<W: Write>(writer: &mut W, lines: &[LangLine], options: &WriteOptions) -> io::Result<()> {
+    let newline = options.line_ending.as_str();
+    for line in &options.header {
+        write!(writer, "# {}{}", line, newline)?;
+    }
+    for line in lines {
+        match line {
+            LangLine::Entry(key, value) => {
+                let value = if options.inline_comments { escape_inline_comment(value) } else { Cow::Borrowed(value.as_str()) };
+                write!(writer, "{}{}{}{}", key, options.separator.as_char(), value, newline)?;
+            }
+            LangLine::Comment(comment) => write!(writer, "{}{}", comment, newline)?,
+            LangLine::Blank => write!(writer, "{}", newline)?,
+        }
+    }
+    Ok(())
+}
+
+/// .langのテキストをJSONのテキストに変換する。エラーの問題(区切り文字の無い行など)があれば、その位置を示すエラー
+pub fn lang_to_json(text: &str, options: &WriteOptions) -> Result<String, String> {
+    let lang_map = parse_lang_strict(text.as_bytes()).map_err(|errors| errors.iter().map(Diagnostic::to_string).collect::<Vec<_>>().join("\n"))?;
+    let mut buffer = Vec::new();
+    write_json(&mut buffer, &lang_map, options).map_err(|e| e.to_string())?;
+    String::from_utf8(buffer).map_err(|e| e.to_string())
+}
+
+/// JSONのテキストを.langのテキストに変換する。文字列以外の値は読み飛ばす
+pub fn json_to_lang(text: &str, options: &WriteOptions) -> Result<String, String> {
+    let (loaded, top_level) = read_json_map(text.as_bytes(), &ReadOptions::default()).map_err(|e| tf("codec.json_parse_failed", &[&e]))?;
+    if top_level != "object" {
+        return Err(tf("io.json_not_object", &[&top_level]));
+    }
+    let mut buffer = Vec::new();
+    write_lang(&mut buffer, &loaded.lang_map, options).map_err(|e| e.to_string())?;
+    String::from_utf8(buffer).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(pairs: &[(&str, &str)]) -> IndexMap<String, String> {
+        pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+    }
+
+    fn inline() -> ReadOptions {
+        ReadOptions { inline_comments: true, ..ReadOptions::default() }
+    }
+
+    fn value_of(line: &str, options: &ReadOptions) -> Option<String> {
+        parse_entry_with(line, options).map(|(_, value)| value.into_owned())
+    }
+
+    #[test]
+    fn inline_comment_after_whitespace_is_stripped() {
+        assert_eq!(value_of("a=Hello # note", &inline()).as_deref(), Some("Hello"));
+        assert_eq!(value_of("a=Hello\t#note", &inline()).as_deref(), Some("Hello"));
+    }
+
+    #[test]
+    fn hash_without_preceding_whitespace_is_kept() {
+        assert_eq!(value_of("a=#1 item#2", &inline()).as_deref(), Some("#1 item#2"));
+    }
+
+    #[test]
+    fn escaped_hash_is_unescaped_and_kept() {
+        assert_eq!(value_of("a=Issue \\#12 # note", &inline()).as_deref(), Some("Issue #12"));
+        assert_eq!(value_of("a=back\\slash", &inline()).as_deref(), Some("back\\slash"));
+    }
+
+    #[test]
+    fn inline_comments_are_kept_by_default() {
+        assert_eq!(value_of("a=Hello # note", &ReadOptions::default()).as_deref(), Some("Hello # note"));
+        assert_eq!(value_of("a=Issue \\#12", &ReadOptions::default()).as_deref(), Some("Issue \\#12"));
+    }
+
+    #[test]
+    fn comment_lines_are_not_entries() {
+        assert!(parse_entry_with("# a=b", &inline()).is_none());
+    }
+
+    #[test]
+    fn inline_comment_escaping_round_trips() {
+        let lang_map = map(&[("a", "Issue #12"), ("b", "C# and F#"), ("c", "50 # 2"), ("d", "#start")]);
+        let options = WriteOptions { inline_comments: true, ..WriteOptions::default() };
+        let mut buffer = Vec::new();
+        write_lang(&mut buffer, &lang_map, &options).unwrap();
+        let text = String::from_utf8(buffer).unwrap();
+        assert!(text.contains("a=Issue \\#12"));
+        assert!(text.contains("b=C# and F#"));
+        assert_eq!(parse_lang_map_with(&text, &options.read_back()), lang_map);
+    }
+
+    #[test]
+    fn backslashes_before_hash_round_trip() {
+        let lang_map = map(&[("a", "a\\#b"), ("b", "x \\#y"), ("c", "\\\\#"), ("d", "dir\\ #tag"), ("e", "C:\\path\\n")]);
+        let options = WriteOptions { inline_comments: true, ..WriteOptions::default() };
+        let mut buffer = Vec::new();
+        write_lang(&mut buffer, &lang_map, &options).unwrap();
+        let text = String::from_utf8(buffer).unwrap();
+        assert!(text.contains("a=a\\\\#b"));
+        assert!(text.contains("e=C:\\path\\n"));
+        assert_eq!(parse_lang_map_with(&text, &options.read_back()), lang_map);
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn inline_comment_escaping_round_trips_any_value(parts in proptest::collection::vec(proptest::sample::select(vec!["#", "\\", " ", "a"]), 0..10)) {
+            let value = parts.concat().trim().to_string();
+            let lang_map = map(&[("k", value.as_str())]);
+            let options = WriteOptions { inline_comments: true, ..WriteOptions::default() };
+            let mut buffer = Vec::new();
+            write_lang(&mut buffer, &lang_map, &options).unwrap();
+            proptest::prop_assert_eq!(parse_lang_map_with(&String::from_utf8(buffer).unwrap(), &options.read_back()), lang_map);
+        }
+    }
+
+    #[test]
+    fn escaped_backslash_before_hash_is_unescaped() {
+        assert_eq!(value_of("a=a\\\\#b", &inline()).as_deref(), Some("a\\#b"));
+        assert_eq!(value_of("a=x \\\\# y # note", &inline()).as_deref(), Some("x \\# y"));
+        assert_eq!(value_of("a=x\\\\\\#", &inline()).as_deref(), Some("x\\#"));
+    }
+
+    #[test]
+    fn lang_lines_keep_comments_and_blanks() {
+        let lines = parse_lang_lines("# head\r\n\na=1\nno separator\n");
+        assert_eq!(lines.len(), 3);
+        assert!(matches!(&lines[0], LangLine::Comment(comment) if comment == "# head"));
+        assert!(matches!(lines[1], LangLine::Blank));
+        assert!(matches!(&lines[2], LangLine::Entry(key, value) if key == "a" && value == "1"));
+    }
+
+    #[test]
+    fn lang_map_trims_and_keeps_later_duplicates() {
+        let loaded = parse_lang_loaded_with(" a = 1 \r\nb=x=y\na=2\n", &ReadOptions::default());
+        assert_eq!(loaded.lang_map, map(&[("a", "2"), ("b", "x=y")]));
+        assert_eq!(loaded.duplicates.conflicting, vec!["a".to_string()]);
+    }
+
+    #[test]
+    fn minify_writes_json_on_one_line() {
+        let options = WriteOptions { minify: true, json_indent: 4, ..WriteOptions::default() };
+        let mut buffer = Vec::new();
+        write_json(&mut buffer, &map(&[("a", "1"), ("b", "x y")]), &options).unwrap();
+        assert_eq!(String::from_utf8(buffer).unwrap(), r#"{"a":"1","b":"x y"}"#);
+    }
+
+    #[test]
+    fn loaders_count_every_empty_key() {
+        let loaded = parse_lang_loaded_with("=a\nk=v\n=b\n=b\n", &ReadOptions::default());
+        assert_eq!(loaded.lang_map.len(), 2);
+        assert_eq!(loaded.duplicates.empty_keys, 3);
+        let (loaded, _) = read_json_map(r#"{"": "a", "k": "v", "": "b"}"#.as_bytes(), &ReadOptions::default()).unwrap();
+        assert_eq!(loaded.duplicates.empty_keys, 2);
+    }
+
+    #[test]
+    fn lang_to_json_uses_pretty_output() {
+        let json = lang_to_json("b=2\na=\"1\"\n", &WriteOptions::default()).unwrap();
+        assert_eq!(json, "{\n  \"b\": \"2\",\n  \"a\": \"\\\"1\\\"\"\n}");
+        assert_eq!(lang_to_json("", &WriteOptions::default()).unwrap(), "{}");
+    }
+
+    #[test]
+    fn lang_to_json_reports_line_errors() {
+        let error = lang_to_json("a=1\nbroken\n", &WriteOptions::default()).unwrap_err();
+        assert!(error.contains('2'), "{}", error);
+    }
+
+    #[test]
+    fn json_to_lang_skips_non_string_values() {
+        let lang = json_to_lang(r#"{"a": "1", "n": 2, "o": {}, "b": "x=y"}"#, &WriteOptions::default()).unwrap();
+        assert_eq!(lang, "a=1\nb=x=y\n");
+    }
+
+    #[test]
+    fn json_to_lang_rejects_bad_input() {
+        assert!(json_to_lang("[]", &WriteOptions::default()).unwrap_err().contains("array"));
+        assert!(json_to_lang("{", &WriteOptions::default()).is_err());
+    }
+
+    #[test]
+    fn utf16_output_has_bom_and_little_endian_units() {
+        let mut buffer = Vec::new();
+        write_encoded(&mut buffer, OutputEncoding::Utf16le, |out| out.write_all("aあ".as_bytes())).unwrap();
+        assert_eq!(buffer, [0xFF, 0xFE, b'a', 0x00, 0x42, 0x30]);
+    }
+}
@@ -0,0 +1,105 @@
+//! .langのコメントを別ファイル(`<名前>.comments.json`)に書き出し、.langに戻すときに付け直す
+//!
+//! 形式: `{"before": {"キー": ["# コメント", ""]}, "trailing": ["# 末尾のコメント"]}`
+//! - `before` はキーの直前(前のキーとの間)にあるコメント行と空行(`""`)を、ファイル内の順にそのまま持つ。
+//!   空行で区切られた複数のコメントのまとまりも、同じキーの前にあれば1つの一覧になる
+//! - 最後のキーより後ろのコメントと空行は `trailing` に入れ、.lang出力の末尾に書く
+//! - 付け直すときに出力に無いキー(--key-excludeで除いた、接頭辞の加工で名前が変わったなど)のコメントは、
+//!   `trailing` の前にまとめて書く
+
+use std::fs;
+use std::path::Path;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use crate::format::LangLine;
+use crate::i18n::tf;
+
+/// コメントのファイル名の末尾
+pub const SIDECAR_SUFFIX: &str = ".comments.json";
+
+/// .langファイルのコメントと空行
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Comments {
+    pub before: IndexMap<String, Vec<String>>,
+    pub trailing: Vec<String>,
+}
+
+/// コメントのファイルか
+pub fn is_sidecar(path: &Path) -> bool {
+    path.file_name().and_then(|name| name.to_str()).is_some_and(|name| name.ends_with(SIDECAR_SUFFIX))
+}
+
+/// .lang/.jsonファイルに対応するコメントのファイルのパス(拡張子を置き換える)
+pub fn sidecar_path(path: &str) -> String {
+    let stem = Path::new(path).extension().and_then(|ext| ext.to_str()).map_or(path, |ext| &path[..path.len() - ext.len() - 1]);
+    format!("{}{}", stem, SIDECAR_SUFFIX)
+}
+
+impl Comments {
+    /// .langの行からコメントと空行を取り出す
+    pub fn from_lines(lines: Vec<LangLine>) -> Comments {
+        let mut comments = Comments::default();
+        let mut pending = Vec::new();
+        for line in lines {
+            match line {
+                LangLine::Entry(key, _) => {
+                    if !pending.is_empty() {
+                        comments.before.entry(key).or_default().append(&mut pending);
+                    }
+                }
+                LangLine::Comment(comment) => pending.push(comment),
+                LangLine::Blank => pending.push(String::new()),
+            }
+        }
+        comments.trailing = pending;
+        comments
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.before.is_empty() && self.trailing.is_empty()
+    }
+
+    /// コメントのファイルを読み込む。ファイルが無ければNone
+    pub fn load(path: &str) -> Result<Option<Comments>, String> {
+        if !Path::new(path).exists() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(path).map_err(|_| tf("io.read_failed", &[&path]))?;
+        serde_json::from_str(&contents).map(Some).map_err(|e| tf("io.comments_invalid", &[&path, &e]))
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, json).map_err(|_| tf("io.write_failed", &[&path]))
+    }
+
+    /// コメントを付け直した.langの行を組み立てる
+    pub fn attach(&self, lang_map: &IndexMap<String, String>) -> Vec<LangLine> {
+        let mut lines = Vec::new();
+        for (key, value) in lang_map {
+            if let Some(before) = self.before.get(key) {
+                lines.extend(before.iter().map(String::as_str).map(to_line));
+            }
+            lines.push(LangLine::Entry(key.clone(), value.clone()));
+        }
+        for (key, before) in &self.before {
+            if !lang_map.contains_key(key) {
+                lines.extend(before.iter().map(String::as_str).map(to_line));
+            }
+        }
+        lines.extend(self.trailing.iter().map(String::as_str).map(to_line));
+        lines
+    }
+}
+
+/// 空文字列は空行、それ以外はコメント行にする(`#` で始まらなければ `# ` を付ける)
+fn to_line(line: &str) -> LangLine {
+    if line.is_empty() {
+        LangLine::Blank
+    } else if line.starts_with('#') {
+        LangLine::Comment(line.to_string())
+    } else {
+        LangLine::Comment(format!("# {}", line))
+    }
+}
@@ -0,0 +1,107 @@
+use std::path::Path;
+use indexmap::IndexMap;
+use clap::ValueEnum;
+use crate::cli::ConcatArgs;
+use crate::format::{load_lang_lines, save_lang_lines, Format, LangLine};
+use crate::i18n::{t, tf};
+use json_lang::LangMap;
+
+/// 同じキーが複数回現れたときの扱い
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum DuplicatePolicy {
+    /// 重複を報告して出力しない
+    Error,
+    /// 最初に現れた値を使う
+    FirstWins,
+    /// 最後に現れた値を使う(位置は最初に現れた場所のまま)
+    LastWins,
+}
+
+/// 値を読んだファイルが前のキーと変わるところに `# from: ファイル` のコメントを入れる。行の順は変えない
+fn with_provenance(lines: Vec<LangLine>, origins: &IndexMap<String, &str>) -> Vec<LangLine> {
+    let mut annotated = Vec::with_capacity(lines.len());
+    let mut current = None;
+    for line in lines {
+        if let LangLine::Entry(key, _) = &line {
+            let origin = origins.get(key).copied();
+            if origin != current {
+                annotated.push(LangLine::Comment(format!("# from: {}", origin.unwrap_or_default())));
+                current = origin;
+            }
+        }
+        annotated.push(line);
+    }
+    annotated
+}
+
+/// concatサブコマンドの実行
+pub fn run_concat(args: &ConcatArgs) -> Result<bool, String> {
+    let output_format = Format::from_path(Path::new(&args.output))
+        .ok_or_else(|| tf("io.unsupported_extension", &[&args.output]))?;
+    let preserve = args.preserve_comments && output_format == Format::Lang;
+    if args.provenance && output_format != Format::Lang {
+        return Err(t("concat.provenance_lang_only"));
+    }
+
+    let mut lang_map = LangMap::default();
+    let mut sources: IndexMap<String, Vec<&str>> = IndexMap::new();
+    // キーごとに、採用した値を読んだファイル
+    let mut origins: IndexMap<String, &str> = IndexMap::new();
+    let mut lines = Vec::new();
+    for part in &args.inputs {
+        let part_lines = match Format::from_path(Path::new(part)) {
+            Some(Format::Lang) => load_lang_lines(part)?,
+            Some(Format::Json) => Format::Json.load(part)?.into_iter().map(|(k, v)| LangLine::Entry(k, v)).collect(),
+            None => return Err(tf("io.unsupported_extension", &[part])),
+        };
+        for line in part_lines {
+            match line {
+                LangLine::Entry(key, value) => {
+                    sources.entry(key.clone()).or_default().push(part);
+                    if let Some(existing) = lang_map.get_mut(&key) {
+                        if args.duplicates == DuplicatePolicy::LastWins {
+                            *existing = value;
+                            origins.insert(key, part);
+                        }
+                        continue;
+                    }
+                    origins.insert(key.clone(), part);
+                    lang_map.insert(key.clone(), value);
+                    lines.push(LangLine::Entry(key, String::new()));
+                }
+                other => lines.push(other),
+            }
+        }
+    }
+
+    let duplicates: Vec<(&String, &Vec<&str>)> = sources.iter().filter(|(_, parts)| parts.len() > 1).collect();
+    if !duplicates.is_empty() {
+        println!("{}", tf("concat.duplicates", &[&duplicates.len()]));
+        for (key, parts) in &duplicates {
+            println!("- {} ({})", key, parts.join(", "));
+        }
+        if args.duplicates == DuplicatePolicy::Error {
+            return Err(t("concat.duplicates_rejected"));
+        }
+    }
+
+    if preserve || args.provenance {
+        if preserve {
+            for line in lines.iter_mut() {
+                if let LangLine::Entry(key, value) = line {
+                    *value = lang_map[key.as_str()].clone();
+                }
+            }
+        } else {
+            lines = lang_map.iter().map(|(key, value)| LangLine::Entry(key.clone(), value.clone())).collect();
+        }
+        if args.provenance {
+            lines = with_provenance(lines, &origins);
+        }
+        save_lang_lines(&args.output, &lines)?;
+    } else {
+        output_format.save(&args.output, &lang_map)?;
+    }
+    println!("{}", tf("io.wrote_keys", &[&args.inputs.join(" + "), &args.output, &lang_map.len()]));
+    Ok(true)
+}
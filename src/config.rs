@@ -0,0 +1,65 @@
+use std::fs;
+use std::path::Path;
+use indexmap::IndexMap;
+use serde::Deserialize;
+use crate::i18n::{t, tf, Language};
+
+/// --configを省略したときに読み込む設定ファイル
+pub const DEFAULT_CONFIG_PATH: &str = "./json_lang.toml";
+
+/// 設定ファイル(json_lang.toml)の内容
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// メッセージの言語(ja/en)
+    pub lang: Option<Language>,
+    /// [convert] セクション。convert/checkのオプション名と値(コマンドラインと環境変数の次に優先する)
+    pub convert: toml::Table,
+    /// [profile.名前] セクション。--profileで選んだものを[convert]に重ねる
+    pub profile: IndexMap<String, toml::Table>,
+    pub lint: LintConfig,
+}
+
+impl Config {
+    /// [convert]に--profileで選んだプロファイルを重ねたオプション
+    pub fn convert_table(&self, profile: Option<&str>) -> Result<toml::Table, String> {
+        let mut table = self.convert.clone();
+        if let Some(name) = profile {
+            let Some(overrides) = self.profile.get(name) else {
+                let available = if self.profile.is_empty() {
+                    t("config.no_profiles")
+                } else {
+                    self.profile.keys().map(String::as_str).collect::<Vec<_>>().join(", ")
+                };
+                return Err(tf("config.unknown_profile", &[&name, &available]));
+            };
+            table.extend(overrides.clone());
+        }
+        Ok(table)
+    }
+}
+
+/// [lint] セクション
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LintConfig {
+    /// 値の最大文字数
+    pub max_value_length: Option<usize>,
+    /// キーの接頭辞ごとの最大文字数(最も長く一致する接頭辞を優先する)
+    pub max_value_length_prefixes: IndexMap<String, usize>,
+    /// 文字数からプレースホルダーを除く
+    pub exclude_placeholders: bool,
+    /// キーの検査で `[A-Za-z0-9._-]` のほかに許可する文字
+    pub allowed_key_chars: String,
+}
+
+/// 設定ファイルを読み込む。パスを省略した場合、既定のファイルが無ければ既定値を使う
+pub fn load_config(path: Option<&str>) -> Result<Config, String> {
+    let path = match path {
+        Some(path) => path,
+        None if Path::new(DEFAULT_CONFIG_PATH).is_file() => DEFAULT_CONFIG_PATH,
+        None => return Ok(Config::default()),
+    };
+    let contents = fs::read_to_string(path).map_err(|_| tf("io.read_failed", &[&path]))?;
+    toml::from_str(&contents).map_err(|e| tf("config.parse_failed", &[&path, &e]))
+}
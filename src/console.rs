@@ -0,0 +1,79 @@
+//! 一括変換の進み具合の表示
+//!
+//! process_files_withにObserverを渡すと、ファイルごとの開始・読み込み・完了・省略・失敗と、全体の完了を受け取れる。
+//! 通知の型はライブラリ(json_lang::events)にあり、ここではこのツールの計画と結果の型で使う。
+//! コマンドラインの表示と--trace-formatのログ(Console)も同じ通知だけを使って出力する
+
+use std::io::{self, IsTerminal, Write};
+use crate::convert::{ConvertOptions, Job, Summary};
+use crate::i18n::tf;
+use json_lang::events;
+
+/// 一括変換の通知(ライブラリの通知にこのツールのJobとSummaryを使う)
+pub type Event<'a> = events::Event<'a, Job, Summary>;
+
+/// 一括変換の通知を受け取る。ライブラリのObserverを実装した型やクロージャーはすべてこれを満たす
+pub trait Observer: events::Observer<Job, Summary> {}
+
+impl<T: events::Observer<Job, Summary> + ?Sized> Observer for T {}
+
+/// コマンドラインと対話メニューでの表示
+pub struct Console<'a> {
+    options: &'a ConvertOptions,
+    /// --only-failuresで進み具合の行を表示している(次の表示の前に消す)
+    progress_shown: bool,
+}
+
+impl<'a> Console<'a> {
+    pub fn new(options: &'a ConvertOptions) -> Console<'a> {
+        Console { options, progress_shown: false }
+    }
+
+    /// 進み具合の行を消す
+    fn clear_progress(&mut self) {
+        if self.progress_shown {
+            print!("\r\x1b[2K");
+            self.progress_shown = false;
+        }
+    }
+}
+
+impl events::Observer<Job, Summary> for Console<'_> {
+    fn on_event(&mut self, event: Event) {
+        let only_failures = self.options.only_failures;
+        match event {
+            // 出力をファイルに書き出す場合は、書き換える行を挟まない
+            Event::FileStarted { job, index, total } if only_failures && io::stdout().is_terminal() => {
+                tracing::debug!(path = %job.input_path, index, total, "file started");
+                print!("\r{}", tf("convert.progress", &[&(index + 1), &total]));
+                let _ = io::stdout().flush();
+                self.progress_shown = true;
+            }
+            Event::FileLoaded { .. } if only_failures => {}
+            Event::FileLoaded { job, notes } => {
+                println!("{} => {}", job.input_path, job.output_path);
+                for note in notes {
+                    println!("  - {}", note);
+                }
+            }
+            Event::FileSkipped { job, reason } => {
+                self.clear_progress();
+                println!("{} => {} ({})", job.input_path, job.output_path, reason);
+            }
+            Event::FileFailed { job, error } if only_failures => {
+                tracing::warn!(path = %job.input_path, error, "file failed");
+                self.clear_progress();
+                println!("{}: {}", job.input_path, error);
+            }
+            Event::BatchCompleted { summary } => {
+                self.clear_progress();
+                summary.print(self.options);
+            }
+            // 以下は--trace-formatのログにだけ書く。失敗は最後にまとめて表示する
+            Event::BatchStarted { total } => tracing::info!(total, "batch started"),
+            Event::FileStarted { job, index, total } => tracing::debug!(path = %job.input_path, index, total, "file started"),
+            Event::FileCompleted { job, keys } => tracing::info!(path = %job.input_path, keys, "file completed"),
+            Event::FileFailed { job, error } => tracing::warn!(path = %job.input_path, error, "file failed"),
+        }
+    }
+}
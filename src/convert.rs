@@ -0,0 +1,1489 @@
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use indexmap::IndexMap;
+use clap::ValueEnum;
+use serde::Serialize;
+use crate::cache::{self, Cache};
+use crate::check::{keys, trimmed};
+use crate::cancel;
+use crate::cleanup::{self, CleanScope};
+use crate::comments::{self, Comments};
+use crate::concat::DuplicatePolicy;
+use crate::console::{Console, Event, Observer};
+use crate::existing::{self, ExistingPolicy};
+use crate::export::{self, Export};
+use crate::ext_map::ExtMap;
+use crate::fallback;
+use crate::filter::FileFilter;
+use crate::format::{self, Format, ReadOptions, WriteOptions};
+use crate::i18n::{t, tf};
+use crate::manifest::{self, ManifestFormat};
+use crate::output_name::{self, NameCollisions, OutputName};
+use crate::report::{self, FileRow, FileStatus, RetriedWrite};
+use crate::route::{self, Route};
+use crate::run_log;
+use crate::schema::Schema;
+use crate::size_guard::{self, LargeFiles};
+use crate::sniff;
+use crate::staging::{Staging, Transaction, STAGING_DIR_NAME};
+use crate::timestamp;
+use crate::timing::{self, FileTiming};
+use crate::transform;
+use crate::transform::{external, Applied};
+use crate::transform::empty::EmptyPolicy;
+use crate::transform::escape::Escape;
+use crate::transform::formatting::FormattingCodes;
+use crate::transform::key_case::KeyCase;
+use crate::transform::key_map::{KeyMap, KeyMapDirection, UnmappedKeys};
+use crate::transform::key_filter::KeyFilter;
+use crate::transform::normalize::Normalization;
+use crate::transform::placeholder::PlaceholderStyle;
+use crate::transform::sort::{self, SortOrder};
+use crate::transform::vars::{MissingVars, Vars};
+
+/// --fail-fast/--max-failuresで中断したときの終了コード(最後まで処理して失敗があった場合は1)
+pub const EXIT_ABORTED: i32 = 3;
+
+/// --max-filesの既定値
+pub const DEFAULT_MAX_FILES: usize = 100_000;
+
+/// 変換方向
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Mode {
+    /// .lang => JSON
+    Lang2json,
+    /// JSON => .lang
+    Json2lang,
+    /// 両方の変換
+    All,
+}
+
+impl Mode {
+    /// 設定ファイルに保存した番号(1〜3)から変換方向を得る
+    pub fn from_number(number: u8) -> Option<Mode> {
+        match number {
+            1 => Some(Mode::Lang2json),
+            2 => Some(Mode::Json2lang),
+            3 => Some(Mode::All),
+            _ => None,
+        }
+    }
+
+    /// 設定ファイルに保存する番号
+    pub fn number(self) -> u8 {
+        match self {
+            Mode::Lang2json => 1,
+            Mode::Json2lang => 2,
+            Mode::All => 3,
+        }
+    }
+
+    /// 変換元と変換先の形式の組に当たる変換方向。同じ形式どうしなどの変換できない組ではNone
+    pub fn from_formats(source: Format, target: Format) -> Option<Mode> {
+        match (source, target) {
+            (Format::Lang, Format::Json) => Some(Mode::Lang2json),
+            (Format::Json, Format::Lang) => Some(Mode::Json2lang),
+            _ => None,
+        }
+    }
+
+    /// この変換方向で入力として扱う形式か
+    fn accepts(self, format: Format) -> bool {
+        match self {
+            Mode::Lang2json => format == Format::Lang,
+            Mode::Json2lang => format == Format::Json,
+            Mode::All => true,
+        }
+    }
+}
+
+/// .lang出力の先頭に書く生成情報(--stamp/--stamp-no-time)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stamp {
+    /// 生成日時も書く
+    WithTime,
+    /// 生成日時を書かない(同じ入力と設定からは同じ出力になる)
+    NoTime,
+}
+
+/// 一括変換の設定
+pub struct ConvertOptions {
+    pub mode: Mode,
+    pub input_dir: String,
+    pub output_dir: String,
+    pub normalize: Option<Normalization>,
+    /// キーの大文字・小文字のそろえ方
+    pub key_case: KeyCase,
+    /// key_caseで同じキーになったときの扱い
+    pub key_case_duplicates: DuplicatePolicy,
+    /// Java版と統合版のキーの対応表
+    pub key_map: Option<KeyMap>,
+    pub key_map_direction: KeyMapDirection,
+    /// 対応表に無いキーの扱い
+    pub unmapped_keys: UnmappedKeys,
+    /// 空のキー・空の値の扱い
+    pub empty_keys: EmptyPolicy,
+    pub empty_values: EmptyPolicy,
+    /// 二重にエンコードされた文字化けを元に戻す
+    pub fix_double_encoding: bool,
+    pub placeholder_style: Option<PlaceholderStyle>,
+    pub formatting_codes: Option<FormattingCodes>,
+    /// 保存時に値をエスケープする方式
+    pub escape_profile: Escape,
+    /// 読み込み時に値のエスケープを解除する方式
+    pub unescape_profile: Escape,
+    pub strip_prefix: Option<String>,
+    pub add_prefix: Option<String>,
+    /// add_prefixの対象をこの文字列で始まるキーに限定する
+    pub prefix_match: Option<String>,
+    /// 値の `{{name}}` を置き換える変数
+    pub vars: Option<Vars>,
+    /// 変数ファイルに無い変数の扱い
+    pub missing_vars: MissingVars,
+    /// 値を加工する外部コマンド
+    pub transform_cmd: Option<String>,
+    /// 外部コマンドの1回の実行の制限時間
+    pub transform_timeout: Duration,
+    pub write: WriteOptions,
+    /// .lang入力の読み込み設定
+    pub read: ReadOptions,
+    /// キーも値も同じ重複をまとめて報告し、値が異なる重複を読み込みの失敗にする
+    pub dedup_identical: bool,
+    /// 拡張子ではなく内容から読み込む形式を決める
+    pub auto_detect: bool,
+    /// 不審なキーがあるファイルを読み込みの失敗にする(指定しなければ警告のみ)
+    pub reject_suspicious_keys: bool,
+    /// 不審なキーの検査で許可する文字
+    pub allow_key_chars: String,
+    /// 書き出す内容を検証するJSON Schema
+    pub schema: Option<Schema>,
+    /// スキーマに違反するファイルを変換の失敗にする(指定しなければ警告のみ)
+    pub schema_strict: bool,
+    /// .langの読み込みで値が変わった行を報告する
+    pub trim_report: bool,
+    /// 前回から変更の無いファイルの変換を省略する
+    pub incremental: bool,
+    /// --incrementalのキャッシュを無視してすべて変換する
+    pub rebuild: bool,
+    /// 変換後、どの入力にも対応しない出力ファイルを削除する
+    pub delete_stale: bool,
+    /// 出力するキーの並び順
+    pub sort: SortOrder,
+    /// 並べ替えで大文字と小文字を区別しない
+    pub sort_ignore_case: bool,
+    /// --sort valuesでも確認用の出力ファイル名(.byvalue)にしない
+    pub force: bool,
+    /// 出力に含めるキーの絞り込み
+    pub keys: KeyFilter,
+    /// 変換する入力ファイルの絞り込み
+    pub files: FileFilter,
+    /// 出力ファイル名のテンプレート
+    pub output_name: OutputName,
+    /// 複数の入力が同じ出力先になったときの扱い
+    pub name_collisions: NameCollisions,
+    /// 接頭辞でキーを別の出力ファイルに振り分けるルート
+    pub routes: Vec<Route>,
+    /// 不足するキーをこのロケールの同じディレクトリのファイルから補う
+    pub with_fallback: Option<String>,
+    /// 出力ファイル名を作る前に入力ファイル名の末尾から取り除く中間の拡張子
+    pub strip_suffixes: Vec<String>,
+    /// .lang/.json以外に入力として扱う拡張子
+    pub ext_map: ExtMap,
+    /// 出力ファイルを各入力ファイルと同じディレクトリに書き出す
+    pub in_place: bool,
+    /// modのソースツリーのルート(Someならassets/<modid>/langごとに変換する)
+    pub project: Option<String>,
+    /// --projectの出力先(Noneなら各langディレクトリに書き戻す)
+    pub project_output: Option<String>,
+    /// --projectでデータ生成の出力も対象にする
+    pub include_generated: bool,
+    /// 出力ファイルの更新日時を入力ファイルに合わせる
+    pub preserve_mtime: bool,
+    /// .lang出力の先頭に生成情報のコメントを書く
+    pub stamp: Option<Stamp>,
+    /// .langのコメントを別ファイルに書き出し、.langに戻すときに付け直す
+    pub comments_sidecar: bool,
+    /// 変換の前に出力ディレクトリを空にする範囲
+    pub clean: Option<CleanScope>,
+    /// ファイルの書き込み・削除を行わず、行う予定の処理だけを表示する
+    pub dry_run: bool,
+    /// 実行ごとの結果を出力ディレクトリの conversion.log に追記する
+    pub run_log: bool,
+    /// 変換に成功したファイルを1件ずつ表示せず、進み具合を1行で表示する
+    pub only_failures: bool,
+    /// 処理時間の長いファイルを表示する件数(Noneなら表示しない)
+    pub timings: Option<usize>,
+    /// 結果と処理時間を書き出すJSONファイル
+    pub report: Option<String>,
+    /// 変換後に出力ファイルのSHA-256マニフェストを書き出すパスと書式
+    pub manifest: Option<(String, ManifestFormat)>,
+    /// 変換せずに照合するマニフェスト
+    pub verify_manifest: Option<String>,
+    /// 既存の出力ファイルを置き換えずにキーをマージする(同じキーの扱い)
+    pub merge_into_existing: Option<ExistingPolicy>,
+    /// 変換の代わりにキーまたは値だけの一覧を書き出す
+    pub export: Option<Export>,
+    /// --exportの一覧をファイルごとではなく、このファイル1つにまとめる
+    pub export_to: Option<String>,
+    /// 一時ディレクトリに書き出し、失敗が無かった場合だけ出力先へ移動する
+    pub transactional: bool,
+    /// 失敗がこの件数を超えたら残りのファイルを処理せずに中断する(Noneなら最後まで処理する)
+    pub max_failures: Option<usize>,
+    /// 入力ディレクトリのサブディレクトリもたどる
+    pub recursive: bool,
+    /// --recursiveでたどるサブディレクトリの深さの上限
+    pub max_depth: Option<usize>,
+    /// 処理する入力ファイル数の上限
+    pub max_files: usize,
+    /// 1回の実行で処理するファイル数(--limit)。残りは--start-afterで続ける
+    pub limit: Option<usize>,
+    /// このパスより後(パスの順)のファイルだけを処理する
+    pub start_after: Option<String>,
+    /// この大きさ(バイト)を超える入力ファイルはlarge_filesに従って扱う
+    pub max_file_size: u64,
+    pub large_files: LargeFiles,
+}
+
+impl Default for ConvertOptions {
+    fn default() -> Self {
+        ConvertOptions {
+            mode: Mode::All,
+            input_dir: "./input".to_string(),
+            output_dir: "./output".to_string(),
+            normalize: None,
+            key_case: KeyCase::Preserve,
+            key_case_duplicates: DuplicatePolicy::Error,
+            key_map: None,
+            key_map_direction: KeyMapDirection::JavaToBedrock,
+            unmapped_keys: UnmappedKeys::Keep,
+            empty_keys: EmptyPolicy::Warn,
+            empty_values: EmptyPolicy::Warn,
+            fix_double_encoding: false,
+            placeholder_style: None,
+            formatting_codes: None,
+            escape_profile: Escape::None,
+            unescape_profile: Escape::None,
+            strip_prefix: None,
+            add_prefix: None,
+            prefix_match: None,
+            write: WriteOptions::default(),
+            incremental: false,
+            rebuild: false,
+            delete_stale: false,
+            sort: SortOrder::None,
+            sort_ignore_case: false,
+            force: false,
+            keys: KeyFilter::default(),
+            files: FileFilter::default(),
+            output_name: OutputName::default(),
+            name_collisions: NameCollisions::Mirror,
+            routes: Vec::new(),
+            with_fallback: None,
+            comments_sidecar: false,
+            vars: None,
+            missing_vars: MissingVars::Error,
+            transform_cmd: None,
+            transform_timeout: Duration::from_secs(external::DEFAULT_TIMEOUT_SECS),
+            read: ReadOptions::default(),
+            dedup_identical: false,
+            auto_detect: false,
+            reject_suspicious_keys: false,
+            allow_key_chars: String::new(),
+            schema: None,
+            schema_strict: false,
+            trim_report: false,
+            strip_suffixes: Vec::new(),
+            ext_map: ExtMap::default(),
+            in_place: false,
+            project: None,
+            project_output: None,
+            include_generated: false,
+            preserve_mtime: false,
+            stamp: None,
+            clean: None,
+            dry_run: false,
+            run_log: true,
+            only_failures: false,
+            timings: None,
+            report: None,
+            manifest: None,
+            verify_manifest: None,
+            merge_into_existing: None,
+            export: None,
+            export_to: None,
+            transactional: false,
+            max_failures: None,
+            recursive: false,
+            max_depth: None,
+            max_files: DEFAULT_MAX_FILES,
+            limit: None,
+            start_after: None,
+            max_file_size: size_guard::DEFAULT_MAX_FILE_SIZE,
+            large_files: LargeFiles::Skip,
+        }
+    }
+}
+
+impl ConvertOptions {
+    /// 組み合わせられない設定を処理の前に検出する
+    pub fn validate(&self) -> Result<(), String> {
+        if self.delete_stale && cleanup::same_path(&self.input_dir, &self.output_dir) {
+            return Err(t("convert.delete_stale_same_dir"));
+        }
+        // 双方向に変換すると生成したファイルが次の実行で入力になり、元のファイルを上書きしてしまう
+        if self.in_place && self.mode == Mode::All {
+            return Err(t("convert.in_place_needs_mode"));
+        }
+        if self.clean.is_some() && cleanup::contains_dir(&self.output_dir, &self.input_dir) {
+            return Err(t("convert.clean_contains_input"));
+        }
+        Ok(())
+    }
+
+    /// 出力ファイルの置き場所。--in-placeの場合は入力ディレクトリ
+    pub fn output_root(&self) -> &str {
+        if self.in_place { &self.input_dir } else { &self.output_dir }
+    }
+
+    /// 出力内容に影響する設定のハッシュ。ツールのバージョンが変わった場合も別の値になる
+    pub fn fingerprint(&self) -> String {
+        let description = format!(
+            "{} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?}",
+            env!("CARGO_PKG_VERSION"),
+            self.keys,
+            self.sort,
+            self.sort_ignore_case,
+            self.normalize,
+            self.key_case,
+            self.key_case_duplicates,
+            self.key_map,
+            self.key_map_direction,
+            self.unmapped_keys,
+            self.empty_keys,
+            self.empty_values,
+            self.fix_double_encoding,
+            self.placeholder_style,
+            self.formatting_codes,
+            self.escape_profile,
+            self.unescape_profile,
+            self.strip_prefix,
+            self.add_prefix,
+            self.prefix_match,
+            self.write,
+            self.merge_into_existing,
+            self.export,
+            self.read,
+            self.comments_sidecar,
+            self.vars,
+            self.missing_vars,
+            self.transform_cmd,
+            self.routes,
+            self.name_collisions,
+            self.with_fallback,
+            self.dedup_identical,
+            self.auto_detect,
+            self.stamp,
+            self.schema,
+            self.schema_strict,
+        );
+        cache::content_hash(description.as_bytes())
+    }
+}
+
+/// ディレクトリ内の対応形式(.lang/.json)のファイル一覧(パスのバイト順)
+pub fn input_files(input_dir: &str) -> Result<Vec<PathBuf>, String> {
+    let entries = fs::read_dir(input_dir).map_err(|_| tf("convert.input_dir_missing", &[&input_dir]))?;
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && Format::from_path(path).is_some())
+        .collect();
+    sort_paths(&mut files);
+    Ok(files)
+}
+
+/// パスをバイト順に並べる。read_dirの順はOSによって違うため、処理や表示の順をこれで固定する
+pub fn sort_paths(paths: &mut [PathBuf]) {
+    paths.sort_by(|a, b| a.as_os_str().as_encoded_bytes().cmp(b.as_os_str().as_encoded_bytes()));
+}
+
+/// 指定されたファイル一覧、省略時はディレクトリ内の対応形式のファイル一覧
+pub fn files_or_input_dir(files: &[String], input_dir: &str) -> Result<Vec<PathBuf>, String> {
+    if files.is_empty() {
+        input_files(input_dir)
+    } else {
+        Ok(files.iter().map(PathBuf::from).collect())
+    }
+}
+
+/// 変換する1ファイル分の計画
+pub struct Job {
+    /// 入力ファイルの拡張子を除いた名前
+    pub name: String,
+    pub input_path: String,
+    pub format: Format,
+    pub output_path: String,
+    /// --routeの各ルートの出力先(ルートと同じ順)
+    pub route_paths: Vec<String>,
+    pub target: Format,
+    /// 入力ファイルの大きさ(バイト)
+    pub size: u64,
+    /// --name-collisionsで出力先を変えた場合、元の出力先
+    pub resolved_from: Option<String>,
+    /// --auto-detectで拡張子と違う形式として読む場合、拡張子から決まる形式
+    pub extension_format: Option<Format>,
+}
+
+impl Job {
+    /// 通常の出力先と--routeの出力先
+    pub fn output_paths(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.output_path.as_str()).chain(self.route_paths.iter().map(String::as_str))
+    }
+}
+
+/// 入力ディレクトリから変換方向に合うファイルを探し、出力先を決める
+pub fn plan_jobs(options: &ConvertOptions) -> Result<(Vec<Job>, Vec<String>), String> {
+    plan_jobs_with_mode(options, options.mode)
+}
+
+/// 入力ディレクトリのファイルを集める。--recursiveならサブディレクトリもたどる。
+/// 深さ・ファイル数の上限やシンボリックリンクの循環、読めないサブディレクトリでたどるのをやめた場合は、その場所を警告として返す。
+/// 入力ディレクトリ自体が読めない場合だけエラーにする
+fn walk_input_dir(options: &ConvertOptions) -> Result<(Vec<PathBuf>, Vec<String>), String> {
+    let mut files = Vec::new();
+    let mut warnings = Vec::new();
+    let root = PathBuf::from(&options.input_dir);
+    let mut visited: HashSet<PathBuf> = root.canonicalize().into_iter().collect();
+    // 入力ディレクトリの中にある出力ディレクトリや一時ディレクトリはたどらない
+    let skipped: Vec<PathBuf> = [PathBuf::from(&options.output_dir), Path::new(options.output_root()).join(STAGING_DIR_NAME)]
+        .iter()
+        .filter_map(|dir| dir.canonicalize().ok())
+        .filter(|dir| !visited.contains(dir))
+        .collect();
+    let mut pending = vec![(root, 0)];
+    while let Some((dir, depth)) = pending.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(_) if depth == 0 => return Err(tf("convert.input_dir_missing", &[&dir.display()])),
+            Err(e) => {
+                warnings.push(tf("convert.walk.unreadable", &[&dir.display(), &e]));
+                continue;
+            }
+        };
+        // read_dirの順はOSによって違うため、--max-filesで打ち切る場所が変わらないよう並べてからたどる
+        let mut paths: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+        sort_paths(&mut paths);
+        for path in paths {
+            if path.is_dir() {
+                if !options.recursive {
+                    continue;
+                }
+                if options.max_depth.is_some_and(|max| depth >= max) {
+                    warnings.push(tf("convert.walk.max_depth", &[&path.display()]));
+                    continue;
+                }
+                let Ok(canonical) = path.canonicalize() else {
+                    continue;
+                };
+                if skipped.contains(&canonical) {
+                    continue;
+                }
+                if !visited.insert(canonical) {
+                    warnings.push(tf("convert.walk.cycle", &[&path.display()]));
+                    continue;
+                }
+                pending.push((path, depth + 1));
+                continue;
+            }
+            if options.ext_map.format_of(&path).is_none() {
+                continue;
+            }
+            files.push(path);
+        }
+    }
+    // たどる順は深さ優先なので、全体を並べてから切り詰めて--max-filesがバイト順の先頭N件を残すようにする
+    sort_paths(&mut files);
+    if files.len() > options.max_files {
+        warnings.push(tf("convert.walk.max_files", &[&options.max_files, &(files.len() - options.max_files)]));
+        files.truncate(options.max_files);
+    }
+    Ok((files, warnings))
+}
+
+/// 設定とは別の変換方向で計画を立てる。入力をたどる途中の警告も返す
+pub fn plan_jobs_with_mode(options: &ConvertOptions, mode: Mode) -> Result<(Vec<Job>, Vec<String>), String> {
+    let mut jobs = Vec::new();
+    // 出力先の衝突を解決するための、出力ディレクトリからの相対パスと入力の相対ディレクトリ
+    let mut names = Vec::new();
+    let (mut files, warnings) = walk_input_dir(options)?;
+    // 処理の順を入力ディレクトリからの相対パスのバイト順に固定する(どのファイルも同じ接頭辞なのでパス全体で比べてよい)。
+    // 結果表・--report・先に処理した方を残す衝突の扱いもこの順になり、--start-afterもこの順で続きを決める
+    sort_paths(&mut files);
+    for path in files {
+        let Some(by_extension) = options.ext_map.format_of(&path) else {
+            continue;
+        };
+        let detected = if options.auto_detect { sniff::mismatch(&path, by_extension) } else { None };
+        let format = detected.unwrap_or(by_extension);
+        if !mode.accepts(format) {
+            continue;
+        }
+        if options.comments_sidecar && comments::is_sidecar(&path) {
+            continue;
+        }
+        let name = output_name::stem(&path, &options.strip_suffixes);
+        let target = format.opposite();
+        let relative_dir = path
+            .parent()
+            .and_then(|parent| parent.strip_prefix(&options.input_dir).ok())
+            .and_then(|dir| dir.to_str())
+            .unwrap_or("");
+        let mut extension = options.export.map_or(target.extension(), Export::extension).to_string();
+        if options.sort == SortOrder::Values && !options.force {
+            extension = format!("{}.{}", sort::BY_VALUE_SUFFIX, extension);
+        }
+        let output_name = options.output_name.render(&name, &extension, relative_dir);
+        let output_path = format!("{}/{}", options.output_root(), output_name);
+        let route_paths = options.routes.iter().map(|route| format!("{}/{}", options.output_root(), route.template.render(&name, &extension, relative_dir))).collect();
+        let size = fs::metadata(&path).map(|metadata| metadata.len()).unwrap_or(0);
+        jobs.push(Job { input_path: path.to_str().unwrap().to_string(), name, format, output_path, route_paths, target, size, resolved_from: None, extension_format: detected.map(|_| by_extension) });
+        names.push((output_name, relative_dir.to_string()));
+    }
+    resolve_collisions(&mut jobs, &names, options);
+    Ok((jobs, warnings))
+}
+
+/// 同じ出力先になる入力の組を--name-collisionsに従って別の出力先にする。
+/// 解決できなかった組(同じディレクトリの入力など)はrecord_output_collisionsで失敗にする
+fn resolve_collisions(jobs: &mut [Job], names: &[(String, String)], options: &ConvertOptions) {
+    if options.name_collisions == NameCollisions::Error {
+        return;
+    }
+    let mut groups: IndexMap<String, Vec<usize>> = IndexMap::new();
+    for (index, job) in jobs.iter().enumerate() {
+        groups.entry(job.output_path.clone()).or_default().push(index);
+    }
+    for indices in groups.values().filter(|indices| indices.len() > 1) {
+        for &index in indices {
+            let (output_name, relative_dir) = &names[index];
+            let resolved = format!("{}/{}", options.output_root(), options.name_collisions.resolve(output_name, relative_dir));
+            let job = &mut jobs[index];
+            if resolved != job.output_path {
+                job.resolved_from = Some(std::mem::replace(&mut job.output_path, resolved));
+            }
+        }
+    }
+}
+
+/// 計画から --include/--exclude に合わないファイルを除く。どのファイルにも一致しないパターンは警告する
+/// 入力ディレクトリが読めなければ読み込みの失敗として記録し、何も変換しない
+fn select_jobs(options: &ConvertOptions, summary: &mut Summary) -> Vec<Job> {
+    let (jobs, warnings) = match plan_jobs(options) {
+        Ok(planned) => planned,
+        Err(e) => {
+            println!("{}", e);
+            summary.failed_reads.push_back(e);
+            return Vec::new();
+        }
+    };
+    for warning in &warnings {
+        println!("{}", warning);
+    }
+    summary.walk_warnings = warnings;
+    if options.files.is_empty() {
+        return jobs;
+    }
+    let relative_path = |job: &Job| -> PathBuf {
+        let path = Path::new(&job.input_path);
+        path.strip_prefix(&options.input_dir).unwrap_or(path).to_path_buf()
+    };
+    let relative_paths: Vec<PathBuf> = jobs.iter().map(relative_path).collect();
+    let path_refs: Vec<&Path> = relative_paths.iter().map(PathBuf::as_path).collect();
+    for pattern in options.files.unmatched(&path_refs) {
+        println!("{}", tf("convert.pattern_unmatched", &[&pattern]));
+    }
+    let total = jobs.len();
+    let selected: Vec<Job> = jobs.into_iter().filter(|job| options.files.accepts(&relative_path(job))).collect();
+    summary.filtered = total - selected.len();
+    selected
+}
+
+/// --start-afterより後のファイルから--limitの件数だけを残す。どちらかを指定した場合は残りの件数を記録する
+fn select_batch(mut jobs: Vec<Job>, options: &ConvertOptions, summary: &mut Summary) -> Vec<Job> {
+    if options.limit.is_none() && options.start_after.is_none() {
+        return jobs;
+    }
+    if let Some(start_after) = &options.start_after {
+        jobs.retain(|job| job.input_path.as_str() > start_after.as_str());
+    }
+    let limit = options.limit.unwrap_or(jobs.len());
+    summary.batch = Some(Batch { last_processed: None, remaining: jobs.len().saturating_sub(limit) });
+    jobs.truncate(limit);
+    jobs
+}
+
+/// --limit/--start-afterで一部だけを処理した結果(次の実行で続けるための情報)
+#[derive(Serialize)]
+pub struct Batch {
+    /// 最後に処理したファイル。次の実行の--start-afterに渡す
+    pub last_processed: Option<String>,
+    /// 処理せずに残したファイル数
+    pub remaining: usize,
+}
+
+/// 一括変換の結果
+#[derive(Default)]
+pub struct Summary {
+    pub converted: usize,
+    /// --incrementalで変更が無く省略したファイル数
+    pub skipped: usize,
+    /// --include/--excludeで除外したファイル数
+    pub filtered: usize,
+    pub renamed_keys: usize,
+    /// --key-include/--key-excludeで取り除いたキーの合計
+    pub dropped_keys: usize,
+    pub failed_reads: VecDeque<String>,      // 読み込み失敗の記録
+    pub failed_transforms: VecDeque<String>, // 加工失敗の記録
+    pub failed_writes: VecDeque<String>,     // 書き込み失敗の記録
+    /// --delete-staleで削除した出力ファイル
+    pub deleted: Vec<String>,
+    /// 一時的な書き込み失敗を再試行して成功したファイル
+    pub retried_writes: Vec<RetriedWrite>,
+    /// Ctrl+Cで途中で中断したか
+    pub interrupted: bool,
+    /// ファイルごとの結果(結果表に表示する)
+    pub rows: Vec<FileRow>,
+    /// 変換したファイルごとの処理時間
+    pub timings: Vec<FileTiming>,
+    /// 書き込み済みの出力ファイル(--incrementalで省略したものを含む)
+    pub outputs: Vec<String>,
+    /// --export-toにまとめる行
+    pub exported_lines: Vec<String>,
+    /// --transactionalの結果
+    pub transaction: Option<Transaction>,
+    /// --fail-fast/--max-failuresで中断した場合、処理しなかったファイル数
+    pub aborted: Option<usize>,
+    /// --max-file-sizeを超えたファイルとその大きさ
+    pub large_files: Vec<(String, u64)>,
+    /// 入力ディレクトリをたどるのを途中でやめた場所
+    pub walk_warnings: Vec<String>,
+    /// 文字列でないため読み飛ばしたJSONの値(ファイル・キー・型)
+    pub skipped_values: Vec<String>,
+    /// --varsの変数のうちどれかのファイルで使ったもの
+    pub used_vars: BTreeSet<String>,
+    /// --routeを使った場合に書き出した出力ファイルとキーの数
+    pub produced: Vec<(String, usize)>,
+    /// --with-fallbackでキーを補ったファイルと補ったキーの数
+    pub filled: Vec<(String, usize)>,
+    /// --duplicates-to-suffixで重複を書き出した別のキー(ファイル・キー)
+    pub suffixed_duplicates: Vec<(String, String)>,
+    /// --name-collisionsで出力先を変えた入力
+    pub resolved_collisions: Vec<String>,
+    /// 空のキーと空の値の合計
+    pub empty_keys: usize,
+    pub empty_values: usize,
+    /// --limit/--start-afterを指定した場合の続きの情報
+    pub batch: Option<Batch>,
+}
+
+/// 失敗した処理の段階
+enum Stage {
+    Read,
+    Transform,
+    Write,
+}
+
+impl Summary {
+    /// 失敗を段階ごとの一覧と結果表に記録する
+    fn fail(&mut self, stage: Stage, job: &Job, reason: String) {
+        let list = match stage {
+            Stage::Read => &mut self.failed_reads,
+            Stage::Transform => &mut self.failed_transforms,
+            Stage::Write => &mut self.failed_writes,
+        };
+        list.push_back(format!("{}: {}", job.name, reason));
+        self.rows.push(FileRow { file: job.input_path.clone(), status: FileStatus::Failed, keys: None, filled_keys: None, reason });
+    }
+
+    pub fn has_failures(&self) -> bool {
+        self.failed_count() > 0
+    }
+
+    /// 失敗したファイル数
+    pub fn failed_count(&self) -> usize {
+        self.failed_reads.len() + self.failed_transforms.len() + self.failed_writes.len()
+    }
+
+    /// 結果表示
+    pub fn print(&self, options: &ConvertOptions) {
+        if self.interrupted {
+            println!("\n{}", t("summary.interrupted"));
+        } else if let Some(remaining) = self.aborted {
+            println!("\n{}", tf("summary.aborted", &[&self.failed_count(), &remaining]));
+        } else {
+            println!("\n{}", t("summary.done"));
+        }
+        if options.incremental {
+            println!("{}", tf("summary.incremental", &[&self.converted, &self.skipped]));
+        }
+        if self.filtered > 0 {
+            println!("{}", tf("summary.filtered", &[&self.filtered]));
+        }
+        if !self.large_files.is_empty() {
+            let action = match options.large_files {
+                LargeFiles::Skip => t("summary.large_files.skip"),
+                LargeFiles::Stream => t("summary.large_files.stream"),
+            };
+            println!("{}", tf("summary.large_files", &[&size_guard::format_size(options.max_file_size), &action, &self.large_files.len()]));
+            for (path, size) in &self.large_files {
+                println!("- {} ({})", path, size_guard::format_size(*size));
+            }
+        }
+        if !self.walk_warnings.is_empty() {
+            println!("{}", tf("summary.walk_warnings", &[&self.walk_warnings.len()]));
+            for warning in &self.walk_warnings {
+                println!("- {}", warning);
+            }
+        }
+        if !self.skipped_values.is_empty() {
+            println!("{}", tf(if options.read.coerce { "summary.skipped_values.coerce" } else { "summary.skipped_values" }, &[&self.skipped_values.len()]));
+            for item in &self.skipped_values {
+                println!("- {}", item);
+            }
+        }
+        if let Some(vars) = &options.vars {
+            let unused: Vec<&str> = vars.0.keys().filter(|name| !self.used_vars.contains(*name)).map(String::as_str).collect();
+            if !unused.is_empty() {
+                println!("{}", tf("summary.unused_vars", &[&unused.len(), &unused.join(", ")]));
+            }
+        }
+        if self.empty_keys + self.empty_values > 0 && (options.empty_keys != EmptyPolicy::Keep || options.empty_values != EmptyPolicy::Keep) {
+            println!("{}", tf("summary.empties", &[&self.empty_keys, &self.empty_values]));
+        }
+        if self.dropped_keys > 0 {
+            println!("{}", tf("summary.dropped_keys", &[&self.dropped_keys]));
+        }
+        if self.renamed_keys > 0 {
+            println!("{}", tf("summary.renamed_keys", &[&self.renamed_keys]));
+        }
+        if let (Some(locale), false) = (&options.with_fallback, self.filled.is_empty()) {
+            let total: usize = self.filled.iter().map(|(_, filled)| filled).sum();
+            println!("{}", tf("summary.filled", &[locale, &total, &self.filled.len()]));
+            for (path, filled) in &self.filled {
+                println!("- {}", tf("summary.filled.item", &[path, filled]));
+            }
+        }
+        if !self.suffixed_duplicates.is_empty() {
+            println!("{}", tf("summary.duplicates_suffixed", &[&self.suffixed_duplicates.len()]));
+            for (path, key) in &self.suffixed_duplicates {
+                println!("- {}: {}", path, key);
+            }
+        }
+        if !self.retried_writes.is_empty() {
+            println!("{}", t("summary.retried"));
+            for retried in &self.retried_writes {
+                println!("- {}", tf("convert.retried", &[&retried.output, &retried.retries]));
+            }
+        }
+        if !self.resolved_collisions.is_empty() {
+            println!("{}", tf("summary.collisions_resolved", &[&self.resolved_collisions.len(), &options.name_collisions.name()]));
+            for item in &self.resolved_collisions {
+                println!("- {}", item);
+            }
+        }
+        if !self.produced.is_empty() {
+            println!("{}", tf("summary.produced", &[&self.produced.len()]));
+            for (path, keys) in &self.produced {
+                println!("- {}", tf("summary.produced.item", &[path, keys]));
+            }
+        }
+        if let Some(batch) = &self.batch {
+            match (&batch.last_processed, batch.remaining) {
+                (Some(last), 0) => println!("{}", tf("summary.batch_done", &[last])),
+                (Some(last), remaining) => println!("{}", tf("summary.batch", &[&remaining, last])),
+                (None, remaining) => println!("{}", tf("summary.batch_empty", &[&remaining])),
+            }
+        }
+        if !self.deleted.is_empty() {
+            println!("{}", tf("summary.deleted", &[&self.deleted.len()]));
+            for path in &self.deleted {
+                println!("- {}", path);
+            }
+        }
+        // --only-failuresでは変換したファイルの行を表に含めない
+        let rows: Vec<&FileRow> = self.rows.iter().filter(|row| !options.only_failures || row.status != FileStatus::Converted).collect();
+        if !rows.is_empty() {
+            println!();
+            report::print_table(rows);
+        }
+        if let Some(count) = options.timings {
+            timing::print_slowest(&self.timings, count);
+        }
+        match self.transaction {
+            Some(Transaction::Committed(count)) => println!("\n{}", tf("staging.committed", &[&count])),
+            Some(Transaction::RolledBack) => println!("\n{}", t("staging.rolled_back")),
+            None => {}
+        }
+        if !self.has_failures() {
+            let message = if self.interrupted { t("summary.processed_ok") } else { t("summary.all_ok") };
+            anstream::println!("\n{}", report::paint(&message, FileStatus::Converted.style()));
+            return;
+        }
+        let counts = [
+            ("summary.stage.read", self.failed_reads.len()),
+            ("summary.stage.transform", self.failed_transforms.len()),
+            ("summary.stage.write", self.failed_writes.len()),
+        ];
+        let counts: Vec<String> = counts.iter().filter(|(_, n)| *n > 0).map(|(stage, n)| tf("summary.stage_count", &[&t(stage), n])).collect();
+        anstream::println!("\n{}", report::paint(&tf("summary.failures", &[&counts.join(", ")]), FileStatus::Failed.style()));
+    }
+}
+
+/// 1ファイルを読み込んで加工する。失敗はsummaryに記録する
+fn load_and_apply(job: &Job, options: &ConvertOptions, summary: &mut Summary) -> Option<Applied> {
+    let loaded = if job.size > options.max_file_size { job.format.load_streaming(&job.input_path, &options.read) } else { job.format.load_with(&job.input_path, &options.read) };
+    let loaded = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            // JSONとして読めなかった.jsonの中身が.langらしければ、そのことも伝える
+            let e = match mismatch_warning(job, options) {
+                Some(warning) => format!("{} ({})", e, warning),
+                None => e,
+            };
+            summary.fail(Stage::Read, job, e);
+            return None;
+        }
+    };
+    if options.dedup_identical && !loaded.duplicates.conflicting.is_empty() {
+        summary.fail(Stage::Read, job, tf("convert.conflicting_duplicates", &[&loaded.duplicates.conflicting.join(", ")]));
+        return None;
+    }
+    // 行番号や元のテキストを調べるために読み直す入力。ストリーミングで読んだ大きなファイルは読み直さない
+    let line_source = (job.size <= options.max_file_size).then_some(job.input_path.as_str());
+    let suspicious_keys = keys::find(&loaded.lang_map, &options.allow_key_chars, line_source, job.format, &options.read);
+    if options.reject_suspicious_keys && !suspicious_keys.is_empty() {
+        summary.fail(Stage::Read, job, tf("convert.suspicious_keys_rejected", &[&suspicious_keys.join(", ")]));
+        return None;
+    }
+    for key in &loaded.duplicates.suffixed {
+        summary.suffixed_duplicates.push((job.input_path.clone(), key.clone()));
+    }
+    for (key, type_name) in &loaded.skipped {
+        summary.skipped_values.push(tf("summary.skipped_values.item", &[&job.input_path, key, type_name]));
+    }
+    let empty_keys = loaded.duplicates.empty_keys;
+    let mut lang_map = loaded.lang_map;
+    // 並べ替えやキーの加工が補ったキーにも及ぶよう、加工の前に補う
+    let mut filled_keys = None;
+    if let Some(reference) = fallback_reference(job, options) {
+        match fallback::fill(&mut lang_map, &reference, &options.read) {
+            Ok(filled) => filled_keys = Some(filled),
+            Err(e) => {
+                summary.fail(Stage::Read, job, e);
+                return None;
+            }
+        }
+    }
+    match transform::apply(lang_map, empty_keys, options) {
+        Ok(mut applied) => {
+            let violations = options.schema.as_ref().map(|schema| schema.violations(&applied.lang_map)).unwrap_or_default();
+            if options.schema_strict && !violations.is_empty() {
+                summary.fail(Stage::Transform, job, tf("convert.schema_rejected", &[&violations.join(", ")]));
+                return None;
+            }
+            for violation in &violations {
+                applied.notes.push(tf("convert.schema_violation", &[violation]));
+            }
+            if options.trim_report && job.format == Format::Lang {
+                // 大きなファイルは全体を読み直すとメモリの上限が無くなるため報告しない
+                match line_source {
+                    Some(path) => applied.notes.extend(trimmed::find(path, &options.read).iter().map(|change| tf("convert.trimmed", &[change]))),
+                    None => applied.notes.push(t("convert.trim_report_skipped")),
+                }
+            }
+            if let (Some(filled), Some(locale)) = (filled_keys.filter(|filled| *filled > 0), &options.with_fallback) {
+                applied.notes.push(tf("convert.filled", &[locale, &filled]));
+                summary.filled.push((job.input_path.clone(), filled));
+            }
+            applied.filled_keys = filled_keys;
+            if !loaded.skipped.is_empty() {
+                applied.notes.push(tf("convert.skipped_values", &[&loaded.skipped.len()]));
+            }
+            for message in &suspicious_keys {
+                applied.notes.push(tf("convert.suspicious_key", &[message]));
+            }
+            if !loaded.duplicates.suffixed.is_empty() {
+                applied.notes.push(tf("convert.duplicates_suffixed", &[&loaded.duplicates.suffixed.len()]));
+            }
+            if options.dedup_identical && loaded.duplicates.identical > 0 {
+                applied.notes.push(tf("convert.dedup_identical", &[&loaded.duplicates.identical]));
+            }
+            if let Some((extension, format)) = options.ext_map.mapped(Path::new(&job.input_path)) {
+                applied.notes.push(tf("convert.ext_mapped", &[&extension, &format.extension()]));
+            }
+            if let Some(by_extension) = job.extension_format {
+                applied.notes.push(tf("convert.detected", &[&job.format.extension(), &by_extension.extension()]));
+                tracing::info!(path = %job.input_path, format = job.format.extension(), "detected format from content");
+            } else if job.format == Format::Lang {
+                applied.notes.extend(mismatch_warning(job, options));
+            }
+            Some(applied)
+        }
+        Err(e) => {
+            summary.fail(Stage::Transform, job, e);
+            None
+        }
+    }
+}
+
+/// 拡張子と内容が合っていないようなら警告を返す(--auto-detectで形式を決めたファイルや大きすぎるファイルは調べない)
+fn mismatch_warning(job: &Job, options: &ConvertOptions) -> Option<String> {
+    if options.auto_detect || job.size > options.max_file_size {
+        return None;
+    }
+    let detected = sniff::mismatch(Path::new(&job.input_path), job.format)?;
+    Some(tf("convert.mismatch", &[&job.format.extension(), &detected.extension()]))
+}
+
+/// 複数の入力が同じ出力先になる組み合わせを記録し、見つかったらtrueを返す
+fn record_output_collisions(jobs: &[Job], summary: &mut Summary) -> bool {
+    let mut seen: IndexMap<&str, &str> = IndexMap::new();
+    let mut found = false;
+    for job in jobs {
+        for output_path in job.output_paths() {
+            let first = *seen.entry(output_path).or_insert(&job.input_path);
+            if first != job.input_path {
+                summary.fail(Stage::Write, job, tf("convert.collision", &[&first, &output_path]));
+                found = true;
+                break;
+            }
+        }
+    }
+    found
+}
+
+/// --comments-sidecarで使うコメント。.lang入力ならその中のコメント、.json入力なら隣のコメントのファイルの内容
+fn load_comments(job: &Job, options: &ConvertOptions) -> Result<Option<Comments>, String> {
+    if !options.comments_sidecar || options.export.is_some() {
+        return Ok(None);
+    }
+    match job.format {
+        Format::Lang => format::load_lang_lines_with(&job.input_path, &options.read).map(|lines| Some(Comments::from_lines(lines))),
+        Format::Json => Comments::load(&comments::sidecar_path(&job.input_path)),
+    }
+}
+
+/// --with-fallbackで補うときの基準のファイル。基準のロケール自身とキーの一覧の書き出しでは補わない
+fn fallback_reference(job: &Job, options: &ConvertOptions) -> Option<PathBuf> {
+    let locale = options.with_fallback.as_deref()?;
+    if options.export.is_some() {
+        return None;
+    }
+    fallback::reference_path(&job.input_path, &job.name, job.format, locale)
+}
+
+/// --incrementalで比べる入力の内容のハッシュ。--comments-sidecarでは付け直すコメントのファイルも、
+/// --with-fallbackでは基準のファイルも含める
+fn input_hash(job: &Job, options: &ConvertOptions) -> io::Result<String> {
+    let mut hash = cache::file_hash(&job.input_path)?;
+    if let Some(reference) = fallback_reference(job, options).filter(|reference| reference.is_file()) {
+        hash = format!("{}+{}", hash, cache::file_hash(&reference.to_string_lossy())?);
+    }
+    let sidecar = comments::sidecar_path(&job.input_path);
+    if options.comments_sidecar && job.format == Format::Json && Path::new(&sidecar).exists() {
+        return Ok(format!("{}+{}", hash, cache::file_hash(&sidecar)?));
+    }
+    Ok(hash)
+}
+
+/// 出力ファイルの更新日時を入力ファイルと同じにする
+fn copy_modified_time(input_path: &str, output_path: &str) -> io::Result<()> {
+    let modified = fs::metadata(input_path)?.modified()?;
+    fs::OpenOptions::new().write(true).open(output_path)?.set_modified(modified)
+}
+
+/// --routeの出力先とその出力先に書き出すキー
+type Routed = Vec<(String, IndexMap<String, String>)>;
+
+/// --routeに当てはまるキーを取り出す。戻り値は通常の出力先のキーと、キーが1つ以上あるルートの出力先とキー
+/// (--export-toでは1つのファイルにまとめるため振り分けない)
+fn split_routes(job: &Job, options: &ConvertOptions, lang_map: IndexMap<String, String>) -> (IndexMap<String, String>, Routed) {
+    if options.export_to.is_some() {
+        return (lang_map, Vec::new());
+    }
+    let (rest, routed) = route::split(lang_map, &options.routes);
+    let routed = job.route_paths.iter().cloned().zip(routed).filter(|(_, route_map)| !route_map.is_empty()).collect();
+    (rest, routed)
+}
+
+/// --routeを使った場合に、書き出した出力ファイルとキーの数を記録する
+fn record_produced(summary: &mut Summary, job: &Job, lang_map: &IndexMap<String, String>, routed: &[(String, IndexMap<String, String>)]) {
+    if job.route_paths.is_empty() {
+        return;
+    }
+    summary.produced.push((job.output_path.clone(), lang_map.len()));
+    summary.produced.extend(routed.iter().map(|(path, route_map)| (path.clone(), route_map.len())));
+}
+
+/// このファイルの出力に使う書式設定。--stampなら.lang出力の先頭のコメントに生成情報を加える(コメントなので読み込むときは無視される)
+fn write_options_for<'a>(job: &Job, options: &'a ConvertOptions) -> Cow<'a, WriteOptions> {
+    let Some(stamp) = options.stamp.filter(|_| job.target == Format::Lang && options.export.is_none()) else {
+        return Cow::Borrowed(&options.write);
+    };
+    let mut write = options.write.clone();
+    let source = Path::new(&job.input_path).file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    write.header.push(format!("generated by json_lang {}", env!("CARGO_PKG_VERSION")));
+    write.header.push(format!("source: {}", source));
+    if let Ok(hash) = cache::file_hash(&job.input_path) {
+        write.header.push(format!("source-hash: {}", hash));
+    }
+    if stamp == Stamp::WithTime {
+        write.header.push(format!("generated-at: {}", timestamp::now_utc()));
+    }
+    Cow::Owned(write)
+}
+
+/// 1ファイルを読み込み、加工して保存する
+/// stagingがあれば出力先ではなく一時ディレクトリに書き込む
+fn convert_job(job: &Job, options: &ConvertOptions, summary: &mut Summary, mut staging: Option<&mut Staging>, observer: &mut dyn Observer) -> bool {
+    let _span = tracing::info_span!("file", path = %job.input_path, from = job.format.extension(), to = job.target.extension()).entered();
+    let start = Instant::now();
+    let Some(applied) = load_and_apply(job, options, summary) else {
+        tracing::warn!(elapsed_ms = timing::millis(start.elapsed()), "load failed");
+        return false;
+    };
+    let load = start.elapsed();
+    tracing::info!(keys = applied.lang_map.len(), elapsed_ms = timing::millis(load), "loaded");
+    observer.on_event(Event::FileLoaded { job, notes: &applied.notes });
+    summary.renamed_keys += applied.renamed_keys;
+    summary.used_vars.extend(applied.used_vars);
+    summary.dropped_keys += applied.dropped_keys;
+    summary.empty_keys += applied.empties.keys;
+    summary.empty_values += applied.empties.values;
+    let (lang_map, routed) = split_routes(job, options, applied.lang_map);
+    let lang_map = match options.merge_into_existing {
+        Some(policy) => match existing::merge_into_existing(&job.output_path, job.target, lang_map, policy, &options.write.read_back()) {
+            Ok(lang_map) => lang_map,
+            Err(e) => {
+                summary.fail(Stage::Write, job, e);
+                return false;
+            }
+        },
+        None => lang_map,
+    };
+    let comments = match load_comments(job, options) {
+        Ok(comments) => comments,
+        Err(e) => {
+            summary.fail(Stage::Read, job, e);
+            return false;
+        }
+    };
+    let reason = match &job.resolved_from {
+        Some(original) => tf("convert.collision_resolved", &[&options.name_collisions.name(), original]),
+        None => String::new(),
+    };
+    let converted = FileRow { file: job.input_path.clone(), status: FileStatus::Converted, keys: Some(lang_map.len()), filled_keys: applied.filled_keys, reason };
+    if let (Some(export), Some(_)) = (options.export, &options.export_to) {
+        summary.exported_lines.extend(export.lines(&lang_map));
+        summary.converted += 1;
+        summary.rows.push(converted);
+        summary.timings.push(FileTiming { file: job.input_path.clone(), load, save: Duration::ZERO, total: start.elapsed() });
+        return true;
+    }
+    if options.dry_run {
+        record_produced(summary, job, &lang_map, &routed);
+        summary.converted += 1;
+        summary.rows.push(FileRow { reason: t("convert.dry_run"), ..converted });
+        summary.timings.push(FileTiming { file: job.input_path.clone(), load, save: Duration::ZERO, total: start.elapsed() });
+        return true;
+    }
+    if cleanup::same_path(&job.input_path, &job.output_path) {
+        summary.fail(Stage::Write, job, t("convert.overwrites_input"));
+        return false;
+    }
+    let save_start = Instant::now();
+    let write_path = match &mut staging {
+        Some(staging) => staging.stage(&job.output_path),
+        None => job.output_path.clone(),
+    };
+    let write = write_options_for(job, options);
+    let saved = match (options.export, &comments) {
+        (Some(export), _) => export.save(&write_path, &lang_map),
+        (None, Some(comments)) if job.target == Format::Lang => format::save_lang_lines_with(&write_path, &comments.attach(&lang_map), &write),
+        (None, _) => job.target.save_with(&write_path, &lang_map, &write),
+    };
+    tracing::info!(output = %job.output_path, keys = lang_map.len(), ok = saved.is_ok(), elapsed_ms = timing::millis(save_start.elapsed()), "saved");
+    match saved {
+        Ok(0) => {}
+        Ok(retries) => summary.retried_writes.push(RetriedWrite { output: job.output_path.clone(), retries }),
+        Err(e) => {
+            summary.fail(Stage::Write, job, e);
+            return false;
+        }
+    }
+    if options.preserve_mtime {
+        if let Err(e) = copy_modified_time(&job.input_path, &write_path) {
+            println!("{}", tf("convert.mtime_failed", &[&job.output_path, &e]));
+        }
+    }
+    if let Some(comments) = comments.filter(|comments| job.target == Format::Json && !comments.is_empty()) {
+        let sidecar = comments::sidecar_path(&job.output_path);
+        let sidecar_write = match &mut staging {
+            Some(staging) => staging.stage(&sidecar),
+            None => sidecar.clone(),
+        };
+        if let Err(e) = comments.save(&sidecar_write) {
+            summary.fail(Stage::Write, job, e);
+            return false;
+        }
+        summary.outputs.push(sidecar);
+    }
+    for (route_path, route_map) in &routed {
+        let write_path = match &mut staging {
+            Some(staging) => staging.stage(route_path),
+            None => route_path.clone(),
+        };
+        let saved = match options.export {
+            Some(export) => export.save(&write_path, route_map),
+            None => job.target.save_with(&write_path, route_map, &write),
+        };
+        match saved {
+            Ok(0) => {}
+            Ok(retries) => summary.retried_writes.push(RetriedWrite { output: route_path.clone(), retries }),
+            Err(e) => {
+                summary.fail(Stage::Write, job, e);
+                return false;
+            }
+        }
+        summary.outputs.push(route_path.clone());
+    }
+    record_produced(summary, job, &lang_map, &routed);
+    summary.converted += 1;
+    summary.rows.push(converted);
+    summary.outputs.push(job.output_path.clone());
+    summary.timings.push(FileTiming { file: job.input_path.clone(), load, save: save_start.elapsed(), total: start.elapsed() });
+    true
+}
+
+/// 特定の変換を実行する関数
+pub fn process_files(options: &ConvertOptions) -> Summary {
+    process_files_with(options, &mut Console::new(options))
+}
+
+/// 1ファイルを変換し、結果をobserverに知らせる
+fn convert_and_notify(job: &Job, options: &ConvertOptions, summary: &mut Summary, staging: Option<&mut Staging>, observer: &mut dyn Observer) -> bool {
+    let converted = convert_job(job, options, summary, staging, observer);
+    let last = summary.rows.last();
+    if converted {
+        observer.on_event(Event::FileCompleted { job, keys: last.and_then(|row| row.keys).unwrap_or(0) });
+    } else {
+        observer.on_event(Event::FileFailed { job, error: last.map_or("", |row| row.reason.as_str()) });
+    }
+    converted
+}
+
+/// 変換を実行し、進み具合をobserverに知らせる
+pub fn process_files_with(options: &ConvertOptions, observer: &mut dyn Observer) -> Summary {
+    let mut summary = Summary::default();
+    let mut cache = if options.incremental && !options.rebuild { Cache::load(options.output_root()) } else { Cache::default() };
+    let options_hash = options.fingerprint();
+    let jobs = select_jobs(options, &mut summary);
+    let jobs = select_batch(jobs, options, &mut summary);
+    summary.resolved_collisions = jobs
+        .iter()
+        .filter_map(|job| job.resolved_from.as_ref().map(|original| tf("summary.collisions_resolved.item", &[&job.input_path, &job.output_path, original])))
+        .collect();
+    // 衝突がある場合は何も書き込まない(--export-toでは出力は1つなので確かめない)
+    if options.export_to.is_none() && record_output_collisions(&jobs, &mut summary) {
+        observer.on_event(Event::BatchCompleted { summary: &summary });
+        return summary;
+    }
+    if let Some(scope) = options.clean {
+        cleanup::clean_output(options, scope);
+    }
+    let mut staging = (options.transactional && !options.dry_run).then(|| Staging::new(options.output_root()));
+
+    let total = jobs.len();
+    let mut processed = 0;
+    observer.on_event(Event::BatchStarted { total });
+    for (index, job) in jobs.into_iter().enumerate() {
+        if cancel::is_cancelled() {
+            summary.interrupted = true;
+            break;
+        }
+        if options.max_failures.is_some_and(|max| summary.failed_count() > max) {
+            summary.aborted = Some(total - index);
+            break;
+        }
+        processed += 1;
+        if let Some(batch) = &mut summary.batch {
+            batch.last_processed = Some(job.input_path.clone());
+        }
+        observer.on_event(Event::FileStarted { job: &job, index, total });
+        if job.size > options.max_file_size {
+            summary.large_files.push((job.input_path.clone(), job.size));
+            if options.large_files == LargeFiles::Skip {
+                observer.on_event(Event::FileSkipped { job: &job, reason: &t("convert.too_large") });
+                summary.skipped += 1;
+                summary.rows.push(FileRow { file: job.input_path.clone(), status: FileStatus::Skipped, keys: None, filled_keys: None, reason: t("convert.too_large") });
+                continue;
+            }
+        }
+        if !options.incremental {
+            convert_and_notify(&job, options, &mut summary, staging.as_mut(), observer);
+            continue;
+        }
+        let content_hash = match input_hash(&job, options) {
+            Ok(hash) => hash,
+            Err(_) => {
+                let error = tf("io.read_failed", &[&job.input_path]);
+                observer.on_event(Event::FileFailed { job: &job, error: &error });
+                summary.fail(Stage::Read, &job, error);
+                continue;
+            }
+        };
+        if cache.is_fresh(&options_hash, &job.input_path, &content_hash, &job.output_path) {
+            observer.on_event(Event::FileSkipped { job: &job, reason: &t("convert.unchanged") });
+            tracing::info!(path = %job.input_path, "skipped unchanged file");
+            summary.skipped += 1;
+            let sidecar = comments::sidecar_path(&job.output_path);
+            if options.comments_sidecar && job.target == Format::Json && Path::new(&sidecar).exists() {
+                summary.outputs.push(sidecar);
+            }
+            summary.outputs.push(job.output_path.clone());
+            summary.rows.push(FileRow { file: job.input_path.clone(), status: FileStatus::Skipped, keys: None, filled_keys: None, reason: t("convert.unchanged") });
+            continue;
+        }
+        if convert_and_notify(&job, options, &mut summary, staging.as_mut(), observer) {
+            cache.files.insert(job.input_path.clone(), content_hash);
+        } else {
+            cache.files.shift_remove(&job.input_path);
+        }
+    }
+
+    // 中断して処理しなかったファイルも次の実行で続ける
+    if let Some(batch) = &mut summary.batch {
+        batch.remaining += total - processed;
+    }
+    if let Some(staging) = staging {
+        summary.transaction = Some(if summary.has_failures() || summary.interrupted {
+            staging.rollback();
+            Transaction::RolledBack
+        } else {
+            match staging.commit() {
+                Ok(count) => Transaction::Committed(count),
+                Err(e) => {
+                    summary.failed_writes.push_back(e);
+                    Transaction::RolledBack
+                }
+            }
+        });
+    }
+    let rolled_back = summary.transaction == Some(Transaction::RolledBack);
+    if options.delete_stale && !summary.has_failures() && !summary.interrupted {
+        summary.deleted = cleanup::delete_stale(options);
+    }
+    if options.incremental && !options.dry_run && !rolled_back {
+        cache.options = options_hash;
+        if let Err(e) = cache.save(options.output_root()) {
+            println!("{}", e);
+        }
+    }
+    if let (Some(export), Some(path), false) = (options.export, &options.export_to, options.dry_run || summary.interrupted || rolled_back) {
+        let contents = export::render_combined(export, std::mem::take(&mut summary.exported_lines), options.sort);
+        match export::save_text(path, &contents) {
+            Ok(_) => {
+                println!("{}", tf("export.written", &[path]));
+                summary.outputs.push(path.clone());
+            }
+            Err(e) => summary.failed_writes.push_back(e),
+        }
+    }
+    if let (Some((path, format)), false) = (&options.manifest, options.dry_run || rolled_back) {
+        match manifest::write_manifest(path, *format, &summary.outputs, options.output_root()) {
+            Ok(count) => println!("{}", tf("manifest.written", &[path, &count])),
+            Err(e) => println!("{}", e),
+        }
+    }
+    observer.on_event(Event::BatchCompleted { summary: &summary });
+    if let Some(path) = &options.report {
+        if let Err(e) = report::write_json_report(path, &summary.rows, &summary.timings, &summary.retried_writes, summary.batch.as_ref(), options.write.encoding) {
+            println!("{}", e);
+        }
+    }
+    if options.run_log && !options.dry_run {
+        if let Err(e) = run_log::append(options, &summary) {
+            println!("{}", e);
+        }
+    }
+    summary
+}
+
+/// 変換結果をメモリ上で生成し、出力ディレクトリの内容と一致するか確かめる(ファイルは書き込まない)
+/// regenerateは出力を作り直すためのコマンド
+pub fn check_outputs(options: &ConvertOptions, regenerate: &str) -> bool {
+    let mut summary = Summary::default();
+    let mut missing = Vec::new();
+    let mut stale = Vec::new();
+    let jobs = select_jobs(options, &mut summary);
+    if options.export_to.is_none() && record_output_collisions(&jobs, &mut summary) {
+        summary.print(options);
+        return false;
+    }
+    for job in jobs {
+        let Some(applied) = load_and_apply(&job, options, &mut summary) else {
+            continue;
+        };
+        let (lang_map, routed) = split_routes(&job, options, applied.lang_map);
+        let lang_map = match options.merge_into_existing {
+            Some(policy) => match existing::merge_into_existing(&job.output_path, job.target, lang_map, policy, &options.write.read_back()) {
+                Ok(lang_map) => lang_map,
+                Err(e) => {
+                    summary.fail(Stage::Write, &job, e);
+                    continue;
+                }
+            },
+            None => lang_map,
+        };
+        if let (Some(export), Some(_)) = (options.export, &options.export_to) {
+            summary.exported_lines.extend(export.lines(&lang_map));
+            continue;
+        }
+        let write = write_options_for(&job, options);
+        let rendered = match (options.export, load_comments(&job, options)) {
+            (Some(export), _) => Ok(export.render(&lang_map)),
+            (None, Err(e)) => Err(e),
+            (None, Ok(Some(comments))) if job.target == Format::Lang => {
+                let mut buffer = Vec::new();
+                let lines = comments.attach(&lang_map);
+                format::write_encoded(&mut buffer, write.encoding, |mut out| format::write_lang_lines(&mut out, &lines, &write)).map(|_| buffer).map_err(|e| e.to_string())
+            }
+            (None, Ok(_)) => job.target.render(&lang_map, &write),
+        };
+        let mut outputs = vec![(job.output_path.clone(), rendered)];
+        for (route_path, route_map) in &routed {
+            let rendered = match options.export {
+                Some(export) => Ok(export.render(route_map)),
+                None => job.target.render(route_map, &write),
+            };
+            outputs.push((route_path.clone(), rendered));
+        }
+        for (output_path, rendered) in outputs {
+            let expected = match rendered {
+                Ok(expected) => expected,
+                Err(e) => {
+                    summary.fail(Stage::Write, &job, e);
+                    break;
+                }
+            };
+            match fs::read(&output_path) {
+                Ok(actual) if actual == expected => {}
+                Ok(_) => stale.push(output_path),
+                Err(_) => missing.push(output_path),
+            }
+        }
+    }
+    if let (Some(export), Some(path)) = (options.export, &options.export_to) {
+        let expected = export::render_combined(export, std::mem::take(&mut summary.exported_lines), options.sort);
+        match fs::read(path) {
+            Ok(actual) if actual == expected => {}
+            Ok(_) => stale.push(path.clone()),
+            Err(_) => missing.push(path.clone()),
+        }
+    }
+
+    let up_to_date = missing.is_empty() && stale.is_empty();
+    for (title, paths) in [(t("check.missing"), &missing), (t("check.stale"), &stale)] {
+        if !paths.is_empty() {
+            println!("{}", title);
+            for path in paths {
+                println!("- {}", path);
+            }
+        }
+    }
+    if up_to_date && !summary.has_failures() {
+        println!("{}", t("check.up_to_date"));
+        return true;
+    }
+    if summary.has_failures() {
+        summary.print(options);
+    }
+    if !up_to_date {
+        println!("\n{}\n  {}", t("check.regenerate"), regenerate);
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(paths: &[PathBuf]) -> Vec<String> {
+        paths.iter().map(|path| path.file_name().unwrap().to_str().unwrap().to_string()).collect()
+    }
+
+    #[test]
+    fn sort_paths_uses_byte_order() {
+        let mut paths: Vec<PathBuf> = ["b.lang", "a.lang", "B.lang", "_.lang", "a.json", "ä.lang", "10.lang", "9.lang"].iter().map(PathBuf::from).collect();
+        sort_paths(&mut paths);
+        // 大文字は小文字より前、数字は文字として比べ、非ASCIIはASCIIの後
+        assert_eq!(names(&paths), ["10.lang", "9.lang", "B.lang", "_.lang", "a.json", "a.lang", "b.lang", "ä.lang"]);
+    }
+
+    #[test]
+    fn sort_paths_compares_whole_paths() {
+        let mut paths: Vec<PathBuf> = ["a/b.lang", "a.b/c.lang", "a-b/c.lang"].iter().map(PathBuf::from).collect();
+        sort_paths(&mut paths);
+        // '-'(0x2D) < '.'(0x2E) < '/'(0x2F)
+        let sorted: Vec<&str> = paths.iter().map(|path| path.to_str().unwrap()).collect();
+        assert_eq!(sorted, ["a-b/c.lang", "a.b/c.lang", "a/b.lang"]);
+    }
+
+    #[test]
+    fn input_files_and_plan_jobs_follow_byte_order() {
+        let dir = std::env::temp_dir().join(format!("json_lang_sort_test_{}", std::process::id()));
+        let input = dir.join("input");
+        fs::create_dir_all(input.join("sub")).unwrap();
+        for name in ["zh_cn.lang", "en_us.json", "Ja_JP.lang", "de_de.lang", "sub/fr_fr.lang", "notes.txt"] {
+            fs::write(input.join(name), if name.ends_with(".json") { "{}" } else { "" }).unwrap();
+        }
+        let input_dir = input.to_str().unwrap().to_string();
+        let expected = ["Ja_JP.lang", "de_de.lang", "en_us.json", "zh_cn.lang"];
+        assert_eq!(names(&input_files(&input_dir).unwrap()), expected);
+
+        let options = ConvertOptions { input_dir: input_dir.clone(), output_dir: dir.join("output").to_str().unwrap().to_string(), ..ConvertOptions::default() };
+        let (jobs, warnings) = plan_jobs(&options).unwrap();
+        assert!(warnings.is_empty());
+        let planned: Vec<&str> = jobs.iter().map(|job| job.input_path.rsplit('/').next().unwrap()).collect();
+        assert_eq!(planned, expected);
+
+        // --recursiveでもサブディレクトリの中身を含めて相対パスのバイト順になる
+        let options = ConvertOptions { recursive: true, ..options };
+        let (jobs, _) = plan_jobs(&options).unwrap();
+        let planned: Vec<&str> = jobs.iter().map(|job| job.input_path.strip_prefix(&input_dir).unwrap()).collect();
+        assert_eq!(planned, ["/Ja_JP.lang", "/de_de.lang", "/en_us.json", "/sub/fr_fr.lang", "/zh_cn.lang"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn max_files_keeps_the_first_files_in_byte_order() {
+        let dir = std::env::temp_dir().join(format!("json_lang_max_files_test_{}", std::process::id()));
+        let input = dir.join("input");
+        for name in ["a/x.lang", "b/y.lang", "z.lang"] {
+            let path = input.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        let input_dir = input.to_str().unwrap().to_string();
+        let options = ConvertOptions { input_dir: input_dir.clone(), output_dir: dir.join("output").to_str().unwrap().to_string(), recursive: true, max_files: 2, ..ConvertOptions::default() };
+        let (jobs, warnings) = plan_jobs(&options).unwrap();
+        let planned: Vec<&str> = jobs.iter().map(|job| job.input_path.strip_prefix(&input_dir).unwrap()).collect();
+        assert_eq!(planned, ["/a/x.lang", "/b/y.lang"]);
+        assert_eq!(warnings.len(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
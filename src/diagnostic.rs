@@ -0,0 +1,204 @@
+//! 途中でやめずにすべての問題を報告する.langの解析(エディターとの連携向け)
+//!
+//! どんなバイト列を渡してもパニックしない。読み込み方はparse_lang_mapと同じ(先頭の `=` で区切り、キーと値の前後の空白を除く)だが、
+//! ファイル先頭のBOMは取り除く。値の中の認識できないエスケープ(`\q` など)は警告にし、値はそのまま残す。
+//! 行・列は0始まりで、列は文字単位
+
+use std::fmt;
+use std::ops::Range;
+use indexmap::IndexMap;
+use crate::codec::{parse_entry_with, ReadOptions};
+use crate::i18n::{t, tf};
+use crate::lang_map::LangMap;
+
+/// 問題の重さ
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    /// その行は読み込めない
+    Error,
+    /// 読み込めるが、意図と違う可能性がある
+    Warning,
+}
+
+/// 1つの問題
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// 行番号(0始まり)
+    pub line: usize,
+    /// 行内の文字の範囲(0始まり)
+    pub columns: Range<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", tf("diagnostic.position", &[&(self.line + 1), &(self.columns.start + 1), &self.message]))
+    }
+}
+
+/// バイト位置を文字単位の列にする
+fn column(line: &str, byte: usize) -> usize {
+    line[..byte].chars().count()
+}
+
+/// 値の中で意味を持つエスケープ(`\n` `\t` `\r` `\\` `\#` `\"` `\'`、4桁の16進数が続く `\u`)以外の `\` を報告する。
+/// valueは値の行内での開始位置(文字単位)からの文字列
+fn check_escapes(value: &str, start: usize, number: usize, diagnostics: &mut Vec<Diagnostic>) {
+    let chars: Vec<char> = value.chars().collect();
+    let mut at = 0;
+    while at < chars.len() {
+        if chars[at] != '\\' {
+            at += 1;
+            continue;
+        }
+        let length = match chars.get(at + 1) {
+            Some('n' | 't' | 'r' | '\\' | '#' | '"' | '\'') => 2,
+            Some('u') if chars.len() >= at + 6 && chars[at + 2..at + 6].iter().all(char::is_ascii_hexdigit) => 6,
+            next => {
+                let length = if next.is_some() { 2 } else { 1 };
+                let escape: String = chars[at..at + length].iter().collect();
+                diagnostics.push(Diagnostic {
+                    severity: Severity::Warning,
+                    line: number,
+                    columns: start + at..start + at + length,
+                    message: tf("diagnostic.invalid_escape", &[&escape]),
+                });
+                length
+            }
+        };
+        at += length;
+    }
+}
+
+/// .langを解析し、読み込めた内容と見つかったすべての問題を返す
+pub fn parse_lang_lossy(bytes: &[u8]) -> (LangMap, Vec<Diagnostic>) {
+    let mut diagnostics = Vec::new();
+    let text = String::from_utf8_lossy(bytes);
+    let mut text: &str = &text;
+    if let Some(rest) = text.strip_prefix('\u{FEFF}') {
+        text = rest;
+    }
+    let options = ReadOptions::default();
+    let mut lang_map = LangMap::default();
+    let mut first_lines: IndexMap<String, usize> = IndexMap::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        let width = line.chars().count();
+        for (at, c) in line.chars().enumerate() {
+            match c {
+                char::REPLACEMENT_CHARACTER => diagnostics.push(Diagnostic {
+                    severity: Severity::Error,
+                    line: number,
+                    columns: at..at + 1,
+                    message: t("diagnostic.invalid_utf8"),
+                }),
+                '\u{FEFF}' => diagnostics.push(Diagnostic {
+                    severity: Severity::Warning,
+                    line: number,
+                    columns: at..at + 1,
+                    message: t("diagnostic.stray_bom"),
+                }),
+                _ => {}
+            }
+        }
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = parse_entry_with(line, &options) else {
+            diagnostics.push(Diagnostic { severity: Severity::Error, line: number, columns: 0..width, message: t("diagnostic.missing_separator") });
+            continue;
+        };
+        if key.is_empty() {
+            let separator = line.find('=').map_or(0, |byte| column(line, byte));
+            diagnostics.push(Diagnostic { severity: Severity::Warning, line: number, columns: separator..separator + 1, message: t("diagnostic.empty_key") });
+        }
+        let key_start = column(line, line.len() - line.trim_start().len());
+        let key_columns = key_start..key_start + key.chars().count();
+        if let Some(first) = first_lines.get(key) {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Warning,
+                line: number,
+                columns: key_columns,
+                message: tf("diagnostic.duplicate_key", &[&key, &(first + 1)]),
+            });
+        } else {
+            first_lines.insert(key.to_string(), number);
+        }
+        if let Some(separator) = line.find('=') {
+            let value_text = &line[separator + 1..];
+            let value_start = column(line, separator + 1) + value_text.chars().count() - value_text.trim_start().chars().count();
+            check_escapes(value_text.trim(), value_start, number, &mut diagnostics);
+        }
+        lang_map.insert(key.to_string(), value.into_owned());
+    }
+    (lang_map, diagnostics)
+}
+
+/// エラーの問題が1つでもあれば失敗にする。警告は無視する
+pub fn parse_lang_strict(bytes: &[u8]) -> Result<LangMap, Vec<Diagnostic>> {
+    let (lang_map, diagnostics) = parse_lang_lossy(bytes);
+    let errors: Vec<Diagnostic> = diagnostics.into_iter().filter(|diagnostic| diagnostic.severity == Severity::Error).collect();
+    if errors.is_empty() {
+        Ok(lang_map)
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns_of(text: &str) -> Vec<(Severity, usize, Range<usize>)> {
+        parse_lang_lossy(text.as_bytes()).1.into_iter().map(|diagnostic| (diagnostic.severity, diagnostic.line, diagnostic.columns)).collect()
+    }
+
+    #[test]
+    fn reports_every_problem_without_stopping() {
+        let (lang_map, diagnostics) = parse_lang_lossy("a=1\nbroken\n=empty\na=2\n".as_bytes());
+        assert_eq!(lang_map.get("a").map(String::as_str), Some("2"));
+        assert_eq!(lang_map.get("").map(String::as_str), Some("empty"));
+        let found: Vec<(Severity, usize)> = diagnostics.iter().map(|diagnostic| (diagnostic.severity, diagnostic.line)).collect();
+        assert_eq!(found, [(Severity::Error, 1), (Severity::Warning, 2), (Severity::Warning, 3)]);
+    }
+
+    #[test]
+    fn leading_bom_is_ignored_and_stray_bom_is_reported() {
+        assert!(parse_lang_lossy("\u{FEFF}a=1\n".as_bytes()).1.is_empty());
+        assert_eq!(columns_of("a=1\nb=x\u{FEFF}\n"), [(Severity::Warning, 1, 3..4)]);
+    }
+
+    #[test]
+    fn invalid_utf8_is_an_error() {
+        let (lang_map, diagnostics) = parse_lang_lossy(b"a=\xFF\n");
+        assert_eq!(lang_map.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].columns, 2..3);
+    }
+
+    #[test]
+    fn unknown_escapes_are_warnings_with_their_columns() {
+        assert!(columns_of("a=line\\nnext \\t \\\\ \\# \\u00e9\n").is_empty());
+        assert_eq!(columns_of("a= x\\qy\n"), [(Severity::Warning, 0, 4..6)]);
+        assert_eq!(columns_of("a=あ\\u12\n"), [(Severity::Warning, 0, 3..5)]);
+        assert_eq!(columns_of("a=end\\\n"), [(Severity::Warning, 0, 5..6)]);
+    }
+
+    #[test]
+    fn strict_parse_fails_only_on_errors() {
+        assert_eq!(parse_lang_strict("a=1\na=\\q\n".as_bytes()).unwrap().len(), 1);
+        let errors = parse_lang_strict("a=1\nbroken\n".as_bytes()).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, 1);
+    }
+
+    #[test]
+    fn arbitrary_bytes_do_not_panic() {
+        let mut bytes: Vec<u8> = (0..=255u8).collect();
+        bytes.extend_from_slice("=\\\u{FEFF}\r\n=\\u\n\\".as_bytes());
+        for start in 0..bytes.len() {
+            let _ = parse_lang_lossy(&bytes[start..]);
+        }
+    }
+}
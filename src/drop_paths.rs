@@ -0,0 +1,101 @@
+//! 位置引数で渡されたファイル・ディレクトリの変換(実行ファイルへのドラッグ&ドロップ向け)
+
+use std::io::{self, Write};
+use std::path::Path;
+use glob::Pattern;
+use crate::cancel;
+use crate::convert::{process_files, ConvertOptions, Mode};
+use crate::filter::FileFilter;
+use crate::format::Format;
+use crate::i18n::{t, tf};
+use crate::settings::Settings;
+
+/// 同じディレクトリにあり同じ方向に変換するファイルの組
+struct FileGroup {
+    dir: String,
+    mode: Mode,
+    names: Vec<Pattern>,
+}
+
+/// ファイルの形式から変換方向を決める
+fn mode_for(format: Format) -> Mode {
+    match format {
+        Format::Lang => Mode::Lang2json,
+        Format::Json => Mode::Json2lang,
+    }
+}
+
+/// 渡されたパスを変換する。ファイルは入力と同じディレクトリに、
+/// ディレクトリは入力ディレクトリとして設定の出力ディレクトリに書き出す
+fn convert_paths(paths: &[String], settings: &Settings) -> bool {
+    let mut groups: Vec<FileGroup> = Vec::new();
+    let mut dirs = Vec::new();
+    let mut rejected = Vec::new();
+    for path in paths {
+        let file = Path::new(path);
+        if file.is_dir() {
+            dirs.push(path.clone());
+            continue;
+        }
+        if !file.is_file() {
+            rejected.push(tf("drop.not_found", &[path]));
+            continue;
+        }
+        let (Some(format), Some(name)) = (Format::from_path(file), file.file_name().and_then(|n| n.to_str())) else {
+            rejected.push(tf("drop.unsupported", &[path]));
+            continue;
+        };
+        let dir = match file.parent().and_then(|p| p.to_str()) {
+            Some("") | None => ".".to_string(),
+            Some(dir) => dir.to_string(),
+        };
+        let mode = mode_for(format);
+        let name = Pattern::new(&Pattern::escape(name)).unwrap();
+        match groups.iter_mut().find(|g| g.dir == dir && g.mode == mode) {
+            Some(group) => group.names.push(name),
+            None => groups.push(FileGroup { dir, mode, names: vec![name] }),
+        }
+    }
+
+    let mut runs: Vec<ConvertOptions> = groups
+        .into_iter()
+        .map(|group| ConvertOptions {
+            input_dir: group.dir,
+            in_place: true,
+            files: FileFilter { include: group.names, exclude: Vec::new() },
+            ..settings.to_options(group.mode)
+        })
+        .collect();
+    runs.extend(dirs.into_iter().map(|dir| ConvertOptions { input_dir: dir, ..settings.to_options(Mode::All) }));
+
+    let mut ok = rejected.is_empty();
+    cancel::install_handler();
+    for options in &runs {
+        if let Err(e) = options.validate() {
+            println!("{}", tf("error.prefix", &[&e]));
+            ok = false;
+            continue;
+        }
+        let summary = process_files(options);
+        ok &= !summary.has_failures();
+        if summary.interrupted {
+            std::process::exit(cancel::EXIT_INTERRUPTED);
+        }
+    }
+    if !rejected.is_empty() {
+        println!("\n{}", t("drop.rejected"));
+        for reason in &rejected {
+            println!("- {}", reason);
+        }
+    }
+    ok
+}
+
+/// パスを変換し、ドラッグ&ドロップで開いたコンソールがすぐ閉じないようEnterを待ってから終了する
+pub fn run(paths: &[String]) -> ! {
+    let ok = convert_paths(paths, &Settings::load());
+    print!("\n{} ", t("drop.press_enter"));
+    io::stdout().flush().unwrap();
+    let _ = io::stdin().read_line(&mut String::new());
+    std::process::exit(if ok { 0 } else { 1 });
+}
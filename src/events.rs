@@ -0,0 +1,34 @@
+//! 一括変換の進み具合の通知
+//!
+//! ファイルごとの開始・読み込み・完了・省略・失敗と、全体の完了を受け取る。
+//! 一括変換の計画(J)と結果(S)の型は使う側が決める(json_langのバイナリではconvertのJobとSummary)
+
+/// 変換の途中で起きたこと
+pub enum Event<'a, J, S> {
+    /// 変換を始める。totalは処理する予定のファイル数
+    BatchStarted { total: usize },
+    /// index番目(0始まり)のファイルの処理を始める
+    FileStarted { job: &'a J, index: usize, total: usize },
+    /// 読み込みと加工が終わった。notesは加工の報告
+    FileLoaded { job: &'a J, notes: &'a [String] },
+    /// 変換して保存した(--dry-runでは保存せずに完了した)。keysは出力したキーの数
+    FileCompleted { job: &'a J, keys: usize },
+    /// 変換しなかった(変更なし、大きすぎるなど)
+    FileSkipped { job: &'a J, reason: &'a str },
+    /// 失敗した
+    FileFailed { job: &'a J, error: &'a str },
+    /// すべて終わった
+    BatchCompleted { summary: &'a S },
+}
+
+/// 変換の通知を受け取る
+pub trait Observer<J, S> {
+    fn on_event(&mut self, event: Event<J, S>);
+}
+
+/// 関数やクロージャーもObserverとして使える
+impl<J, S, F: FnMut(Event<J, S>)> Observer<J, S> for F {
+    fn on_event(&mut self, event: Event<J, S>) {
+        self(event)
+    }
+}
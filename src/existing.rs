@@ -0,0 +1,31 @@
+//! 既存の出力ファイルへの追記・マージ(--merge-into-existing)
+
+use std::path::Path;
+use indexmap::IndexMap;
+use crate::format::{Format, ReadOptions};
+use crate::i18n::tf;
+use json_lang::lang_map::LangMap;
+
+pub use json_lang::lang_map::ExistingPolicy;
+
+/// 既存の出力を読み込み、新しいキーをマージした内容を返す。
+/// 既存のキーは元の順序のまま残し、新しいキーはその後ろに追加する。
+/// 既存の出力が読み込めない場合は上書きせずにエラーにする
+pub fn merge_into_existing(
+    output_path: &str,
+    format: Format,
+    lang_map: IndexMap<String, String>,
+    policy: ExistingPolicy,
+    read: &ReadOptions,
+) -> Result<IndexMap<String, String>, String> {
+    if !Path::new(output_path).exists() {
+        return Ok(lang_map);
+    }
+    let existing = format.load_with(output_path, read).map(|loaded| loaded.lang_map).map_err(|e| tf("existing.unreadable", &[&output_path, &e]))?;
+    let mut merged = LangMap::from(existing);
+    let conflicts = merged.merge(&LangMap::from(lang_map), policy);
+    if !conflicts.is_empty() {
+        return Err(tf("existing.conflicts", &[&output_path, &conflicts.join(", ")]));
+    }
+    Ok(merged.into())
+}
@@ -0,0 +1,137 @@
+//! キーだけ・値だけの一覧の書き出し(--export)。用語集の抽出やスペルチェック向け
+
+use std::io::Write;
+use clap::ValueEnum;
+use indexmap::{IndexMap, IndexSet};
+use crate::format::create_output;
+use crate::i18n::tf;
+use crate::transform::sort::SortOrder;
+
+/// 書き出す内容
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Export {
+    /// 1行に1つのキー
+    Keys,
+    /// 1行に1つの値(改行とバックスラッシュは `\n` `\\` のようにエスケープする)
+    Values,
+}
+
+/// 値の中の改行が行の区切りにならないようエスケープする
+fn escape_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\n', "\\n").replace('\r', "\\r")
+}
+
+impl Export {
+    /// 出力ファイルの拡張子(`{ext}`)
+    pub fn extension(self) -> &'static str {
+        match self {
+            Export::Keys => "keys.txt",
+            Export::Values => "values.txt",
+        }
+    }
+
+    /// 書き出す行
+    pub fn lines(self, lang_map: &IndexMap<String, String>) -> Vec<String> {
+        match self {
+            Export::Keys => lang_map.keys().cloned().collect(),
+            Export::Values => lang_map.values().map(|value| escape_value(value)).collect(),
+        }
+    }
+
+    /// 1ファイル分の内容
+    pub fn render(self, lang_map: &IndexMap<String, String>) -> Vec<u8> {
+        render_lines(&self.lines(lang_map))
+    }
+
+    /// 戻り値はファイル作成を再試行した回数
+    pub fn save(self, output_path: &str, lang_map: &IndexMap<String, String>) -> Result<u32, String> {
+        save_text(output_path, &self.render(lang_map))
+    }
+}
+
+fn render_lines(lines: &[String]) -> Vec<u8> {
+    lines.iter().flat_map(|line| [line.as_bytes(), b"\n"]).flatten().copied().collect()
+}
+
+/// 内容をそのまま書き出す。戻り値はファイル作成を再試行した回数
+pub fn save_text(output_path: &str, contents: &[u8]) -> Result<u32, String> {
+    let (mut writer, retries) = create_output(output_path, "io.kind.text")?;
+    writer.write_all(contents).and_then(|_| writer.flush()).map_err(|_| tf("io.write_failed", &[&output_path]))?;
+    Ok(retries)
+}
+
+/// 全ファイルの行を1つにまとめた内容。キーは重複を除き、--sort keysなら全体を並べ替える
+pub fn render_combined(export: Export, lines: Vec<String>, sort: SortOrder) -> Vec<u8> {
+    let mut lines = match export {
+        Export::Keys => lines.into_iter().collect::<IndexSet<String>>().into_iter().collect(),
+        Export::Values => lines,
+    };
+    if export == Export::Keys && sort == SortOrder::Keys {
+        lines.sort();
+    }
+    render_lines(&lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(pairs: &[(&str, &str)]) -> IndexMap<String, String> {
+        pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+    }
+
+    fn text(bytes: Vec<u8>) -> String {
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn keys_are_written_one_per_line_in_file_order() {
+        let lang_map = map(&[("b.key", "B"), ("a.key", "A")]);
+        assert_eq!(text(Export::Keys.render(&lang_map)), "b.key\na.key\n");
+    }
+
+    #[test]
+    fn values_escape_newlines_and_backslashes() {
+        let lang_map = map(&[("a", "line1\nline2"), ("b", "C:\\path"), ("c", "cr\r")]);
+        assert_eq!(text(Export::Values.render(&lang_map)), "line1\\nline2\nC:\\\\path\ncr\\r\n");
+    }
+
+    #[test]
+    fn empty_map_renders_nothing() {
+        assert!(Export::Keys.render(&IndexMap::new()).is_empty());
+        assert!(Export::Values.render(&IndexMap::new()).is_empty());
+    }
+
+    #[test]
+    fn combined_keys_drop_duplicates_across_files() {
+        let mut lines = Export::Keys.lines(&map(&[("b", "1"), ("a", "2")]));
+        lines.extend(Export::Keys.lines(&map(&[("a", "3"), ("c", "4")])));
+        assert_eq!(text(render_combined(Export::Keys, lines, SortOrder::None)), "b\na\nc\n");
+    }
+
+    #[test]
+    fn combined_keys_are_sorted_with_sort_keys() {
+        let mut lines = Export::Keys.lines(&map(&[("b", "1"), ("a", "2")]));
+        lines.extend(Export::Keys.lines(&map(&[("a", "3"), ("c", "4")])));
+        assert_eq!(text(render_combined(Export::Keys, lines, SortOrder::Keys)), "a\nb\nc\n");
+    }
+
+    #[test]
+    fn combined_values_keep_duplicates_and_order() {
+        let mut lines = Export::Values.lines(&map(&[("b", "same"), ("a", "z")]));
+        lines.extend(Export::Values.lines(&map(&[("c", "same")])));
+        assert_eq!(text(render_combined(Export::Values, lines, SortOrder::Keys)), "same\nz\nsame\n");
+    }
+
+    #[test]
+    fn save_text_writes_contents() {
+        let dir = std::env::temp_dir().join(format!("json_lang_export_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("out.keys.txt");
+        let path_str = path.to_str().unwrap();
+        let contents = Export::Keys.render(&map(&[("a", "1")]));
+        assert_eq!(save_text(path_str, &contents).unwrap(), 0);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "a\n");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,67 @@
+//! .lang/.json以外の拡張子を既存の形式として扱う対応表(--ext-map txt=lang,locale=lang)
+
+use std::path::Path;
+use clap::ValueEnum;
+use crate::format::Format;
+use crate::i18n::tf;
+
+/// 追加の拡張子と、それを読み込む形式
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExtMap(Vec<(String, Format)>);
+
+impl ExtMap {
+    /// `拡張子=形式` をカンマで区切った指定を読む。形式はlang/jsonのどちらか
+    pub fn parse(text: &str) -> Result<ExtMap, String> {
+        let mut entries: Vec<(String, Format)> = Vec::new();
+        for item in text.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+            let Some((extension, format)) = item.split_once('=') else {
+                return Err(tf("convert.ext_map.syntax", &[&item]));
+            };
+            let extension = extension.trim().trim_start_matches('.').to_ascii_lowercase();
+            if extension.is_empty() {
+                return Err(tf("convert.ext_map.empty", &[&item]));
+            }
+            let format = Format::from_str(format.trim(), true).map_err(|_| tf("convert.ext_map.format", &[&item, &format.trim()]))?;
+            if Format::ALL.iter().any(|known| known.extension() == extension) {
+                return Err(tf("convert.ext_map.builtin", &[&extension]));
+            }
+            if entries.iter().any(|(existing, _)| *existing == extension) {
+                return Err(tf("convert.ext_map.repeated", &[&extension]));
+            }
+            entries.push((extension, format));
+        }
+        Ok(ExtMap(entries))
+    }
+
+    /// 対応表で形式を決めた拡張子。.lang/.jsonや対応表に無い拡張子ならNone
+    pub fn mapped(&self, path: &Path) -> Option<(&str, Format)> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        self.0.iter().find(|(mapped, _)| *mapped == extension).map(|(mapped, format)| (mapped.as_str(), *format))
+    }
+
+    /// 拡張子から形式を判定する(.lang/.jsonと対応表の拡張子)
+    pub fn format_of(&self, path: &Path) -> Option<Format> {
+        Format::from_path(path).or_else(|| self.mapped(path).map(|(_, format)| format))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_normalizes_extensions() {
+        let ext_map = ExtMap::parse(" .TXT=lang, locale=JSON ,").unwrap();
+        assert_eq!(ext_map.format_of(Path::new("en_us.txt")), Some(Format::Lang));
+        assert_eq!(ext_map.format_of(Path::new("en_us.Locale")), Some(Format::Json));
+        assert_eq!(ext_map.format_of(Path::new("en_us.lang")), Some(Format::Lang));
+        assert_eq!(ext_map.format_of(Path::new("en_us.csv")), None);
+    }
+
+    #[test]
+    fn parse_rejects_invalid_entries() {
+        for (text, mentioned) in [("txt", "txt"), ("=lang", "=lang"), ("txt=yaml", "yaml"), ("json=lang", "json"), ("txt=lang,TXT=json", "txt")] {
+            assert!(ExtMap::parse(text).unwrap_err().contains(mentioned), "{}", text);
+        }
+    }
+}
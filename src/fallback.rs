@@ -0,0 +1,35 @@
+//! 翻訳が不完全なロケールのキーを基準のロケール(en_usなど)の値で補う(--with-fallback)
+//!
+//! 基準のファイルは各入力ファイルと同じディレクトリの `<ロケール>.<入力と同じ拡張子>`、無ければもう一方の拡張子のファイル。
+//! 基準のファイル自身は補わない。補ったキーはファイルには印を付けず、結果とレポートにだけ件数を記録する
+
+use std::path::{Path, PathBuf};
+use indexmap::IndexMap;
+use crate::format::{Format, ReadOptions};
+use crate::i18n::tf;
+
+/// 基準のファイルのパス。入力ファイルが基準のロケール自身ならNone
+pub fn reference_path(input_path: &str, name: &str, format: Format, locale: &str) -> Option<PathBuf> {
+    if name.eq_ignore_ascii_case(locale) {
+        return None;
+    }
+    let dir = Path::new(input_path).parent().unwrap_or(Path::new(""));
+    let candidates = [format, format.opposite()].map(|format| dir.join(format!("{}.{}", locale, format.extension())));
+    let existing = candidates.iter().find(|path| path.is_file()).cloned();
+    Some(existing.unwrap_or_else(|| candidates[0].clone()))
+}
+
+/// 基準のファイルを読み込み、lang_mapに無いキーを基準のファイルの順で末尾に加える。加えたキーの数を返す
+pub fn fill(lang_map: &mut IndexMap<String, String>, reference: &Path, read: &ReadOptions) -> Result<usize, String> {
+    let path = reference.to_string_lossy();
+    if !reference.is_file() {
+        return Err(tf("convert.fallback_missing", &[&path]));
+    }
+    let format = Format::from_path(reference).ok_or_else(|| tf("io.unsupported_extension", &[&path]))?;
+    let reference_map = format.load_with(&path, read)?.lang_map;
+    let before = lang_map.len();
+    for (key, value) in reference_map {
+        lang_map.entry(key).or_insert(value);
+    }
+    Ok(lang_map.len() - before)
+}
@@ -0,0 +1,111 @@
+//! 他のツール(C#のランチャーなど)から呼ぶためのC ABI
+//!
+//! 文字列はすべてNUL終端のUTF-8。成功すると `*output` に、失敗すると `*error` に新しい文字列を入れるので、
+//! 呼び出し側は使い終わったら `json_lang_free` で解放する。パニックは境界を越えないようにエラーとして返す
+
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use crate::codec::{self, WriteOptions};
+use crate::i18n::{self, t, Language};
+
+/// 変換できた
+pub const JSON_LANG_OK: c_int = 0;
+/// 入力を解析できなかった
+pub const JSON_LANG_PARSE_ERROR: c_int = 1;
+/// 引数がNULL、または入力がUTF-8ではない
+pub const JSON_LANG_INVALID_ARGUMENT: c_int = 2;
+/// 内部でパニックした
+pub const JSON_LANG_PANIC: c_int = 3;
+
+/// NULを含む文字列はCの文字列にできないため、NULを取り除く
+fn to_c_string(text: String) -> *mut c_char {
+    let text = CString::new(text).unwrap_or_else(|e| {
+        let mut bytes = e.into_vec();
+        bytes.retain(|byte| *byte != 0);
+        CString::new(bytes).unwrap_or_default()
+    });
+    text.into_raw()
+}
+
+/// 引数を確かめて変換し、結果を出力引数に入れる
+unsafe fn convert(
+    input: *const c_char,
+    output: *mut *mut c_char,
+    error: *mut *mut c_char,
+    conversion: fn(&str, &WriteOptions) -> Result<String, String>,
+) -> c_int {
+    if output.is_null() || error.is_null() {
+        return JSON_LANG_INVALID_ARGUMENT;
+    }
+    *output = ptr::null_mut();
+    *error = ptr::null_mut();
+    if input.is_null() {
+        *error = to_c_string(t("ffi.input_null"));
+        return JSON_LANG_INVALID_ARGUMENT;
+    }
+    let Ok(text) = CStr::from_ptr(input).to_str() else {
+        *error = to_c_string(t("ffi.input_not_utf8"));
+        return JSON_LANG_INVALID_ARGUMENT;
+    };
+    match panic::catch_unwind(AssertUnwindSafe(|| conversion(text, &WriteOptions::default()))) {
+        Ok(Ok(converted)) => {
+            *output = to_c_string(converted);
+            JSON_LANG_OK
+        }
+        Ok(Err(message)) => {
+            *error = to_c_string(message);
+            JSON_LANG_PARSE_ERROR
+        }
+        Err(_) => {
+            *error = to_c_string(t("ffi.panic"));
+            JSON_LANG_PANIC
+        }
+    }
+}
+
+/// .langのテキストを整形したJSONに変換する。戻り値はJSON_LANG_OKなどの結果コード
+///
+/// # Safety
+/// `input` はNULL、またはNUL終端の文字列を指すこと。`output` と `error` はNULL、または書き込めるポインタを指すこと
+#[no_mangle]
+pub unsafe extern "C" fn json_lang_lang_to_json(input: *const c_char, output: *mut *mut c_char, error: *mut *mut c_char) -> c_int {
+    convert(input, output, error, codec::lang_to_json)
+}
+
+/// JSONのテキストを.langに変換する。戻り値はJSON_LANG_OKなどの結果コード
+///
+/// # Safety
+/// `input` はNULL、またはNUL終端の文字列を指すこと。`output` と `error` はNULL、または書き込めるポインタを指すこと
+#[no_mangle]
+pub unsafe extern "C" fn json_lang_json_to_lang(input: *const c_char, output: *mut *mut c_char, error: *mut *mut c_char) -> c_int {
+    convert(input, output, error, codec::json_to_lang)
+}
+
+/// エラーメッセージの言語を `"ja"` か `"en"` に切り替える。戻り値はJSON_LANG_OKかJSON_LANG_INVALID_ARGUMENT
+///
+/// # Safety
+/// `language` はNULL、またはNUL終端の文字列を指すこと
+#[no_mangle]
+pub unsafe extern "C" fn json_lang_set_language(language: *const c_char) -> c_int {
+    if language.is_null() {
+        return JSON_LANG_INVALID_ARGUMENT;
+    }
+    match CStr::from_ptr(language).to_bytes() {
+        b"ja" => i18n::set_language(Language::Ja),
+        b"en" => i18n::set_language(Language::En),
+        _ => return JSON_LANG_INVALID_ARGUMENT,
+    }
+    JSON_LANG_OK
+}
+
+/// json_lang_lang_to_json/json_lang_json_to_langが返した文字列を解放する。NULLは何もしない
+///
+/// # Safety
+/// `text` はNULL、またはこのライブラリが返してまだ解放していない文字列であること
+#[no_mangle]
+pub unsafe extern "C" fn json_lang_free(text: *mut c_char) {
+    if !text.is_null() {
+        drop(CString::from_raw(text));
+    }
+}
@@ -0,0 +1,36 @@
+use std::path::Path;
+use glob::Pattern;
+use crate::i18n::tf;
+
+/// 入力ファイルを絞り込むglobパターン(入力ディレクトリからの相対パスに対して評価する)
+#[derive(Clone, Debug, Default)]
+pub struct FileFilter {
+    pub include: Vec<Pattern>,
+    pub exclude: Vec<Pattern>,
+}
+
+/// コマンドライン引数のglobパターンを解釈する
+pub fn parse_glob(pattern: &str) -> Result<Pattern, String> {
+    Pattern::new(pattern).map_err(|e| tf("convert.invalid_glob", &[&pattern, &e]))
+}
+
+impl FileFilter {
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// includeのいずれかに一致し(includeが空なら常に一致)、excludeのどれにも一致しないか
+    pub fn accepts(&self, relative: &Path) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|p| p.matches_path(relative)))
+            && !self.exclude.iter().any(|p| p.matches_path(relative))
+    }
+
+    /// 与えたパスのどれにも一致しなかったパターン
+    pub fn unmatched<'a>(&'a self, paths: &[&Path]) -> Vec<&'a Pattern> {
+        self.include
+            .iter()
+            .chain(&self.exclude)
+            .filter(|pattern| !paths.iter().any(|path| pattern.matches_path(path)))
+            .collect()
+    }
+}
@@ -0,0 +1,328 @@
+//! .lang/JSONファイルの読み込みと保存
+//!
+//! テキストの解析と書き出しはライブラリのcodecにあり、ここではファイルの入出力とメッセージを扱う
+
+use std::fs::{self, create_dir_all, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+use indexmap::IndexMap;
+use serde::de;
+use serde_json::error::Category;
+use clap::ValueEnum;
+use crate::i18n::{t, tf};
+pub use json_lang::codec::*;
+
+/// 対応しているファイル形式
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Lang,
+    Json,
+}
+
+impl Format {
+    /// 対応しているすべての形式(メニューの一覧の順)
+    pub const ALL: [Format; 2] = [Format::Lang, Format::Json];
+
+    /// 拡張子から形式を判定する
+    pub fn from_path(path: &Path) -> Option<Format> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("lang") => Some(Format::Lang),
+            Some("json") => Some(Format::Json),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Lang => "lang",
+            Format::Json => "json",
+        }
+    }
+
+    /// 変換先の形式
+    pub fn opposite(self) -> Format {
+        match self {
+            Format::Lang => Format::Json,
+            Format::Json => Format::Lang,
+        }
+    }
+
+    pub fn load(self, file_path: &str) -> Result<IndexMap<String, String>, String> {
+        self.load_with(file_path, &ReadOptions::default()).map(|loaded| loaded.lang_map)
+    }
+
+    /// 読み込み設定を指定して読み込む
+    pub fn load_with(self, file_path: &str, options: &ReadOptions) -> Result<Loaded, String> {
+        match self {
+            Format::Lang => load_lang_file_with(file_path, options),
+            Format::Json => load_json_file(file_path, options),
+        }
+    }
+
+    /// ファイル全体を文字列として読み込まずに読む(JSONは常にそうしている)
+    pub fn load_streaming(self, file_path: &str, options: &ReadOptions) -> Result<Loaded, String> {
+        match self {
+            Format::Lang => load_lang_file_streaming(file_path, options),
+            Format::Json => load_json_file(file_path, options),
+        }
+    }
+
+    pub fn save(self, output_path: &str, lang_map: &IndexMap<String, String>) -> Result<u32, String> {
+        self.save_with(output_path, lang_map, &WriteOptions::default())
+    }
+
+    /// 戻り値はファイル作成を再試行した回数
+    pub fn save_with(self, output_path: &str, lang_map: &IndexMap<String, String>, options: &WriteOptions) -> Result<u32, String> {
+        match self {
+            Format::Lang => save_as_lang(output_path, lang_map, options),
+            Format::Json => save_as_pretty_json(output_path, lang_map, options),
+        }
+    }
+
+    /// ファイルに書き込まれる内容をメモリ上に生成する
+    pub fn render(self, lang_map: &IndexMap<String, String>, options: &WriteOptions) -> Result<Vec<u8>, String> {
+        let mut buffer = Vec::new();
+        write_encoded(&mut buffer, options.encoding, |mut out| match self {
+            Format::Lang => write_lang(&mut out, lang_map, options),
+            Format::Json => write_json(&mut out, lang_map, options),
+        })
+        .map_err(|e| e.to_string())?;
+        Ok(buffer)
+    }
+}
+
+/// .langファイルをコメント・空行を保持したまま行単位で読み込む
+pub fn load_lang_lines(file_path: &str) -> Result<Vec<LangLine>, String> {
+    load_lang_lines_with(file_path, &ReadOptions::default())
+}
+
+/// load_lang_linesを読み込み設定に従って行う
+pub fn load_lang_lines_with(file_path: &str, options: &ReadOptions) -> Result<Vec<LangLine>, String> {
+    let contents = fs::read_to_string(file_path).map_err(|_| tf("io.read_failed", &[&file_path]))?;
+    Ok(parse_lang_lines_with(&contents, options))
+}
+
+/// .langファイル全体を読み込んでからマップにする。重複したキーも返す
+pub fn load_lang_file_with(file_path: &str, options: &ReadOptions) -> Result<Loaded, String> {
+    let contents = fs::read_to_string(file_path).map_err(|_| tf("io.read_failed", &[&file_path]))?;
+    Ok(parse_lang_loaded_with(&contents, options))
+}
+
+/// .langファイルを1行ずつ読みながらマップに入れる。ファイル全体を文字列として読み込まないため大きなファイル向け
+pub fn load_lang_file_streaming(file_path: &str, options: &ReadOptions) -> Result<Loaded, String> {
+    let file = File::open(file_path).map_err(|_| tf("io.read_failed", &[&file_path]))?;
+    let mut loaded = Loaded::from(IndexMap::new());
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|_| tf("io.read_failed", &[&file_path]))?;
+        if let Some((key, value)) = parse_entry_with(&line, options) {
+            loaded.insert_entry(key.to_string(), value.into_owned(), options);
+        }
+    }
+    Ok(loaded)
+}
+
+/// JSONファイルを読み込む。値の木を作らず、ファイルを読みながらキーと文字列の値を1件ずつファイル内の順にマップに入れる
+/// 文字列以外の値(--coerceでは文字列にできない値)は読み飛ばす。トップレベルがオブジェクトでなければエラー
+pub fn load_json_file(file_path: &str, options: &ReadOptions) -> Result<Loaded, String> {
+    let file = File::open(file_path).map_err(|_| tf("io.read_failed", &[&file_path]))?;
+    parse_json_map_with(BufReader::new(file), options).map_err(|e| match e.classify() {
+        // 構文は正しいが形が違う(トップレベルがオブジェクトでない)
+        Category::Data => format!("{}: {}", file_path, e),
+        _ => tf("io.json_parse_failed", &[&file_path]),
+    })
+}
+
+/// JSONをマップとして読み、読み飛ばしたキーとその型も返す。トップレベルがオブジェクトでなければエラー
+pub fn parse_json_map_with<R: io::Read>(reader: R, options: &ReadOptions) -> serde_json::Result<Loaded> {
+    let (loaded, top_level) = read_json_map(reader, options)?;
+    if top_level != "object" {
+        return Err(de::Error::custom(tf("io.json_not_object", &[&top_level])));
+    }
+    Ok(loaded)
+}
+
+/// JSONをload_json_fileと同じ規則でマップとして読む
+pub fn parse_json_map<R: io::Read>(reader: R) -> serde_json::Result<IndexMap<String, String>> {
+    parse_json_map_with(reader, &ReadOptions::default()).map(|loaded| loaded.lang_map)
+}
+
+pub fn load_by_extension(file_path: &str) -> Result<IndexMap<String, String>, String> {
+    Format::from_path(Path::new(file_path))
+        .ok_or_else(|| tf("io.unsupported_extension", &[&file_path]))?
+        .load(file_path)
+}
+
+/// 作成の再試行までの待ち時間(3回まで試し、合計で約500ms待つ)
+const CREATE_RETRY_DELAYS: [Duration; 2] = [Duration::from_millis(150), Duration::from_millis(350)];
+
+/// ウイルス対策ソフトや同期ソフトがファイルを開いている間の一時的な失敗か
+fn is_transient(error: &io::Error) -> bool {
+    // ERROR_SHARING_VIOLATION(32), ERROR_LOCK_VIOLATION(33)
+    cfg!(windows) && (error.kind() == io::ErrorKind::PermissionDenied || matches!(error.raw_os_error(), Some(32) | Some(33)))
+}
+
+/// ファイル操作を行い、一時的な失敗は待って再試行する。再試行した回数も返す
+pub fn retry_transient<T>(mut operation: impl FnMut() -> io::Result<T>) -> (io::Result<T>, u32) {
+    let mut retries = 0;
+    loop {
+        match operation() {
+            Err(e) if is_transient(&e) && (retries as usize) < CREATE_RETRY_DELAYS.len() => {
+                thread::sleep(CREATE_RETRY_DELAYS[retries as usize]);
+                retries += 1;
+            }
+            result => return (result, retries),
+        }
+    }
+}
+
+/// 出力先ディレクトリを作成してファイルを開く。一時的な失敗は待って再試行し、再試行した回数も返す
+/// kindはファイルの種類を表すメッセージのキー
+pub fn create_output(output_path: &str, kind: &str) -> Result<(BufWriter<File>, u32), String> {
+    if let Some(parent_dir) = Path::new(output_path).parent() {
+        create_dir_all(parent_dir).map_err(|_| tf("io.mkdir_failed", &[&output_path]))?;
+    }
+    match retry_transient(|| File::create(output_path)) {
+        (Ok(file), retries) => Ok((BufWriter::new(file), retries)),
+        (Err(_), _) => Err(tf("io.create_failed", &[&output_path, &t(kind)])),
+    }
+}
+
+/// JSONファイルに整形して出力する関数
+/// 戻り値はファイル作成を再試行した回数
+pub fn save_as_pretty_json(output_path: &str, lang_map: &IndexMap<String, String>, options: &WriteOptions) -> Result<u32, String> {
+    let (mut writer, retries) = create_output(output_path, "io.kind.json")?;
+    write_encoded(&mut writer, options.encoding, |mut out| write_json(&mut out, lang_map, options))
+        .and_then(|_| writer.flush())
+        .map_err(|_| tf("io.json_write_failed", &[&output_path]))?;
+    Ok(retries)
+}
+
+/// .langファイルとして保存する関数
+pub fn save_as_lang(output_path: &str, lang_map: &IndexMap<String, String>, options: &WriteOptions) -> Result<u32, String> {
+    let (mut writer, retries) = create_output(output_path, "io.kind.lang")?;
+    write_encoded(&mut writer, options.encoding, |mut out| write_lang(&mut out, lang_map, options))
+        .and_then(|_| writer.flush())
+        .map_err(|_| tf("io.write_failed", &[&output_path]))?;
+    Ok(retries)
+}
+
+/// 行単位のデータをコメント・空行ごと.langファイルとして保存する
+pub fn save_lang_lines(output_path: &str, lines: &[LangLine]) -> Result<u32, String> {
+    save_lang_lines_with(output_path, lines, &WriteOptions::default())
+}
+
+/// 書式設定(先頭のコメント・区切り文字・改行コードなど)に従って保存する。見出し(section_depth)は入れない
+pub fn save_lang_lines_with(output_path: &str, lines: &[LangLine], options: &WriteOptions) -> Result<u32, String> {
+    let (mut writer, retries) = create_output(output_path, "io.kind.lang")?;
+    write_encoded(&mut writer, options.encoding, |mut out| write_lang_lines(&mut out, lines, options))
+        .and_then(|_| writer.flush())
+        .map_err(|_| tf("io.write_failed", &[&output_path]))?;
+    Ok(retries)
+}
+
+/// 拡張子(.lang/.json)に応じて保存関数を選ぶ
+pub fn save_by_extension(output_path: &str, lang_map: &IndexMap<String, String>) -> Result<u32, String> {
+    Format::from_path(Path::new(output_path))
+        .ok_or_else(|| tf("io.unsupported_extension", &[&output_path]))?
+        .save(output_path, lang_map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 下層への書き込み回数を数える(ファイルならそれぞれがシステムコールになる)
+    #[derive(Default)]
+    struct CountingWriter {
+        writes: usize,
+        bytes: usize,
+    }
+
+    impl Write for CountingWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.writes += 1;
+            self.bytes += buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn large_map(len: usize) -> IndexMap<String, String> {
+        (0..len).map(|i| (format!("item.synthetic.key{}", i), format!("Synthetic value number {}", i))).collect()
+    }
+
+    /// 書き出しに使った書き込み回数とバイト数を数える
+    fn measure(buffered: bool, write: impl Fn(&mut dyn Write) -> io::Result<()>) -> CountingWriter {
+        let mut counter = CountingWriter::default();
+        if buffered {
+            let mut writer = BufWriter::new(&mut counter);
+            write_encoded(&mut writer, OutputEncoding::Utf8, &write).unwrap();
+            writer.flush().unwrap();
+        } else {
+            write_encoded(&mut counter, OutputEncoding::Utf8, &write).unwrap();
+        }
+        counter
+    }
+
+    #[test]
+    fn buffered_writes_do_not_issue_one_write_per_line() {
+        let lang_map = large_map(100_000);
+        let options = WriteOptions::default();
+        for (name, write) in [
+            ("lang", Box::new(|mut out: &mut dyn Write| write_lang(&mut out, &lang_map, &options)) as Box<dyn Fn(&mut dyn Write) -> io::Result<()>>),
+            ("json", Box::new(|mut out: &mut dyn Write| write_json(&mut out, &lang_map, &options))),
+        ] {
+            let unbuffered = measure(false, &write);
+            let buffered = measure(true, &write);
+            assert_eq!(unbuffered.bytes, buffered.bytes);
+            assert!(unbuffered.writes >= lang_map.len(), "{}: {}", name, unbuffered.writes);
+            // 既定の8KiBのバッファなら、書き込みは出力の大きさ/8KiBの程度になる
+            assert!(buffered.writes <= buffered.bytes / 4096 + 2, "{}: {}", name, buffered.writes);
+        }
+    }
+
+    #[test]
+    fn streaming_json_matches_serde_json_pretty_output() {
+        let lang_map = large_map(1_000);
+        let mut streamed = Vec::new();
+        write_json(&mut streamed, &lang_map, &WriteOptions::default()).unwrap();
+        assert_eq!(String::from_utf8(streamed).unwrap(), serde_json::to_string_pretty(&lang_map).unwrap());
+    }
+
+    #[test]
+    fn json_top_level_must_be_an_object() {
+        for (text, type_name) in [("[1, 2]", "array"), ("\"text\"", "string"), ("42", "number"), ("true", "boolean"), ("null", "null")] {
+            let error = parse_json_map_with(text.as_bytes(), &ReadOptions::default()).err().unwrap();
+            assert_eq!(error.classify(), Category::Data, "{}", text);
+            assert!(error.to_string().contains(type_name), "{}: {}", text, error);
+        }
+    }
+
+    #[test]
+    fn empty_json_object_is_an_empty_map() {
+        assert!(parse_json_map("{}".as_bytes()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn truncated_json_is_a_syntax_error() {
+        let error = parse_json_map("{\"a\": \"b\"".as_bytes()).err().unwrap();
+        assert_eq!(error.classify(), Category::Eof);
+        let error = parse_json_map("{\"a\" \"b\"}".as_bytes()).err().unwrap();
+        assert_eq!(error.classify(), Category::Syntax);
+    }
+
+    #[test]
+    fn json_file_errors_name_the_file_and_type() {
+        let path = std::env::temp_dir().join(format!("json_lang_top_level_test_{}.json", std::process::id()));
+        fs::write(&path, "[]").unwrap();
+        let path_str = path.to_str().unwrap();
+        let error = load_json_file(path_str, &ReadOptions::default()).err().unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(error.contains(path_str) && error.contains("array"), "{}", error);
+    }
+}
@@ -0,0 +1,86 @@
+//! 用語集のための値の一覧(glossaryサブコマンド)
+//!
+//! 同じ値をまとめ、現れた回数と使っているキーを回数の多い順にCSVで書き出す。
+//! --sourceと--targetを指定すると、同じキーの翻訳元の値と翻訳先の値の組をまとめる
+
+use std::fs;
+use indexmap::IndexMap;
+use crate::cli::GlossaryArgs;
+use crate::convert::files_or_input_dir;
+use crate::format::load_by_extension;
+use crate::i18n::tf;
+use crate::report::csv_field;
+
+/// 1つの用語の集計
+struct Term {
+    count: usize,
+    /// 使っているキー(重複なし、現れた順)
+    keys: Vec<String>,
+}
+
+/// 短すぎる値と数字だけの値を除く
+fn accepts(value: &str, args: &GlossaryArgs) -> bool {
+    let trimmed = value.trim();
+    if trimmed.chars().count() < args.min_length {
+        return false;
+    }
+    !(args.skip_numeric && trimmed.chars().all(|c| c.is_ascii_digit() || matches!(c, '.' | ',' | '-' | '+' | '%' | ' ')))
+}
+
+fn record(terms: &mut IndexMap<(String, Option<String>), Term>, term: (String, Option<String>), key: &str) {
+    let entry = terms.entry(term).or_insert(Term { count: 0, keys: Vec::new() });
+    entry.count += 1;
+    if !entry.keys.iter().any(|existing| existing == key) {
+        entry.keys.push(key.to_string());
+    }
+}
+
+/// glossaryサブコマンドの実行
+pub fn run_glossary(args: &GlossaryArgs) -> Result<bool, String> {
+    let mut terms: IndexMap<(String, Option<String>), Term> = IndexMap::new();
+    match (&args.source, &args.target) {
+        (Some(source), Some(target)) => {
+            let source_map = load_by_extension(source)?;
+            let target_map = load_by_extension(target)?;
+            for (key, value) in &source_map {
+                let Some(translated) = target_map.get(key) else {
+                    continue;
+                };
+                if accepts(value, args) {
+                    record(&mut terms, (value.clone(), Some(translated.clone())), key);
+                }
+            }
+        }
+        _ => {
+            for path in files_or_input_dir(&args.files, &args.input)? {
+                let lang_map = load_by_extension(&path.to_string_lossy())?;
+                for (key, value) in &lang_map {
+                    if accepts(value, args) {
+                        record(&mut terms, (value.clone(), None), key);
+                    }
+                }
+            }
+        }
+    }
+    // 回数の多い順。同じ回数なら値の順
+    terms.sort_by(|a_term, a, b_term, b| b.count.cmp(&a.count).then_with(|| a_term.cmp(b_term)));
+
+    let paired = args.target.is_some();
+    let mut contents = String::from(if paired { "source,target,count,keys\n" } else { "value,count,keys\n" });
+    for ((value, translated), term) in &terms {
+        contents.push_str(&csv_field(value));
+        if let Some(translated) = translated {
+            contents.push(',');
+            contents.push_str(&csv_field(translated));
+        }
+        contents.push_str(&format!(",{},{}\n", term.count, csv_field(&term.keys.join("; "))));
+    }
+    match &args.output {
+        Some(path) => {
+            fs::write(path, contents).map_err(|_| tf("io.write_failed", &[path]))?;
+            println!("{}", tf("glossary.written", &[path, &terms.len()]));
+        }
+        None => print!("{}", contents),
+    }
+    Ok(true)
+}
@@ -0,0 +1,95 @@
+//! ツール自身のメッセージの言語切り替え
+//!
+//! メッセージはこのツールの.lang形式で書いたカタログ(ja.lang/en.lang)を埋め込んで読み込む。
+//! 値の `{0}`, `{1}` は引数に置き換える。カタログに無いキーは日本語、それも無ければキーをそのまま使う
+
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+use clap::ValueEnum;
+use indexmap::IndexMap;
+use serde::Deserialize;
+use crate::codec::parse_lang_map;
+
+/// メッセージの言語
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    /// 日本語
+    Ja,
+    /// 英語
+    En,
+}
+
+const JA: &str = include_str!("ja.lang");
+const EN: &str = include_str!("en.lang");
+
+static JAPANESE: OnceLock<IndexMap<String, String>> = OnceLock::new();
+static ENGLISH: OnceLock<IndexMap<String, String>> = OnceLock::new();
+/// 現在の言語(Languageの順番)。設定しなければ日本語
+static CURRENT: AtomicU8 = AtomicU8::new(Language::Ja as u8);
+
+impl Language {
+    /// 環境変数LANGから言語を決める。`en` で始まる場合のみ英語
+    pub fn from_env() -> Option<Language> {
+        let lang = std::env::var("LANG").ok()?;
+        if lang.starts_with("en") {
+            Some(Language::En)
+        } else if lang.starts_with("ja") {
+            Some(Language::Ja)
+        } else {
+            None
+        }
+    }
+}
+
+fn japanese() -> &'static IndexMap<String, String> {
+    JAPANESE.get_or_init(|| parse_lang_map(JA))
+}
+
+/// 使う言語を設定する
+pub fn set_language(language: Language) {
+    CURRENT.store(language as u8, Ordering::Relaxed);
+}
+
+fn catalog() -> &'static IndexMap<String, String> {
+    if CURRENT.load(Ordering::Relaxed) == Language::En as u8 {
+        ENGLISH.get_or_init(|| parse_lang_map(EN))
+    } else {
+        japanese()
+    }
+}
+
+/// キーに対応するメッセージ
+pub fn t(key: &str) -> String {
+    catalog()
+        .get(key)
+        .or_else(|| japanese().get(key))
+        .cloned()
+        .unwrap_or_else(|| key.to_string())
+}
+
+/// キーに対応するメッセージの `{0}`, `{1}`... を引数で置き換える
+pub fn tf(key: &str, args: &[&dyn Display]) -> String {
+    let template = t(key);
+    let mut message = String::with_capacity(template.len());
+    let mut rest = template.as_str();
+    while let Some(start) = rest.find('{') {
+        message.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let digits = after.bytes().take_while(u8::is_ascii_digit).count();
+        let arg = after[..digits].parse::<usize>().ok().and_then(|index| args.get(index));
+        match arg {
+            Some(arg) if after[digits..].starts_with('}') => {
+                message.push_str(&arg.to_string());
+                rest = &after[digits + 1..];
+            }
+            _ => {
+                message.push('{');
+                rest = after;
+            }
+        }
+    }
+    message.push_str(rest);
+    message
+}
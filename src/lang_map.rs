@@ -0,0 +1,270 @@
+//! キーと値の対応表(ファイル内の順序を保つ)と、よく使う操作
+//!
+//! 中身はIndexMapで、Deref/From/Intoで今までどおりIndexMapとしても扱える
+
+use std::cmp::Ordering;
+use std::ops::{Deref, DerefMut};
+use clap::ValueEnum;
+use indexmap::IndexMap;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// キーと値の対応表。シリアライズはキーの順序を保ったJSONオブジェクトになる
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct LangMap(pub IndexMap<String, String>);
+
+/// 既存の出力にも同じキーがあり値が異なる場合の扱い
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ExistingPolicy {
+    /// 新しい値で上書きする
+    Overwrite,
+    /// 既存の値を残す
+    Keep,
+    /// そのファイルを失敗として扱い、書き込まない
+    Error,
+}
+
+/// キーの並び順
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    /// 入力ファイルの順のまま
+    #[default]
+    None,
+    /// キーの辞書順
+    Keys,
+    /// 値の辞書順(値が同じならキーの順)
+    Values,
+}
+
+/// 文字列を比べる。ignore_caseなら大文字と小文字を区別せずに比べ、同じなら区別して比べる
+pub fn compare(a: &str, b: &str, ignore_case: bool) -> Ordering {
+    if ignore_case {
+        a.to_lowercase().cmp(&b.to_lowercase()).then_with(|| a.cmp(b))
+    } else {
+        a.cmp(b)
+    }
+}
+
+/// 2つの対応表の違い
+#[derive(Debug, Default)]
+pub struct DiffResult {
+    /// 比べた側にだけあるキー
+    pub added: Vec<String>,
+    /// 元の側にだけあるキー
+    pub removed: Vec<String>,
+    /// 値が異なるキーと元の値・比べた側の値
+    pub changed: Vec<(String, String, String)>,
+    /// キーと値は同じで順序だけが異なる
+    pub reordered: bool,
+}
+
+impl DiffResult {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty() && !self.reordered
+    }
+}
+
+impl LangMap {
+    /// prefixで始まるキー(ファイル内の順)
+    pub fn keys_with_prefix<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = &'a String> {
+        self.0.keys().filter(move |key| key.starts_with(prefix))
+    }
+
+    /// otherのキーを取り込む。あるキーは元の位置のまま、無いキーは末尾に追加する。
+    /// 値が異なるキーはpolicyに従い、戻り値はErrorのときに衝突したキー
+    pub fn merge(&mut self, other: &LangMap, policy: ExistingPolicy) -> Vec<String> {
+        let mut conflicts = Vec::new();
+        for (key, value) in &other.0 {
+            match self.0.get_mut(key) {
+                Some(existing) if existing == value => {}
+                Some(existing) => match policy {
+                    ExistingPolicy::Overwrite => existing.clone_from(value),
+                    ExistingPolicy::Keep => {}
+                    ExistingPolicy::Error => conflicts.push(key.clone()),
+                },
+                None => {
+                    self.0.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// selfを元、otherを比べた側として違いを調べる
+    pub fn diff(&self, other: &LangMap) -> DiffResult {
+        let mut result = DiffResult::default();
+        for (key, value) in &self.0 {
+            match other.0.get(key) {
+                None => result.removed.push(key.clone()),
+                Some(other_value) if other_value != value => result.changed.push((key.clone(), value.clone(), other_value.clone())),
+                Some(_) => {}
+            }
+        }
+        result.added = other.0.keys().filter(|key| !self.0.contains_key(*key)).cloned().collect();
+        result.reordered = result.is_empty() && !self.0.keys().eq(other.0.keys());
+        result
+    }
+
+    /// 指定した順に並べ替える。ignore_caseなら大文字と小文字を区別せずに比べる
+    pub fn sort_keys(&mut self, order: SortOrder, ignore_case: bool) {
+        match order {
+            SortOrder::None => {}
+            SortOrder::Keys => self.0.sort_by(|a, _, b, _| compare(a, b, ignore_case)),
+            SortOrder::Values => self.0.sort_by(|a_key, a, b_key, b| compare(a, b, ignore_case).then_with(|| compare(a_key, b_key, ignore_case))),
+        }
+    }
+
+    /// 正規表現に合うキーだけを残し、取り除いた件数を返す
+    pub fn retain_matching(&mut self, re: &Regex) -> usize {
+        let before = self.0.len();
+        self.0.retain(|key, _| re.is_match(key));
+        before - self.0.len()
+    }
+
+    /// 正規表現に合うキーを取り除き、取り除いた件数を返す
+    pub fn remove_matching(&mut self, re: &Regex) -> usize {
+        let before = self.0.len();
+        self.0.retain(|key, _| !re.is_match(key));
+        before - self.0.len()
+    }
+}
+
+impl Deref for LangMap {
+    type Target = IndexMap<String, String>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for LangMap {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl From<IndexMap<String, String>> for LangMap {
+    fn from(lang_map: IndexMap<String, String>) -> Self {
+        LangMap(lang_map)
+    }
+}
+
+impl From<LangMap> for IndexMap<String, String> {
+    fn from(lang_map: LangMap) -> Self {
+        lang_map.0
+    }
+}
+
+impl FromIterator<(String, String)> for LangMap {
+    fn from_iter<I: IntoIterator<Item = (String, String)>>(iter: I) -> Self {
+        LangMap(iter.into_iter().collect())
+    }
+}
+
+impl IntoIterator for LangMap {
+    type Item = (String, String);
+    type IntoIter = indexmap::map::IntoIter<String, String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a LangMap {
+    type Item = (&'a String, &'a String);
+    type IntoIter = indexmap::map::Iter<'a, String, String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(pairs: &[(&str, &str)]) -> LangMap {
+        pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn merge_keeps_positions_and_appends_new_keys() {
+        let mut lang_map = map(&[("a", "1"), ("b", "2")]);
+        let conflicts = lang_map.merge(&map(&[("c", "3"), ("a", "1")]), ExistingPolicy::Error);
+        assert!(conflicts.is_empty());
+        assert_eq!(lang_map, map(&[("a", "1"), ("b", "2"), ("c", "3")]));
+    }
+
+    #[test]
+    fn merge_follows_the_policy_for_changed_values() {
+        let other = map(&[("b", "new")]);
+        let mut overwritten = map(&[("a", "1"), ("b", "old")]);
+        assert!(overwritten.merge(&other, ExistingPolicy::Overwrite).is_empty());
+        assert_eq!(overwritten, map(&[("a", "1"), ("b", "new")]));
+
+        let mut kept = map(&[("a", "1"), ("b", "old")]);
+        assert!(kept.merge(&other, ExistingPolicy::Keep).is_empty());
+        assert_eq!(kept, map(&[("a", "1"), ("b", "old")]));
+
+        let mut rejected = map(&[("a", "1"), ("b", "old")]);
+        assert_eq!(rejected.merge(&other, ExistingPolicy::Error), vec!["b".to_string()]);
+        assert_eq!(rejected["b"], "old");
+    }
+
+    #[test]
+    fn diff_reports_added_removed_and_changed_keys() {
+        let diff = map(&[("a", "1"), ("b", "2"), ("c", "3")]).diff(&map(&[("a", "1"), ("b", "two"), ("d", "4")]));
+        assert_eq!(diff.added, vec!["d".to_string()]);
+        assert_eq!(diff.removed, vec!["c".to_string()]);
+        assert_eq!(diff.changed, vec![("b".to_string(), "2".to_string(), "two".to_string())]);
+        assert!(!diff.reordered);
+    }
+
+    #[test]
+    fn diff_detects_reordering_only_when_contents_match() {
+        let diff = map(&[("a", "1"), ("b", "2")]).diff(&map(&[("b", "2"), ("a", "1")]));
+        assert!(diff.reordered && !diff.is_empty());
+        assert!(map(&[("a", "1")]).diff(&map(&[("a", "1")])).is_empty());
+        let changed = map(&[("a", "1"), ("b", "2")]).diff(&map(&[("b", "3"), ("a", "1")]));
+        assert!(!changed.reordered);
+    }
+
+    #[test]
+    fn sort_keys_orders_by_key_or_value() {
+        let mut by_key = map(&[("b", "1"), ("B", "2"), ("a", "3")]);
+        by_key.sort_keys(SortOrder::Keys, false);
+        assert_eq!(by_key.keys().collect::<Vec<_>>(), ["B", "a", "b"]);
+        by_key.sort_keys(SortOrder::Keys, true);
+        assert_eq!(by_key.keys().collect::<Vec<_>>(), ["a", "B", "b"]);
+
+        let mut by_value = map(&[("c", "x"), ("b", "a"), ("a", "x")]);
+        by_value.sort_keys(SortOrder::Values, false);
+        assert_eq!(by_value.keys().collect::<Vec<_>>(), ["b", "a", "c"]);
+
+        let mut unsorted = map(&[("b", "1"), ("a", "2")]);
+        unsorted.sort_keys(SortOrder::None, false);
+        assert_eq!(unsorted.keys().collect::<Vec<_>>(), ["b", "a"]);
+    }
+
+    #[test]
+    fn retain_and_remove_matching_count_removed_keys() {
+        let re = Regex::new(r"^item\.").unwrap();
+        let mut retained = map(&[("item.a", "1"), ("block.b", "2"), ("item.c", "3")]);
+        assert_eq!(retained.retain_matching(&re), 1);
+        assert_eq!(retained.keys().collect::<Vec<_>>(), ["item.a", "item.c"]);
+
+        let mut removed = map(&[("item.a", "1"), ("block.b", "2"), ("item.c", "3")]);
+        assert_eq!(removed.remove_matching(&re), 2);
+        assert_eq!(removed.keys().collect::<Vec<_>>(), ["block.b"]);
+    }
+
+    #[test]
+    fn serializes_as_an_ordered_json_object() {
+        let lang_map = map(&[("b", "1"), ("a", "2")]);
+        let json = serde_json::to_string(&lang_map).unwrap();
+        assert_eq!(json, r#"{"b":"1","a":"2"}"#);
+        assert_eq!(serde_json::from_str::<LangMap>(&json).unwrap(), lang_map);
+    }
+}
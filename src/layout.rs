@@ -0,0 +1,162 @@
+//! カテゴリごとのディレクトリ構成(`out/en_us/item.json`、`out/en_us/block.json`)との相互変換(explode/implodeサブコマンド)
+//!
+//! カテゴリはキーの最初の区切り。ファイル名に使えないカテゴリ(空、パス区切りを含むなど)や区切りの無いキー、
+//! --miscと同じ名前のカテゴリのキーは、キーをそのまま--miscのファイルに入れるため、往復してもすべてのキーが元に戻る
+
+use std::fs;
+use std::path::Path;
+use clap::ValueEnum;
+use indexmap::IndexMap;
+use crate::cli::{ExplodeArgs, ImplodeArgs};
+use crate::convert::sort_paths;
+use crate::format::{load_by_extension, Format};
+use crate::i18n::tf;
+
+/// カテゴリごとのファイルでのキーの最初の区切りの扱い
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum CategoryPrefix {
+    /// 取り除く(`item.apple` は item.json の `apple`)。implodeではファイル名を接頭辞として戻す
+    Strip,
+    /// 残す(`item.apple` は item.json の `item.apple`)
+    Keep,
+}
+
+/// ファイル名に使えるカテゴリか
+fn is_file_name(category: &str) -> bool {
+    !category.is_empty() && category != "." && category != ".." && !category.contains(['/', '\\', ':', '*', '?', '"', '<', '>', '|'])
+}
+
+/// キーのカテゴリと、カテゴリのファイルに書くキー。カテゴリに振り分けないキーはNone
+fn categorize<'a>(key: &'a str, misc: &str, prefix: CategoryPrefix) -> Option<(&'a str, &'a str)> {
+    let (category, rest) = key.split_once('.')?;
+    if !is_file_name(category) || category == misc {
+        return None;
+    }
+    match prefix {
+        CategoryPrefix::Strip => Some((category, rest)),
+        CategoryPrefix::Keep => Some((category, key)),
+    }
+}
+
+/// explodeサブコマンドの実行
+pub fn run_explode(args: &ExplodeArgs) -> Result<bool, String> {
+    let input_format = Format::from_path(Path::new(&args.input)).ok_or_else(|| tf("io.unsupported_extension", &[&args.input]))?;
+    let output_format = args.format.unwrap_or(input_format);
+    let stem = Path::new(&args.input).file_stem().and_then(|stem| stem.to_str()).unwrap_or_default();
+    let dir = Path::new(&args.out_dir).join(stem);
+    let lang_map = input_format.load(&args.input)?;
+
+    let mut parts: IndexMap<String, IndexMap<String, String>> = IndexMap::new();
+    for (key, value) in lang_map {
+        let (category, key) = match categorize(&key, &args.misc, args.category_prefix) {
+            Some((category, part_key)) => (category.to_string(), part_key.to_string()),
+            None => (args.misc.clone(), key),
+        };
+        parts.entry(category).or_default().insert(key, value);
+    }
+
+    fs::create_dir_all(&dir).map_err(|_| tf("io.write_failed", &[&dir.display()]))?;
+    for (category, part) in &parts {
+        let output_path = dir.join(format!("{}.{}", category, output_format.extension())).to_string_lossy().to_string();
+        output_format.save(&output_path, part)?;
+        println!("{}", tf("layout.exploded", &[&args.input, &output_path, &part.len()]));
+    }
+    Ok(true)
+}
+
+/// implodeサブコマンドの実行
+pub fn run_implode(args: &ImplodeArgs) -> Result<bool, String> {
+    let dir = Path::new(args.input.trim_end_matches(['/', '\\']));
+    let entries = fs::read_dir(dir).map_err(|_| tf("io.read_failed", &[&args.input]))?;
+    let mut paths: Vec<_> = entries.flatten().map(|entry| entry.path()).filter(|path| path.is_file() && Format::from_path(path).is_some()).collect();
+    sort_paths(&mut paths);
+    if paths.is_empty() {
+        return Err(tf("layout.no_files", &[&args.input]));
+    }
+
+    let mut lang_map: IndexMap<String, String> = IndexMap::new();
+    let mut origins: IndexMap<String, String> = IndexMap::new();
+    for path in &paths {
+        let path_str = path.to_string_lossy().to_string();
+        let category = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default();
+        let is_misc = category == args.misc;
+        for (key, value) in load_by_extension(&path_str)? {
+            let key = match args.category_prefix {
+                CategoryPrefix::Strip if !is_misc => format!("{}.{}", category, key),
+                _ => key,
+            };
+            if let Some(first) = origins.get(&key) {
+                return Err(tf("layout.duplicate_key", &[&key, first, &path_str]));
+            }
+            origins.insert(key.clone(), path_str.clone());
+            lang_map.insert(key, value);
+        }
+    }
+
+    let output = match &args.output {
+        Some(output) => output.clone(),
+        None => format!("{}.{}", dir.to_string_lossy(), args.format.unwrap_or(Format::Json).extension()),
+    };
+    let format = args.format.or_else(|| Format::from_path(Path::new(&output))).ok_or_else(|| tf("io.unsupported_extension", &[&output]))?;
+    format.save(&output, &lang_map)?;
+    println!("{}", tf("layout.imploded", &[&args.input, &paths.len(), &output, &lang_map.len()]));
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn categorize_sends_unusable_categories_to_misc() {
+        assert_eq!(categorize("item.apple", "misc", CategoryPrefix::Strip), Some(("item", "apple")));
+        assert_eq!(categorize("item.apple", "misc", CategoryPrefix::Keep), Some(("item", "item.apple")));
+        assert_eq!(categorize("nodots", "misc", CategoryPrefix::Strip), None);
+        assert_eq!(categorize(".apple", "misc", CategoryPrefix::Strip), None);
+        assert_eq!(categorize("a:b.apple", "misc", CategoryPrefix::Strip), None);
+        assert_eq!(categorize("misc.apple", "misc", CategoryPrefix::Strip), None);
+    }
+
+    #[test]
+    fn explode_then_implode_restores_every_key() {
+        let dir = std::env::temp_dir().join(format!("json_lang_layout_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("en_us.json");
+        fs::write(&input, r#"{"item.apple":"Apple","block.stone":"Stone","gui":"GUI","misc.x":"X","item.bread":"Bread"}"#).unwrap();
+        let out_dir = dir.join("out");
+        run_explode(&ExplodeArgs {
+            input: input.to_str().unwrap().to_string(),
+            out_dir: out_dir.to_str().unwrap().to_string(),
+            category_prefix: CategoryPrefix::Strip,
+            misc: "misc".to_string(),
+            format: None,
+        })
+        .unwrap();
+        let exploded = out_dir.join("en_us");
+        assert_eq!(load_by_extension(exploded.join("item.json").to_str().unwrap()).unwrap().len(), 2);
+        assert_eq!(load_by_extension(exploded.join("misc.json").to_str().unwrap()).unwrap().keys().collect::<Vec<_>>(), ["gui", "misc.x"]);
+
+        let output = dir.join("joined.json");
+        let mut args = ImplodeArgs {
+            input: exploded.to_str().unwrap().to_string(),
+            output: Some(output.to_str().unwrap().to_string()),
+            category_prefix: CategoryPrefix::Strip,
+            misc: "misc".to_string(),
+            format: None,
+        };
+        run_implode(&args).unwrap();
+        let mut joined: Vec<String> = load_by_extension(output.to_str().unwrap()).unwrap().into_keys().collect();
+        joined.sort();
+        assert_eq!(joined, ["block.stone", "gui", "item.apple", "item.bread", "misc.x"]);
+
+        // 同じキーが別のファイルにもあれば結合しない
+        fs::write(exploded.join("gui.json"), r#"{"":"dup"}"#).unwrap();
+        fs::write(exploded.join("misc.json"), r#"{"gui.":"GUI"}"#).unwrap();
+        assert!(run_implode(&args).unwrap_err().contains("gui."));
+
+        args.input = dir.join("empty").to_str().unwrap().to_string();
+        fs::create_dir_all(&args.input).unwrap();
+        assert!(run_implode(&args).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
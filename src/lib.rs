@@ -0,0 +1,20 @@
+//! json_langの変換の中心部分。ファイルを扱わないため、ブラウザ(wasm32-unknown-unknown)でも使える
+//!
+//! `wasm` フィーチャーを有効にすると、wasm-bindgenで `lang_to_json` と `json_to_lang` をJavaScriptに公開する。
+//! `ffi` フィーチャーを有効にすると、同じ変換をC ABI(`include/json_lang.h`)で公開する
+
+pub mod codec;
+pub mod diagnostic;
+pub mod events;
+pub mod i18n;
+pub mod lang_map;
+pub mod roundtrip;
+
+pub use events::{Event, Observer};
+pub use lang_map::LangMap;
+pub use roundtrip::{verify_roundtrip, RoundtripLoss};
+
+#[cfg(feature = "wasm")]
+mod wasm;
+#[cfg(feature = "ffi")]
+mod ffi;
@@ -1,191 +1,234 @@
-use std::collections::VecDeque;
-use std::fs::{self, create_dir_all, File};
-use std::io::{self, Write};
-use std::path::Path;
-use indexmap::IndexMap;
-use serde_json::Value;
-use serde_json::to_writer_pretty;
+mod badges;
+mod cache;
+mod cancel;
+mod check;
+mod cleanup;
+mod cli;
+mod comments;
+mod concat;
+mod config;
+mod console;
+mod convert;
+mod drop_paths;
+mod existing;
+mod export;
+mod ext_map;
+mod fallback;
+mod filter;
+mod format;
+mod glossary;
+mod layout;
+mod manifest;
+mod menu;
+mod merge;
+mod namespaces;
+mod new_locale;
+mod output_name;
+mod placeholders;
+mod preview;
+mod project;
+mod remote;
+mod repl;
+mod report;
+mod route;
+mod run_log;
+mod schema;
+mod serve;
+mod settings;
+mod size_guard;
+mod sniff;
+mod split;
+mod staging;
+mod timestamp;
+mod timing;
+mod trace;
+mod transform;
+mod verify;
 
-/// 起動時に必要なディレクトリが存在するか確認し、なければ作成する
-fn ensure_directories() {
-    let input_dir = "./input";
-    let output_dir = "./output";
+use std::ffi::OsString;
+use std::io;
+use clap::{ArgMatches, CommandFactory, FromArgMatches};
+use cli::{Cli, Command, ConvertArgs, OptionSource, CORE_OPTIONS, ENV_NO_COLOR};
+use config::Config;
+use settings::Settings;
+use convert::{process_files, ConvertOptions};
+use json_lang::i18n::{self, t, tf, Language};
 
-    if !Path::new(input_dir).exists() {
-        create_dir_all(input_dir).expect("inputディレクトリの作成に失敗しました。");
-        println!("inputディレクトリを作成しました。");
-    }
-    if !Path::new(output_dir).exists() {
-        create_dir_all(output_dir).expect("outputディレクトリの作成に失敗しました。");
-        println!("outputディレクトリを作成しました。");
-    }
+/// checkと同じ引数で出力を再生成するconvertコマンド
+fn regenerate_command() -> String {
+    let args: Vec<String> = std::env::args()
+        .skip(1)
+        .map(|arg| if arg == "check" { "convert".to_string() } else { arg })
+        .collect();
+    format!("json_lang {}", args.join(" "))
 }
 
-/// .langファイルを読み込んで順序を保持するIndexMapに格納する関数
-fn load_lang_file(file_path: &str) -> Result<IndexMap<String, String>, String> {
-    let contents = fs::read_to_string(file_path).map_err(|_| format!("{} の読み込みに失敗しました。", file_path))?;
-    let mut lang_map = IndexMap::new();
-    for line in contents.lines() {
-        if line.trim().is_empty() || line.starts_with('#') {
-            continue;
-        }
-        if let Some((key, value)) = line.split_once('=') {
-            lang_map.insert(key.trim().to_string(), value.trim().to_string());
+/// サブコマンドの結果を終了コードに変換する
+fn exit_with(result: Result<bool, String>) -> ! {
+    match result {
+        Ok(true) => std::process::exit(0),
+        Ok(false) => std::process::exit(1),
+        Err(e) => {
+            eprintln!("{}", tf("error.prefix", &[&e]));
+            std::process::exit(1);
         }
     }
-    Ok(lang_map)
 }
 
-/// JSONファイルを読み込んでIndexMapに変換する関数
-fn load_json_file(file_path: &str) -> Result<IndexMap<String, String>, String> {
-    let file_content = fs::read_to_string(file_path).map_err(|_| format!("{} の読み込みに失敗しました。", file_path))?;
-    let json_value: Value = serde_json::from_str(&file_content).map_err(|_| format!("{} のJSON解析に失敗しました。", file_path))?;
-    let mut lang_map = IndexMap::new();
-    if let Value::Object(map) = json_value {
-        for (key, value) in map {
-            if let Value::String(val) = value {
-                lang_map.insert(key, val);
-            }
+/// convert/checkの引数に設定ファイル([convert]と--profile)を重ね、--verboseなら各値の出どころを表示する
+fn resolve_convert_args(args: ConvertArgs, cli_matches: &ArgMatches, config: &Config, verbose: bool) -> Result<ConvertArgs, String> {
+    let (name, matches) = cli_matches.subcommand().unwrap();
+    let table = config.convert_table(args.profile.as_deref())?;
+    let command = Cli::command();
+    let injected = cli::config_args(&table, matches, command.find_subcommand(name).unwrap())?;
+    // 設定ファイルを置くだけで任意のコマンドが実行されないよう、外部コマンドはコマンドラインでのみ受け付ける
+    if injected.iter().any(|(id, _)| id == "transform_cmd") {
+        return Err(t("config.transform_cmd_forbidden"));
+    }
+    let reparsed;
+    let (args, matches) = if injected.is_empty() {
+        (args, matches)
+    } else {
+        let extra = injected.iter().flat_map(|(_, args)| args.iter().map(OsString::from));
+        reparsed = Cli::command().try_get_matches_from(std::env::args_os().chain(extra)).unwrap_or_else(|e| e.exit());
+        let matches = reparsed.subcommand().unwrap().1;
+        (ConvertArgs::from_arg_matches(matches).unwrap_or_else(|e| e.exit()), matches)
+    };
+    if verbose {
+        let mut ids: Vec<(&str, &str)> = CORE_OPTIONS.to_vec();
+        ids.extend(injected.iter().map(|(id, _)| (id.as_str(), "")).filter(|(id, _)| !CORE_OPTIONS.iter().any(|(core, _)| core == id)));
+        for (id, env) in ids {
+            let source = if injected.iter().any(|(injected_id, _)| injected_id == id) { OptionSource::Config } else { OptionSource::of(matches, id, env) };
+            let value = matches
+                .get_raw(id)
+                .map(|values| values.map(|v| v.to_string_lossy()).collect::<Vec<_>>().join(", "))
+                .unwrap_or_default();
+            eprintln!("{}", tf("source.line", &[&id, &value, &source.label()]));
         }
     }
-    Ok(lang_map)
+    Ok(args)
 }
 
-/// JSONファイルに整形して出力する関数
-fn save_as_pretty_json(output_path: &str, lang_map: &IndexMap<String, String>) -> Result<(), String> {
-    if let Some(parent_dir) = Path::new(output_path).parent() {
-        create_dir_all(parent_dir).map_err(|_| format!("出力先ディレクトリの作成に失敗しました: {}", output_path))?;
+/// --transform-cmdで外部コマンドを実行することを、変換を始める前に知らせる
+fn announce_transform_cmd(options: &ConvertOptions) {
+    if let Some(command) = &options.transform_cmd {
+        eprintln!("{}", tf("transform_cmd.notice", &[command]));
     }
-    let file = File::create(output_path).map_err(|_| format!("{} のJSONファイル作成に失敗しました。", output_path))?;
-    to_writer_pretty(file, lang_map).map_err(|_| format!("{} へのJSONデータ書き込みに失敗しました。", output_path))?;
-    Ok(())
 }
 
-/// .langファイルとして保存する関数
-fn save_as_lang(output_path: &str, lang_map: &IndexMap<String, String>) -> Result<(), String> {
-    if let Some(parent_dir) = Path::new(output_path).parent() {
-        create_dir_all(parent_dir).map_err(|_| format!("出力先ディレクトリの作成に失敗しました: {}", output_path))?;
-    }
-    let mut file = File::create(output_path).map_err(|_| format!("{} の.langファイル作成に失敗しました。", output_path))?;
-    for (key, value) in lang_map {
-        writeln!(file, "{}={}", key, value).map_err(|_| format!("{} へのデータ書き込みに失敗しました。", output_path))?;
-    }
-    Ok(())
+/// 引数の解析より前に決める言語(--langと環境変数LANG)。value_parserのエラーもこの言語で表示する
+fn early_language() -> Option<Language> {
+    let args: Vec<String> = std::env::args().collect();
+    let value = args.iter().enumerate().find_map(|(index, arg)| match arg.strip_prefix("--lang") {
+        Some("") => args.get(index + 1).map(String::as_str),
+        Some(rest) => rest.strip_prefix('='),
+        None => None,
+    });
+    value.and_then(|value| <Language as clap::ValueEnum>::from_str(value, true).ok()).or_else(Language::from_env)
 }
 
-/// 特定の変換を実行する関数
-fn process_files(mode: u8) {
-    let input_dir = "./input";
-    let output_dir = "./output";
-
-    let mut failed_reads = VecDeque::new();    // 読み込み失敗の記録
-    let mut failed_writes = VecDeque::new();   // 書き込み失敗の記録
-
-    for entry in fs::read_dir(input_dir).expect("inputディレクトリが存在しません。") {
-        if let Ok(entry) = entry {
-            let path = entry.path();
-            let file_name = path.file_stem().unwrap().to_str().unwrap().to_string();
-
-            if mode == 1 && path.extension().map_or(false, |e| e == "lang") {
-                // .lang => JSON
-                let input_path = path.to_str().unwrap();
-                let output_path = format!("{}/{}.json", output_dir, file_name);
-                if let Ok(lang_map) = load_lang_file(input_path) {
-                    println!("{} => {}", input_path, output_path);
-                    if let Err(e) = save_as_pretty_json(&output_path, &lang_map) {
-                        failed_writes.push_back(format!("{}: {}", file_name, e));
-                    }
-                } else if let Err(e) = load_lang_file(input_path) {
-                    failed_reads.push_back(format!("{}: {}", file_name, e))
-                }
-            } else if mode == 2 && path.extension().map_or(false, |e| e == "json") {
-                // JSON => .lang
-                let input_path = path.to_str().unwrap();
-                let output_path = format!("{}/{}.lang", output_dir, file_name);
-                if let Ok(lang_map) = load_json_file(input_path) {
-                    println!("{} => {}", input_path, output_path);
-                    if let Err(e) = save_as_lang(&output_path, &lang_map) {
-                        failed_writes.push_back(format!("{}: {}", file_name, e));
-                    }
-                } else if let Err(e) = load_json_file(input_path) {
-                    failed_reads.push_back(format!("{}: {}", file_name, e))
-                }
-            } else if mode == 3 {
-                // 両方の変換
-                if path.extension().map_or(false, |e| e == "lang") {
-                    let input_path = path.to_str().unwrap();
-                    let output_path = format!("{}/{}.json", output_dir, file_name);
-                    match load_lang_file(input_path) {
-                        Ok(lang_map) => {
-                            println!("{} => {}", input_path, output_path);
-                            if let Err(e) = save_as_pretty_json(&output_path, &lang_map) {
-                                failed_writes.push_back(format!("{}: {}", file_name, e));
-                            }
-                        }
-                        Err(e) => failed_reads.push_back(format!("{}: {}", file_name, e)),
-                    }
-                } else if path.extension().map_or(false, |e| e == "json") {
-                    let input_path = path.to_str().unwrap();
-                    let output_path = format!("{}/{}.lang", output_dir, file_name);
-                    match load_json_file(input_path) {
-                        Ok(lang_map) => {
-                            println!("{} => {}", input_path, output_path);
-                            if let Err(e) = save_as_lang(&output_path, &lang_map) {
-                                failed_writes.push_back(format!("{}: {}", file_name, e));
-                            }
-                        }
-                        Err(e) => failed_reads.push_back(format!("{}: {}", file_name, e)),
-                    }
+fn main() {
+    i18n::set_language(early_language().unwrap_or(Language::Ja));
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    if cli.no_color {
+        anstream::ColorChoice::Never.write_global();
+    }
+    if let Some(format) = cli.trace_format {
+        trace::init(format, cli.no_color);
+    }
+    remote::set_offline(cli.offline);
+    // 優先順位は --lang、設定ファイル、環境変数LANGの順。設定ファイルのエラーはそれ以外で決めた言語で表示する
+    i18n::set_language(cli.lang.or_else(Language::from_env).unwrap_or(Language::Ja));
+    let config = match config::load_config(cli.config.as_deref()) {
+        Ok(config) => config,
+        Err(e) => exit_with(Err(e)),
+    };
+    if let (None, Some(language)) = (cli.lang, config.lang) {
+        i18n::set_language(language);
+    }
+    if cli.verbose {
+        let source = OptionSource::of(&matches, "no_color", ENV_NO_COLOR);
+        eprintln!("{}", tf("source.line", &[&"no-color", &cli.no_color, &source.label()]));
+    }
+    match cli.command {
+        Some(Command::Convert(args)) => {
+            let mut options = match resolve_convert_args(args, &matches, &config, cli.verbose) {
+                Ok(args) => args.into_options(),
+                Err(e) => exit_with(Err(e)),
+            };
+            if cli.verbose {
+                options.timings = options.timings.or(Some(timing::DEFAULT_SLOWEST));
+            }
+            if let Some(path) = &options.verify_manifest {
+                exit_with(manifest::verify_manifest(path, options.output_root()));
+            }
+            // --projectではlangディレクトリごとに入出力先を決めてから確かめる
+            if options.project.is_none() {
+                if let Err(e) = options.validate() {
+                    exit_with(Err(e));
                 }
             }
-        }
-    }
-
-    // 結果表示
-    println!("\n処理完了:");
-    if failed_reads.is_empty() && failed_writes.is_empty() {
-        println!("すべてのファイルが正常に処理されました。");
-    } else {
-        if !failed_reads.is_empty() {
-            println!("\n読み込みに失敗したファイル:");
-            for error in &failed_reads {
-                println!("- {}", error);
+            announce_transform_cmd(&options);
+            cancel::install_handler();
+            let summaries = match options.project.clone() {
+                Some(root) => match project::run_project(&mut options, &root) {
+                    Ok(summaries) => summaries,
+                    Err(e) => exit_with(Err(e)),
+                },
+                None => vec![process_files(&options)],
+            };
+            if summaries.iter().any(|summary| summary.interrupted) {
+                std::process::exit(cancel::EXIT_INTERRUPTED);
+            }
+            if summaries.iter().any(|summary| summary.aborted.is_some()) {
+                std::process::exit(convert::EXIT_ABORTED);
             }
+            exit_with(Ok(!summaries.iter().any(|summary| summary.has_failures())));
         }
-        if !failed_writes.is_empty() {
-            println!("\n出力に失敗したファイル:");
-            for error in &failed_writes {
-                println!("- {}", error);
+        Some(Command::Check(args)) => {
+            let options = match resolve_convert_args(args, &matches, &config, cli.verbose) {
+                Ok(args) => args.into_options(),
+                Err(e) => exit_with(Err(e)),
+            };
+            // convertが受け付けない組み合わせはcheckでも同じように拒む
+            if options.project.is_none() {
+                if let Err(e) = options.validate() {
+                    exit_with(Err(e));
+                }
             }
+            announce_transform_cmd(&options);
+            exit_with(Ok(convert::check_outputs(&options, &regenerate_command())));
         }
-    }
-}
-
-/// メニュー表示と選択を繰り返す関数
-fn prompt_for_mode() -> u8 {
-    loop {
-        println!("\n1: lang=>json\n2: json=>lang\n3: すべて変換\n0: アプリを終了");
-        print!("選択してください: ");
-        io::stdout().flush().unwrap();
-
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).expect("入力の読み取りに失敗しました。");
-
-        match input.trim().parse::<u8>() {
-            Ok(0) => {
-                println!("アプリを終了します。");
-                std::process::exit(0);
+        Some(Command::Merge3(args)) => exit_with(merge::run_merge3(&args)),
+        Some(Command::NewLocale(args)) => exit_with(new_locale::run_new_locale(&args)),
+        Some(Command::ValidateKeys(args)) => exit_with(check::reference::run_validate_keys(&args)),
+        Some(Command::Concat(args)) => exit_with(concat::run_concat(&args)),
+        Some(Command::Split(args)) => exit_with(split::run_split(&args)),
+        Some(Command::Explode(args)) => exit_with(layout::run_explode(&args)),
+        Some(Command::Implode(args)) => exit_with(layout::run_implode(&args)),
+        Some(Command::Lint(args)) => exit_with(check::lint::run_lint(&args, &config)),
+        Some(Command::ValueLength(args)) => exit_with(check::lint::run_value_length(&args, &config)),
+        Some(Command::LengthRatio(args)) => exit_with(check::length_ratio::run_length_ratio(&args)),
+        Some(Command::Verify(args)) => exit_with(verify::run_verify(&args)),
+        Some(Command::Placeholders(args)) => exit_with(placeholders::run_placeholders(&args)),
+        Some(Command::Glossary(args)) => exit_with(glossary::run_glossary(&args)),
+        Some(Command::ValidateMc(args)) => exit_with(check::minecraft::run_validate_mc(&args)),
+        Some(Command::Badges(args)) => exit_with(badges::run_badges(&args)),
+        Some(Command::Namespaces(args)) => exit_with(namespaces::run_namespaces(&args)),
+        Some(Command::Serve(args)) => exit_with(serve::run_serve(&args)),
+        Some(Command::Repl) => repl::run(&Settings::load().to_options(convert::Mode::All).write),
+        Some(Command::Completions(args)) => {
+            clap_complete::generate(args.shell, &mut Cli::command(), "json_lang", &mut io::stdout());
+        }
+        None if !cli.paths.is_empty() => drop_paths::run(&cli.paths),
+        None => {
+            let settings = Settings::load();
+            if let Err(e) = menu::ensure_directories(&settings) {
+                eprintln!("{}", tf("error.prefix", &[&e]));
+                std::process::exit(menu::EXIT_SETUP);
             }
-            Ok(1) | Ok(2) | Ok(3) => return input.trim().parse().unwrap(),
-            _ => println!("無効な選択です。0(終了)、1(変換:lang=>json)、2(変換:json=>lang)、3(全て変換)を選択してください。\n"),
+            menu::run(settings)
         }
     }
 }
-
-fn main() {
-    ensure_directories();
-    loop {
-        let mode = prompt_for_mode();
-        process_files(mode);
-    }
-}
@@ -0,0 +1,100 @@
+//! 出力ファイルのSHA-256マニフェストの作成と照合
+
+use std::fs;
+use std::path::Path;
+use clap::ValueEnum;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use crate::i18n::{t, tf};
+
+/// マニフェストの書式
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ManifestFormat {
+    /// `{"相対パス": {"sha256": ..., "size": ...}}`
+    Json,
+    /// `sha256sum` と同じ `<ハッシュ>  <相対パス>` の行
+    Sha256sum,
+}
+
+/// マニフェストの1ファイル分
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub sha256: String,
+    /// sha256sum形式では記録しない
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+}
+
+/// ファイルの内容のハッシュとサイズを求める
+fn entry_for(path: &Path) -> Result<ManifestEntry, String> {
+    let bytes = fs::read(path).map_err(|_| tf("io.read_failed", &[&path.display()]))?;
+    let sha256 = Sha256::digest(&bytes).iter().map(|b| format!("{:02x}", b)).collect();
+    Ok(ManifestEntry { sha256, size: Some(bytes.len() as u64) })
+}
+
+/// 出力ルートからの相対パス(区切りは常に `/`)
+fn relative_path(path: &str, root: &str) -> String {
+    let relative = Path::new(path).strip_prefix(root).unwrap_or(Path::new(path));
+    relative.to_string_lossy().replace('\\', "/")
+}
+
+/// 書き込み済みの出力ファイルを読み直してマニフェストを書き出す
+pub fn write_manifest(path: &str, format: ManifestFormat, outputs: &[String], root: &str) -> Result<usize, String> {
+    let mut entries = IndexMap::new();
+    for output in outputs {
+        entries.insert(relative_path(output, root), entry_for(Path::new(output))?);
+    }
+    entries.sort_keys();
+    let contents = match format {
+        ManifestFormat::Json => serde_json::to_string_pretty(&entries).map_err(|_| tf("io.write_failed", &[&path]))? + "\n",
+        ManifestFormat::Sha256sum => entries.iter().map(|(file, entry)| format!("{}  {}\n", entry.sha256, file)).collect(),
+    };
+    fs::write(path, contents).map_err(|_| tf("io.write_failed", &[&path]))?;
+    Ok(entries.len())
+}
+
+/// マニフェストを読み込む。`{` で始まればJSON、それ以外はsha256sum形式として扱う
+fn load_manifest(path: &str) -> Result<IndexMap<String, ManifestEntry>, String> {
+    let contents = fs::read_to_string(path).map_err(|_| tf("io.read_failed", &[&path]))?;
+    if contents.trim_start().starts_with('{') {
+        return serde_json::from_str(&contents).map_err(|e| tf("manifest.parse_failed", &[&path, &e]));
+    }
+    let mut entries = IndexMap::new();
+    for (index, line) in contents.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+        let Some((hash, file)) = line.split_once("  ") else {
+            return Err(tf("manifest.parse_failed", &[&path, &tf("manifest.bad_line", &[&(index + 1)])]));
+        };
+        entries.insert(file.to_string(), ManifestEntry { sha256: hash.to_string(), size: None });
+    }
+    Ok(entries)
+}
+
+/// マニフェストの各ファイルを出力ルートで照合し、不一致を表示する。すべて一致すればtrue
+pub fn verify_manifest(path: &str, root: &str) -> Result<bool, String> {
+    let entries = load_manifest(path)?;
+    let mut mismatches = 0;
+    for (file, expected) in &entries {
+        let actual_path = Path::new(root).join(file);
+        let problem = if !actual_path.is_file() {
+            Some(t("manifest.missing"))
+        } else {
+            let actual = entry_for(&actual_path)?;
+            match expected.size {
+                Some(size) if Some(size) != actual.size => Some(tf("manifest.size_mismatch", &[&size, &actual.size.unwrap_or(0)])),
+                _ if actual.sha256 != expected.sha256 => Some(t("manifest.hash_mismatch")),
+                _ => None,
+            }
+        };
+        if let Some(problem) = problem {
+            println!("{}: {}", file, problem);
+            mismatches += 1;
+        }
+    }
+    if mismatches == 0 {
+        println!("{}", tf("manifest.verified", &[&entries.len()]));
+    } else {
+        println!("{}", tf("manifest.mismatches", &[&mismatches, &entries.len()]));
+    }
+    Ok(mismatches == 0)
+}
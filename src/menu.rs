@@ -0,0 +1,303 @@
+//! 引数なしで起動したときの対話メニュー
+
+use std::fs::create_dir_all;
+use std::io::{self, Write};
+use std::path::Path;
+use clap::ValueEnum;
+use crate::convert::{plan_jobs_with_mode, process_files, ConvertOptions, Mode, Summary};
+use crate::format::Format;
+use crate::i18n::{t, tf};
+use crate::preview;
+use crate::repl;
+use crate::settings::{settings_path, Settings};
+use crate::transform::sort::SortOrder;
+
+/// メニューで選ばれた操作
+enum Action {
+    Convert(Mode),
+    Settings,
+    Repl,
+    Preview,
+}
+
+/// メニューの上に表示する前回の変換結果
+struct LastRun {
+    mode: Mode,
+    converted: usize,
+    skipped: usize,
+    failed: usize,
+}
+
+impl LastRun {
+    fn new(mode: Mode, summary: &Summary) -> LastRun {
+        LastRun { mode, converted: summary.converted, skipped: summary.skipped, failed: summary.failed_count() }
+    }
+}
+
+/// メニューでの変換方向の表示名
+pub fn mode_label(mode: Mode) -> String {
+    match mode {
+        Mode::Lang2json => t("menu.lang2json"),
+        Mode::Json2lang => t("menu.json2lang"),
+        Mode::All => t("menu.all"),
+    }
+}
+
+/// 起動時に入力/出力ディレクトリを用意できなかったときの終了コード
+pub const EXIT_SETUP: i32 = 4;
+
+/// ディレクトリを作成できなかった理由を説明するメッセージ
+fn create_error(dir: &str, error: &io::Error) -> String {
+    let path = Path::new(dir);
+    let parent_is_file = path.ancestors().skip(1).find(|ancestor| ancestor.exists()).is_some_and(|ancestor| !ancestor.is_dir());
+    let reason = if error.kind() == io::ErrorKind::PermissionDenied {
+        t("dir.reason.permission")
+    } else if parent_is_file {
+        t("dir.reason.parent_not_dir")
+    } else {
+        error.to_string()
+    };
+    tf("dir.create_failed", &[&dir, &reason])
+}
+
+/// 入力ディレクトリと出力ディレクトリが存在するか確認し、なければ作成する。
+/// 入力ディレクトリを新しく作った場合は、変換するファイルがまだ無いことを案内する
+pub fn ensure_directories(settings: &Settings) -> Result<(), String> {
+    for dir in [&settings.input_dir, &settings.output_dir] {
+        let path = Path::new(dir);
+        if path.is_dir() {
+            continue;
+        }
+        if path.exists() {
+            return Err(tf("dir.create_failed", &[dir, &t("dir.reason.not_dir")]));
+        }
+        create_dir_all(dir).map_err(|e| create_error(dir, &e))?;
+        println!("{}", tf("dir.created", &[dir]));
+        if dir == &settings.input_dir {
+            println!("{}", tf("dir.input_empty", &[dir]));
+        }
+    }
+    Ok(())
+}
+
+/// アプリを終了する
+fn quit() -> ! {
+    println!("{}", t("menu.bye"));
+    std::process::exit(0);
+}
+
+/// 案内を表示して1行読み込む。入力が終わっている(Ctrl+Dやパイプの終端)場合は終了する
+fn read_line(prompt: &str) -> String {
+    print!("{} ", prompt);
+    io::stdout().flush().unwrap();
+    let mut input = String::new();
+    let read = io::stdin().read_line(&mut input).unwrap_or_else(|_| panic!("{}", t("menu.read_failed")));
+    if read == 0 {
+        println!();
+        quit();
+    }
+    input.trim().to_string()
+}
+
+/// 各項目の説明と使うディレクトリを表示する
+fn print_help(settings: &Settings) {
+    println!("\n{}", t("menu.help.title"));
+    for key in ["menu.help.convert", "menu.help.all", "menu.help.settings", "menu.help.repl", "menu.help.preview", "menu.help.quit"] {
+        println!("  {}", t(key));
+    }
+    println!("{}", tf("menu.help.dirs", &[&settings.input_dir, &settings.output_dir]));
+    println!("{}", t("menu.help.incremental"));
+}
+
+/// 前回の変換(設定に保存した番号)。番号が変換でなければNone
+fn last_mode(settings: &Settings) -> Option<Mode> {
+    settings.last_mode.and_then(Mode::from_number)
+}
+
+/// 入力ディレクトリにある形式とファイルの件数(変換と同じ計画から数える)
+fn detected_formats(settings: &Settings) -> Vec<(Format, usize)> {
+    let (jobs, _) = plan_jobs_with_mode(&settings.to_options(Mode::All), Mode::All).unwrap_or_default();
+    Format::ALL
+        .into_iter()
+        .map(|format| (format, jobs.iter().filter(|job| job.format == format).count()))
+        .filter(|(_, count)| *count > 0)
+        .collect()
+}
+
+/// 一覧から形式を1つ選ぶ。0(戻る)ならNone
+fn choose_format(title: &str, formats: &[(Format, String)]) -> Option<Format> {
+    loop {
+        println!("\n{}", title);
+        for (number, (_, label)) in formats.iter().enumerate() {
+            println!("{}: {}", number + 1, label);
+        }
+        println!("0: {}", t("menu.back"));
+        let choice = read_line(&t("menu.prompt"));
+        if choice == "0" {
+            return None;
+        }
+        match choice.parse::<usize>() {
+            Ok(number) if (1..=formats.len()).contains(&number) => return Some(formats[number - 1].0),
+            _ => println!("{}", t("menu.invalid_format")),
+        }
+    }
+}
+
+/// 変換元、変換先の順に形式を選ぶ。0で1つ前の段階に戻り、変換元の選択で戻ればNone
+fn prompt_for_formats(settings: &Settings) -> Option<Mode> {
+    let detected = detected_formats(settings);
+    if detected.is_empty() {
+        println!("{}", tf("menu.no_inputs", &[&settings.input_dir]));
+        return None;
+    }
+    let sources: Vec<(Format, String)> = detected.iter().map(|(format, count)| (*format, tf("menu.format_count", &[&format.extension(), count]))).collect();
+    loop {
+        let source = choose_format(&t("menu.source"), &sources)?;
+        let targets: Vec<(Format, String)> = Format::ALL
+            .into_iter()
+            .filter(|target| Mode::from_formats(source, *target).is_some())
+            .map(|target| (target, target.extension().to_string()))
+            .collect();
+        if let Some(target) = choose_format(&tf("menu.target", &[&source.extension()]), &targets) {
+            return Mode::from_formats(source, target);
+        }
+    }
+}
+
+/// メニュー表示と選択を繰り返す関数
+fn prompt_for_action(settings: &Settings, last_run: Option<&LastRun>) -> Action {
+    loop {
+        println!("\n{}", tf("menu.banner", &[&settings.input_dir, &settings.output_dir]));
+        if let Some(last) = last_run {
+            println!("{}", tf("menu.last_run", &[&mode_label(last.mode), &last.converted, &last.skipped, &last.failed]));
+        }
+        println!(
+            "1: {}\n2: {}\n3: {}\n4: {}\n5: {}\nh: {}\n0: {}",
+            t("menu.convert"),
+            t("menu.all"),
+            t("menu.settings"),
+            t("menu.repl"),
+            t("menu.preview"),
+            t("menu.help"),
+            t("menu.quit")
+        );
+        let default = last_mode(settings);
+        let prompt = match default {
+            Some(mode) => tf("menu.prompt_default", &[&mode_label(mode)]),
+            None => t("menu.prompt"),
+        };
+        let choice = read_line(&prompt);
+        if choice == "h" || choice == "?" {
+            print_help(settings);
+            continue;
+        }
+        // 空の入力は前回の変換を選んだものとみなす
+        let action = match choice.as_str() {
+            "" => default.map(Action::Convert),
+            "0" => quit(),
+            "1" => match prompt_for_formats(settings) {
+                Some(mode) => Some(Action::Convert(mode)),
+                None => continue,
+            },
+            "2" => Some(Action::Convert(Mode::All)),
+            "3" => Some(Action::Settings),
+            "4" => Some(Action::Repl),
+            "5" => Some(Action::Preview),
+            _ => None,
+        };
+        if let Some(action) = action {
+            return action;
+        }
+        println!("{}\n", t("menu.invalid"));
+    }
+}
+
+/// 設定の表示と変更。変更するたびに保存する
+fn edit_settings(settings: &mut Settings) {
+    loop {
+        println!("\n{}", tf("settings.title", &[&settings_path().display()]));
+        println!("1: {}: {}", t("settings.input_dir"), settings.input_dir);
+        println!("2: {}: {}", t("settings.output_dir"), settings.output_dir);
+        println!("3: {}: {}", t("settings.sort"), settings.sort.to_possible_value().unwrap().get_name());
+        println!("4: {}: {}", t("settings.indent"), settings.indent);
+        println!("0: {}", t("settings.back"));
+        let choice = read_line(&t("menu.prompt"));
+        if choice == "0" {
+            return;
+        }
+        if !["1", "2", "3", "4"].contains(&choice.as_str()) {
+            println!("{}", t("settings.invalid_choice"));
+            continue;
+        }
+        let value = read_line(&t("settings.new_value"));
+        if value.is_empty() {
+            continue;
+        }
+        match choice.as_str() {
+            "1" => settings.input_dir = value,
+            "2" => settings.output_dir = value,
+            "3" => match SortOrder::from_str(&value, true) {
+                Ok(order) => settings.sort = order,
+                Err(_) => {
+                    println!("{}", t("settings.invalid_sort"));
+                    continue;
+                }
+            },
+            _ => match value.parse() {
+                Ok(indent) => settings.indent = indent,
+                Err(_) => {
+                    println!("{}", t("settings.invalid_indent"));
+                    continue;
+                }
+            },
+        }
+        match settings.save() {
+            Ok(path) => println!("{}", tf("settings.saved", &[&path.display()])),
+            Err(e) => println!("{}", e),
+        }
+        if let Err(e) = ensure_directories(settings) {
+            println!("{}", e);
+        }
+    }
+}
+
+/// 対話メニューを終了が選ばれるまで繰り返す。
+/// 続けて同じ変換を選んでも変更の無いファイルは変換しないよう、キャッシュを使う
+pub fn run(mut settings: Settings) -> ! {
+    let mut last_run = None;
+    loop {
+        match prompt_for_action(&settings, last_run.as_ref()) {
+            Action::Convert(mode) => {
+                let options = ConvertOptions { incremental: true, ..settings.to_options(mode) };
+                let summary = process_files(&options);
+                last_run = Some(LastRun::new(mode, &summary));
+                // 次回の起動後もEnterだけで同じ変換を選べるよう保存する
+                if settings.last_mode != Some(mode.number()) {
+                    settings.last_mode = Some(mode.number());
+                    if let Err(e) = settings.save() {
+                        println!("{}", e);
+                    }
+                }
+            }
+            Action::Settings => edit_settings(&mut settings),
+            Action::Repl => repl::run(&settings.to_options(Mode::All).write),
+            Action::Preview => preview::print_preview(&settings),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enter_defaults_only_to_a_saved_conversion() {
+        let with = |last_mode| Settings { last_mode, ..Settings::default() };
+        assert_eq!(last_mode(&with(None)), None);
+        assert_eq!(last_mode(&with(Some(1))), Some(Mode::Lang2json));
+        assert_eq!(last_mode(&with(Some(3))), Some(Mode::All));
+        // 設定ファイルを手で書き換えた場合など、変換でない番号は既定にしない
+        assert_eq!(last_mode(&with(Some(0))), None);
+        assert_eq!(last_mode(&with(Some(9))), None);
+    }
+}
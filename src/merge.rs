@@ -0,0 +1,198 @@
+use std::fs::{self, create_dir_all};
+use std::path::Path;
+use crate::cli::{ConflictStyle, Merge3Args};
+use crate::format::{load_by_extension, save_by_extension};
+use crate::i18n::{t, tf};
+use crate::remote;
+use json_lang::LangMap;
+
+/// 3方向マージで衝突したキー
+pub struct Conflict {
+    pub key: String,
+    pub base: Option<String>,
+    pub ours: Option<String>,
+    pub theirs: Option<String>,
+}
+
+/// マージ後の1エントリ
+pub enum Merged {
+    Value(String),
+    Conflict(Conflict),
+}
+
+/// base/ours/theirsの3つのマップをキー単位でマージする
+/// 順序はbaseの順、追加キーはours→theirsの順で末尾に並ぶ
+/// 両側で削除されたキー、片側で削除され他方が未変更のキーは結果に含まれない
+pub fn merge3(base: &LangMap, ours: &LangMap, theirs: &LangMap) -> Vec<(String, Merged)> {
+    let mut keys: Vec<&String> = base.keys().collect();
+    keys.extend(ours.keys().filter(|k| !base.contains_key(*k)));
+    keys.extend(theirs.keys().filter(|k| !base.contains_key(*k) && !ours.contains_key(*k)));
+
+    let mut merged = Vec::new();
+    for key in keys {
+        let b = base.get(key);
+        let o = ours.get(key);
+        let t = theirs.get(key);
+        let resolved = if o == t {
+            o
+        } else if o == b {
+            t
+        } else if t == b {
+            o
+        } else {
+            merged.push((
+                key.clone(),
+                Merged::Conflict(Conflict {
+                    key: key.clone(),
+                    base: b.cloned(),
+                    ours: o.cloned(),
+                    theirs: t.cloned(),
+                }),
+            ));
+            continue;
+        };
+        if let Some(value) = resolved {
+            merged.push((key.clone(), Merged::Value(value.clone())));
+        }
+    }
+    merged
+}
+
+/// 衝突箇所をgit形式のマーカーで囲んだ.langテキストを生成する
+fn render_with_markers(merged: &[(String, Merged)], ours_label: &str, theirs_label: &str) -> String {
+    let mut out = String::new();
+    for (key, entry) in merged {
+        match entry {
+            Merged::Value(value) => out.push_str(&format!("{}={}\n", key, value)),
+            Merged::Conflict(c) => {
+                out.push_str(&format!("<<<<<<< {}\n", ours_label));
+                if let Some(value) = &c.ours {
+                    out.push_str(&format!("{}={}\n", key, value));
+                }
+                out.push_str("=======\n");
+                if let Some(value) = &c.theirs {
+                    out.push_str(&format!("{}={}\n", key, value));
+                }
+                out.push_str(&format!(">>>>>>> {}\n", theirs_label));
+            }
+        }
+    }
+    out
+}
+
+/// 衝突をbaseの値に戻したマップを作る(baseに無いキーは出力しない)
+fn resolve_to_base(merged: &[(String, Merged)]) -> LangMap {
+    let mut lang_map = LangMap::default();
+    for (key, entry) in merged {
+        match entry {
+            Merged::Value(value) => {
+                lang_map.insert(key.clone(), value.clone());
+            }
+            Merged::Conflict(c) => {
+                if let Some(value) = &c.base {
+                    lang_map.insert(key.clone(), value.clone());
+                }
+            }
+        }
+    }
+    lang_map
+}
+
+fn describe(value: &Option<String>) -> String {
+    match value {
+        Some(v) => format!("\"{}\"", v),
+        None => t("merge.none"),
+    }
+}
+
+/// merge3サブコマンドの実行。衝突があった場合はOk(false)を返す
+pub fn run_merge3(args: &Merge3Args) -> Result<bool, String> {
+    let base = LangMap::from(load_by_extension(&remote::resolve(&args.base)?)?);
+    let ours = LangMap::from(load_by_extension(&remote::resolve(&args.ours)?)?);
+    let theirs = LangMap::from(load_by_extension(&remote::resolve(&args.theirs)?)?);
+
+    let output_path = match &args.output {
+        Some(path) => path.clone(),
+        None => {
+            let file_name = Path::new(&args.ours).file_name().and_then(|n| n.to_str()).unwrap_or("merged.lang");
+            format!("./output/{}", file_name)
+        }
+    };
+    let is_lang_output = Path::new(&output_path).extension().is_some_and(|e| e == "lang");
+    if args.conflict == ConflictStyle::Markers && !is_lang_output {
+        return Err(tf("merge.markers_need_lang", &[&output_path]));
+    }
+
+    let merged = merge3(&base, &ours, &theirs);
+    let conflicts: Vec<&Conflict> = merged
+        .iter()
+        .filter_map(|(_, entry)| match entry {
+            Merged::Conflict(c) => Some(c),
+            Merged::Value(_) => None,
+        })
+        .collect();
+
+    match args.conflict {
+        ConflictStyle::Markers => {
+            if let Some(parent_dir) = Path::new(&output_path).parent() {
+                create_dir_all(parent_dir).map_err(|_| tf("io.mkdir_failed", &[&output_path]))?;
+            }
+            let text = render_with_markers(&merged, &args.ours, &args.theirs);
+            fs::write(&output_path, text).map_err(|_| tf("io.create_failed", &[&output_path, &t("io.kind.lang")]))?;
+        }
+        ConflictStyle::Report => {
+            save_by_extension(&output_path, &resolve_to_base(&merged))?;
+        }
+    }
+
+    println!("{} + {} (base: {}) => {}", args.ours, args.theirs, args.base, output_path);
+    if conflicts.is_empty() {
+        println!("{}", t("merge.clean"));
+        return Ok(true);
+    }
+    println!("\n{}", tf("merge.conflicts", &[&conflicts.len()]));
+    for c in &conflicts {
+        println!("- {}: base={} ours={} theirs={}", c.key, describe(&c.base), describe(&c.ours), describe(&c.theirs));
+    }
+    if args.conflict == ConflictStyle::Report {
+        println!("\n{}", t("merge.kept_base"));
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(pairs: &[(&str, &str)]) -> LangMap {
+        pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+    }
+
+    fn values(merged: &[(String, Merged)]) -> Vec<(&str, Option<&str>)> {
+        merged
+            .iter()
+            .map(|(key, entry)| match entry {
+                Merged::Value(value) => (key.as_str(), Some(value.as_str())),
+                Merged::Conflict(_) => (key.as_str(), None),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn takes_the_side_that_changed() {
+        let base = map(&[("a", "1"), ("b", "2"), ("c", "3")]);
+        let ours = map(&[("a", "one"), ("b", "2"), ("c", "3"), ("o", "new")]);
+        let theirs = map(&[("a", "1"), ("b", "two"), ("t", "new")]);
+        let merged = merge3(&base, &ours, &theirs);
+        assert_eq!(values(&merged), [("a", Some("one")), ("b", Some("two")), ("o", Some("new")), ("t", Some("new"))]);
+    }
+
+    #[test]
+    fn different_changes_on_both_sides_conflict() {
+        let merged = merge3(&map(&[("a", "1")]), &map(&[("a", "ours")]), &map(&[("a", "theirs")]));
+        assert_eq!(values(&merged), [("a", None)]);
+        assert_eq!(resolve_to_base(&merged), map(&[("a", "1")]));
+        let text = render_with_markers(&merged, "ours.lang", "theirs.lang");
+        assert_eq!(text, "<<<<<<< ours.lang\na=ours\n=======\na=theirs\n>>>>>>> theirs.lang\n");
+    }
+}
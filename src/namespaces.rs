@@ -0,0 +1,105 @@
+//! キーを名前空間ごとに数える(namespacesサブコマンド)
+//!
+//! 名前空間は、レジストリの種類で始まる新しい形式のキー(`block.<名前空間>.<パス>`)では2番目の区切り、
+//! それ以外(`tile.stone.name` のような旧形式のキーなど)では最初の区切りとする。
+//! --allowed-namespacesを指定すると、それ以外の名前空間のキーを警告(--unknown-namespaces errorならエラー)にする
+
+use clap::ValueEnum;
+use indexmap::IndexMap;
+use crate::cli::NamespacesArgs;
+use crate::convert::files_or_input_dir;
+use crate::format::load_by_extension;
+use crate::i18n::tf;
+
+/// 2番目の区切りが名前空間になるキーの最初の区切り(レジストリの種類)
+const REGISTRY_KINDS: &[&str] = &[
+    "advancements",
+    "attribute",
+    "biome",
+    "block",
+    "container",
+    "effect",
+    "enchantment",
+    "entity",
+    "instrument",
+    "item",
+    "jukebox_song",
+    "painting",
+    "stat",
+    "trim_material",
+    "trim_pattern",
+];
+
+/// 許可されていない名前空間の扱い
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum UnknownNamespaces {
+    /// 報告だけする
+    Warn,
+    /// 終了コードを1にする
+    Error,
+}
+
+/// キーの名前空間
+pub fn namespace_of(key: &str) -> &str {
+    let mut segments = key.split('.');
+    let first = segments.next().unwrap_or_default();
+    match (segments.next(), segments.next()) {
+        (Some(second), Some(_)) if REGISTRY_KINDS.contains(&first) && !second.is_empty() => second,
+        _ => first,
+    }
+}
+
+/// namespacesサブコマンドの実行。許可されていない名前空間があり、--unknown-namespaces errorの場合はOk(false)を返す
+pub fn run_namespaces(args: &NamespacesArgs) -> Result<bool, String> {
+    let mut unknown_total = 0;
+    for path in files_or_input_dir(&args.files, &args.input)? {
+        let path_str = path.to_string_lossy().to_string();
+        let lang_map = load_by_extension(&path_str)?;
+        let mut counts: IndexMap<&str, Vec<&String>> = IndexMap::new();
+        for key in lang_map.keys() {
+            counts.entry(namespace_of(key)).or_default().push(key);
+        }
+        // 件数の多い順、同じ件数なら名前順
+        counts.sort_by(|a, keys_a, b, keys_b| keys_b.len().cmp(&keys_a.len()).then(a.cmp(b)));
+        println!("{}", tf("namespaces.file", &[&path_str, &counts.len()]));
+        for (namespace, keys) in &counts {
+            if args.allowed_namespaces.is_empty() || args.allowed_namespaces.iter().any(|allowed| allowed == namespace) {
+                println!("{}", tf("namespaces.count", &[namespace, &keys.len()]));
+                continue;
+            }
+            unknown_total += keys.len();
+            let mut listed = keys.iter().take(3).map(|key| key.as_str()).collect::<Vec<_>>().join(", ");
+            if keys.len() > 3 {
+                listed.push_str(&tf("namespaces.more", &[&(keys.len() - 3)]));
+            }
+            println!("{}", tf("namespaces.unknown", &[namespace, &keys.len(), &listed]));
+        }
+    }
+    if unknown_total > 0 {
+        println!("{}", tf("namespaces.unknown_total", &[&unknown_total]));
+    }
+    Ok(unknown_total == 0 || args.unknown_namespaces == UnknownNamespaces::Warn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn namespace_of_registry_keys_is_the_second_segment() {
+        assert_eq!(namespace_of("block.minecraft.stone"), "minecraft");
+        assert_eq!(namespace_of("item.examplemod.gem.tooltip"), "examplemod");
+        assert_eq!(namespace_of("entity.create.contraption"), "create");
+    }
+
+    #[test]
+    fn namespace_of_other_keys_is_the_first_segment() {
+        assert_eq!(namespace_of("tile.stone.name"), "tile");
+        assert_eq!(namespace_of("gui.done"), "gui");
+        // レジストリの種類でも区切りが足りない・空ならそのまま
+        assert_eq!(namespace_of("block.stone"), "block");
+        assert_eq!(namespace_of("block..stone"), "block");
+        assert_eq!(namespace_of("nodots"), "nodots");
+        assert_eq!(namespace_of(""), "");
+    }
+}
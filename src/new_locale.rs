@@ -0,0 +1,74 @@
+use std::path::Path;
+use indexmap::IndexMap;
+use clap::ValueEnum;
+use crate::cli::NewLocaleArgs;
+use crate::format::{Format, WriteOptions};
+use crate::i18n::tf;
+use crate::remote;
+use crate::timestamp;
+
+/// 新しいロケールの値の埋め方
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum FillPolicy {
+    /// 空文字列にする
+    Empty,
+    /// テンプレートの値をそのまま使う
+    Source,
+    /// テンプレートの値の先頭に `TODO: ` を付ける
+    Todo,
+}
+
+/// テンプレートのロケール名またはパスから読み込むファイルを探す
+fn find_template(dir: &str, from: &str) -> Result<(String, Format), String> {
+    let from = &remote::resolve(from)?;
+    if let Some(format) = Format::from_path(Path::new(from)).filter(|_| Path::new(from).is_file()) {
+        return Ok((from.to_string(), format));
+    }
+    for format in [Format::Lang, Format::Json] {
+        let path = format!("{}/{}.{}", dir, from, format.extension());
+        if Path::new(&path).is_file() {
+            return Ok((path, format));
+        }
+    }
+    Err(tf("new_locale.template_missing", &[from, &dir]))
+}
+
+/// new-localeサブコマンドの実行
+pub fn run_new_locale(args: &NewLocaleArgs) -> Result<bool, String> {
+    let (template_path, format) = find_template(&args.dir, &args.from)?;
+    let output_path = format!("{}/{}.{}", args.dir, args.locale, format.extension());
+
+    if !args.force {
+        for existing in [Format::Lang, Format::Json] {
+            let path = format!("{}/{}.{}", args.dir, args.locale, existing.extension());
+            if Path::new(&path).exists() {
+                return Err(tf("new_locale.exists", &[&path]));
+            }
+        }
+    }
+
+    let template = format.load(&template_path)?;
+    let lang_map: IndexMap<String, String> = template
+        .into_iter()
+        .map(|(key, value)| {
+            let value = match args.fill {
+                FillPolicy::Empty => String::new(),
+                FillPolicy::Source => value,
+                FillPolicy::Todo => format!("TODO: {}", value),
+            };
+            (key, value)
+        })
+        .collect();
+
+    // JSONにはコメントを書けないため、ヘッダーは.langの場合のみ出力される
+    // URLから取得した場合はキャッシュのファイル名ではなくURLの最後の部分を書く
+    let template_source = if args.from.starts_with("https://") { &args.from } else { &template_path };
+    let template_name = Path::new(template_source).file_name().and_then(|n| n.to_str()).unwrap_or(&template_path);
+    let write_options = WriteOptions {
+        header: vec![tf("new_locale.header", &[&args.locale, &template_name, &timestamp::today()])],
+        ..WriteOptions::default()
+    };
+    format.save_with(&output_path, &lang_map, &write_options)?;
+    println!("{}", tf("io.wrote_keys", &[&template_path, &output_path, &lang_map.len()]));
+    Ok(true)
+}
@@ -0,0 +1,125 @@
+//! 出力ファイル名のテンプレート(`{name}`, `{ext}`, `{dir}`)
+//!
+//! ドットを複数含むファイル名の扱い:
+//! - `{name}` は最後の拡張子(.lang/.json)だけを取り除いた部分。`en_us.backup.lang` は `en_us.backup` になる
+//! - 最後の拡張子が.lang/.jsonでないファイル(`en_us.lang.txt` など)は変換の対象にしない
+//! - --strip-suffixesで指定した中間の拡張子は末尾から順に取り除く。`de_de.min.json` は `min` の指定で `de_de` になる
+//! - 取り除くと名前が空になる場合(`min.json` など)は取り除かない
+
+use std::path::Path;
+use clap::ValueEnum;
+use crate::i18n::tf;
+
+/// 入力ファイルのパスから `{name}` に使う名前を取り出す
+pub fn stem(path: &Path, strip_suffixes: &[String]) -> String {
+    let mut name = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default();
+    while let Some((rest, suffix)) = name.rsplit_once('.') {
+        if rest.is_empty() || !strip_suffixes.iter().any(|strip| strip.eq_ignore_ascii_case(suffix)) {
+            break;
+        }
+        name = rest;
+    }
+    name.to_string()
+}
+
+/// テンプレートの1要素
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    /// 入力ファイル名(拡張子を除く)
+    Name,
+    /// 出力形式の拡張子
+    Ext,
+    /// 入力ディレクトリからの相対ディレクトリ
+    Dir,
+}
+
+/// --output-nameで指定する出力ファイル名のテンプレート
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutputName {
+    parts: Vec<Part>,
+}
+
+impl Default for OutputName {
+    fn default() -> Self {
+        OutputName::parse("{dir}/{name}.{ext}").unwrap()
+    }
+}
+
+impl OutputName {
+    /// テンプレートを解釈する。未知のプレースホルダーや閉じていない波括弧はエラー
+    pub fn parse(template: &str) -> Result<OutputName, String> {
+        let mut parts = Vec::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                parts.push(Part::Text(rest[..start].to_string()));
+            }
+            let end = rest[start..].find('}').ok_or_else(|| tf("convert.output_name.unclosed", &[&template]))?;
+            let part = match &rest[start + 1..start + end] {
+                "name" => Part::Name,
+                "ext" => Part::Ext,
+                "dir" => Part::Dir,
+                other => return Err(tf("convert.output_name.unknown", &[&template, &other])),
+            };
+            parts.push(part);
+            rest = &rest[start + end + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Text(rest.to_string()));
+        }
+        Ok(OutputName { parts })
+    }
+
+    /// 出力先ディレクトリからの相対パスを組み立てる。空の要素と `.` は取り除く
+    pub fn render(&self, name: &str, ext: &str, dir: &str) -> String {
+        let rendered: String = self
+            .parts
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.as_str(),
+                Part::Name => name,
+                Part::Ext => ext,
+                Part::Dir => dir,
+            })
+            .collect();
+        rendered.split(['/', '\\']).filter(|segment| !segment.is_empty() && *segment != ".").collect::<Vec<_>>().join("/")
+    }
+}
+
+/// 複数の入力が同じ出力先になったときの扱い(--name-collisions)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum NameCollisions {
+    /// 重なった出力を入力ディレクトリと同じ構成のサブディレクトリに書き出す
+    Mirror,
+    /// 重なった出力のファイル名の前に入力の相対ディレクトリを付ける(`moda_lang_en_us.json`)
+    Prefix,
+    /// 何も書き込まずに重なった入力を報告する
+    Error,
+}
+
+impl NameCollisions {
+    pub fn name(self) -> &'static str {
+        match self {
+            NameCollisions::Mirror => "mirror",
+            NameCollisions::Prefix => "prefix",
+            NameCollisions::Error => "error",
+        }
+    }
+
+    /// 重なった出力先の相対パスを入力の相対ディレクトリで区別する。Errorと相対ディレクトリが無い場合は変えない
+    pub fn resolve(self, output_name: &str, dir: &str) -> String {
+        let segments: Vec<&str> = dir.split(['/', '\\']).filter(|segment| !segment.is_empty() && *segment != ".").collect();
+        if segments.is_empty() {
+            return output_name.to_string();
+        }
+        match self {
+            NameCollisions::Mirror => format!("{}/{}", segments.join("/"), output_name),
+            NameCollisions::Prefix => match output_name.rsplit_once('/') {
+                Some((parent, file)) => format!("{}/{}_{}", parent, segments.join("_"), file),
+                None => format!("{}_{}", segments.join("_"), output_name),
+            },
+            NameCollisions::Error => output_name.to_string(),
+        }
+    }
+}
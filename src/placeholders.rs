@@ -0,0 +1,189 @@
+//! 値の中のプレースホルダーの形式ごとの使用状況(placeholdersサブコマンド)
+//!
+//! 形式は `%s`、`%d`、`%1$s`(番号付き)、`{0}`、`{{name}}` の5つ。
+//! `%s` と `%d` の組み合わせはprintf形式として普通に使うため、1つの値で形式が混ざっているとはみなさない
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use clap::ValueEnum;
+use serde::Serialize;
+use unicode_width::UnicodeWidthStr;
+use crate::cli::PlaceholdersArgs;
+use crate::convert::files_or_input_dir;
+use crate::format::Format;
+use crate::i18n::{t, tf};
+use crate::report::{csv_field, pad};
+use crate::transform::placeholder::find_placeholders;
+
+/// プレースホルダーの形式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Style {
+    PrintfString,
+    PrintfNumber,
+    Positional,
+    Brace,
+    Named,
+}
+
+/// 表の列の順
+const STYLES: [Style; 5] = [Style::PrintfString, Style::PrintfNumber, Style::Positional, Style::Brace, Style::Named];
+
+impl Style {
+    fn of(token: &str) -> Style {
+        match token {
+            "%s" => Style::PrintfString,
+            "%d" => Style::PrintfNumber,
+            _ if token.starts_with('%') => Style::Positional,
+            _ if token.starts_with("{{") => Style::Named,
+            _ => Style::Brace,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Style::PrintfString => "%s",
+            Style::PrintfNumber => "%d",
+            Style::Positional => "%1$s",
+            Style::Brace => "{0}",
+            Style::Named => "{{name}}",
+        }
+    }
+
+    /// 混在を判定するときの系統(`%s` と `%d` は同じ系統)
+    fn family(self) -> Style {
+        match self {
+            Style::PrintfNumber => Style::PrintfString,
+            other => other,
+        }
+    }
+
+    fn index(self) -> usize {
+        STYLES.iter().position(|style| *style == self).unwrap()
+    }
+}
+
+/// 出力の書式
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat {
+    /// 桁を揃えた表
+    Table,
+    Csv,
+    Json,
+}
+
+/// 1ファイル分の集計
+#[derive(Serialize)]
+struct FileCounts {
+    file: String,
+    /// STYLESの順の出現回数
+    counts: [usize; 5],
+    total: usize,
+}
+
+/// 1つの値に複数の系統が混ざっているキー
+#[derive(Serialize)]
+struct MixedKey {
+    file: String,
+    key: String,
+    styles: Vec<&'static str>,
+}
+
+/// JSONで書き出す内容
+#[derive(Serialize)]
+struct Report {
+    styles: Vec<&'static str>,
+    files: Vec<FileCounts>,
+    totals: [usize; 5],
+    total: usize,
+    mixed: Vec<MixedKey>,
+}
+
+impl Report {
+    fn table(&self) -> String {
+        let (file_label, total_label) = (t("table.file"), t("table.total"));
+        let header: Vec<&str> = [file_label.as_str()].into_iter().chain(self.styles.iter().copied()).chain([total_label.as_str()]).collect();
+        let mut rows: Vec<Vec<String>> = self
+            .files
+            .iter()
+            .map(|file| [file.file.clone()].into_iter().chain(file.counts.iter().map(usize::to_string)).chain([file.total.to_string()]).collect())
+            .collect();
+        rows.push([total_label.clone()].into_iter().chain(self.totals.iter().map(usize::to_string)).chain([self.total.to_string()]).collect());
+        let widths: Vec<usize> = (0..header.len())
+            .map(|column| rows.iter().map(|row| row[column].width()).chain([header[column].width()]).max().unwrap_or(0))
+            .collect();
+        let mut out = String::new();
+        let line = |cells: Vec<&str>| cells.iter().zip(&widths).map(|(cell, width)| pad(cell, *width)).collect::<Vec<_>>().join("  ").trim_end().to_string();
+        let _ = writeln!(out, "{}", line(header.clone()));
+        for row in &rows {
+            let _ = writeln!(out, "{}", line(row.iter().map(String::as_str).collect()));
+        }
+        if !self.mixed.is_empty() {
+            let _ = writeln!(out, "\n{}", tf("placeholders.mixed", &[&self.mixed.len()]));
+            for mixed in &self.mixed {
+                let _ = writeln!(out, "- {}: {} ({})", mixed.file, mixed.key, mixed.styles.join(", "));
+            }
+        }
+        out
+    }
+
+    /// 表の部分だけのCSV(最後の行は合計)
+    fn csv(&self) -> String {
+        let mut out = String::new();
+        let header: Vec<String> = ["file"].into_iter().chain(self.styles.iter().copied()).chain(["total"]).map(csv_field).collect();
+        let _ = writeln!(out, "{}", header.join(","));
+        for file in &self.files {
+            let _ = writeln!(out, "{},{},{}", csv_field(&file.file), join_counts(&file.counts), file.total);
+        }
+        let _ = writeln!(out, "total,{},{}", join_counts(&self.totals), self.total);
+        out
+    }
+}
+
+fn join_counts(counts: &[usize]) -> String {
+    counts.iter().map(usize::to_string).collect::<Vec<_>>().join(",")
+}
+
+/// placeholdersサブコマンドの実行
+pub fn run_placeholders(args: &PlaceholdersArgs) -> Result<bool, String> {
+    let mut report = Report { styles: STYLES.iter().map(|style| style.label()).collect(), files: Vec::new(), totals: [0; 5], total: 0, mixed: Vec::new() };
+    for path in files_or_input_dir(&args.files, &args.input)? {
+        let path_str = path.to_string_lossy().to_string();
+        let format = Format::from_path(Path::new(&path)).ok_or_else(|| tf("io.unsupported_extension", &[&path_str]))?;
+        let lang_map = format.load(&path_str)?;
+        let mut counts = [0; 5];
+        for (key, value) in &lang_map {
+            let mut families: Vec<Style> = Vec::new();
+            for range in find_placeholders(value) {
+                let style = Style::of(&value[range]);
+                counts[style.index()] += 1;
+                if !families.contains(&style.family()) {
+                    families.push(style.family());
+                }
+            }
+            if families.len() > 1 {
+                let styles = families.iter().map(|style| style.label()).collect();
+                report.mixed.push(MixedKey { file: path_str.clone(), key: key.clone(), styles });
+            }
+        }
+        for (total, count) in report.totals.iter_mut().zip(counts) {
+            *total += count;
+        }
+        let total = counts.iter().sum();
+        report.total += total;
+        report.files.push(FileCounts { file: path_str, counts, total });
+    }
+    let contents = match args.format {
+        ReportFormat::Table => report.table(),
+        ReportFormat::Csv => report.csv(),
+        ReportFormat::Json => serde_json::to_string_pretty(&report).map_err(|e| e.to_string())? + "\n",
+    };
+    match &args.output {
+        Some(path) => {
+            fs::write(path, contents).map_err(|_| tf("io.write_failed", &[path]))?;
+            println!("{}", tf("io.written", &[path]));
+        }
+        None => print!("{}", contents),
+    }
+    Ok(true)
+}
@@ -0,0 +1,52 @@
+//! 対話メニューの5: 各変換方向で行う処理の一覧(ファイルの中身は読まない)
+//!
+//! 変換と同じ計画(plan_jobs_with_mode)を使い、ディレクトリの情報だけで作成・上書きする出力を表示する
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::convert::{plan_jobs_with_mode, Mode};
+use crate::format::Format;
+use crate::i18n::{t, tf};
+use crate::menu::mode_label;
+use crate::settings::Settings;
+
+/// 入力ディレクトリ直下の、どの変換方向の対象にもならないファイル
+fn ignored_inputs(input_dir: &str) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(input_dir) else {
+        return Vec::new();
+    };
+    let mut ignored: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).filter(|path| path.is_file() && Format::from_path(path).is_none()).collect();
+    ignored.sort();
+    ignored
+}
+
+/// 変換方向ごとの計画を表示する
+pub fn print_preview(settings: &Settings) {
+    let all = settings.to_options(Mode::All);
+    let (jobs, _) = plan_jobs_with_mode(&all, Mode::All).unwrap_or_default();
+    let lang_count = jobs.iter().filter(|job| job.format == Format::Lang).count();
+    let ignored = ignored_inputs(&all.input_dir);
+    println!("\n{}", tf("preview.inputs", &[&all.input_dir, &lang_count, &(jobs.len() - lang_count), &ignored.len()]));
+
+    for mode in [Mode::Lang2json, Mode::Json2lang, Mode::All] {
+        let options = settings.to_options(mode);
+        let (mut jobs, _) = plan_jobs_with_mode(&options, mode).unwrap_or_default();
+        jobs.sort_by(|a, b| a.input_path.cmp(&b.input_path));
+        let overwrites = jobs.iter().filter(|job| Path::new(&job.output_path).exists()).count();
+        println!("\n{}  {}", mode_label(mode), tf("preview.counts", &[&(jobs.len() - overwrites), &overwrites]));
+        // すべて変換の内訳はlang=>jsonとjson=>langと同じため、件数だけを表示する
+        if mode == Mode::All {
+            continue;
+        }
+        for job in &jobs {
+            let marker = if Path::new(&job.output_path).exists() { t("preview.overwrite") } else { t("preview.create") };
+            println!("  - {} => {} ({})", job.input_path, job.output_path, marker);
+        }
+    }
+    if !ignored.is_empty() {
+        println!("\n{}", t("preview.ignored"));
+        for path in &ignored {
+            println!("  - {}", path.display());
+        }
+    }
+}
@@ -0,0 +1,91 @@
+//! Forge/Fabricのmodのソースツリーの一括変換(--project)
+//!
+//! `<ルート>/src/<ソースセット>/resources/assets/<modid>/lang` を探す(マルチプロジェクトの場合は1つ下のディレクトリの `src` も探す)。
+//! データ生成の出力(ソースセット `generated`)は既定では対象にしない。
+//! 出力は各langディレクトリに書き戻すか、--project-outputのディレクトリにルートからの相対パスのまま書き出す
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::convert::{process_files, ConvertOptions, Summary};
+use crate::i18n::{t, tf};
+use crate::report::pad;
+
+/// データ生成が出力するソースセットの名前
+const GENERATED_SOURCE_SET: &str = "generated";
+
+/// 1つのmodのlangディレクトリ
+pub struct ModLang {
+    pub modid: String,
+    pub dir: PathBuf,
+}
+
+/// ディレクトリ直下のディレクトリ(名前順)
+fn subdirs(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).filter(|path| path.is_dir()).collect();
+    dirs.sort();
+    dirs
+}
+
+/// ルートとその直下のディレクトリから、modごとのlangディレクトリを探す
+pub fn find_lang_dirs(root: &Path, include_generated: bool) -> Vec<ModLang> {
+    let mut found = Vec::new();
+    for project in [root.to_path_buf()].into_iter().chain(subdirs(root)) {
+        for source_set in subdirs(&project.join("src")) {
+            if !include_generated && source_set.file_name().is_some_and(|name| name == GENERATED_SOURCE_SET) {
+                continue;
+            }
+            for mod_dir in subdirs(&source_set.join("resources").join("assets")) {
+                let dir = mod_dir.join("lang");
+                if dir.is_dir() {
+                    let modid = mod_dir.file_name().unwrap_or_default().to_string_lossy().to_string();
+                    found.push(ModLang { modid, dir });
+                }
+            }
+        }
+    }
+    found
+}
+
+/// 見つかったmodごとに変換し、最後にmodごとの結果を表示する
+pub fn run_project(options: &mut ConvertOptions, root: &str) -> Result<Vec<Summary>, String> {
+    let root_path = Path::new(root);
+    if !root_path.is_dir() {
+        return Err(tf("convert.input_dir_missing", &[&root]));
+    }
+    let mods = find_lang_dirs(root_path, options.include_generated);
+    if mods.is_empty() {
+        return Err(tf("project.none_found", &[&root]));
+    }
+    let mut results = Vec::new();
+    for lang in &mods {
+        let dir = lang.dir.to_string_lossy().to_string();
+        options.input_dir = dir.clone();
+        match &options.project_output {
+            Some(output) => {
+                let relative = lang.dir.strip_prefix(root_path).unwrap_or(&lang.dir);
+                options.output_dir = Path::new(output).join(relative).to_string_lossy().to_string();
+                options.in_place = false;
+            }
+            None => options.in_place = true,
+        }
+        options.validate()?;
+        println!("\n{}", tf("project.header", &[&lang.modid, &dir]));
+        let summary = process_files(options);
+        let stop = summary.interrupted || summary.aborted.is_some();
+        results.push(summary);
+        if stop {
+            break;
+        }
+    }
+
+    println!("\n{}", t("project.summary"));
+    let width = mods.iter().map(|lang| lang.modid.len()).max().unwrap_or(0);
+    for (lang, summary) in mods.iter().zip(&results) {
+        let counts = tf("project.row", &[&summary.converted, &summary.skipped, &summary.failed_count()]);
+        println!("- {}  {} ({})", pad(&lang.modid, width), counts, lang.dir.display());
+    }
+    Ok(results)
+}
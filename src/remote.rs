@@ -0,0 +1,89 @@
+//! 参照ファイルの引数に指定された `https://` のURLの取得とキャッシュ
+//!
+//! 取得したファイルはカレントディレクトリの `.json_lang_remote` に、URLのハッシュと元の拡張子の名前で保存し、
+//! ETagを隣の `.etag` ファイルに記録する。次回は `If-None-Match` を付けて取得し、304ならキャッシュを使う。
+//! 取得に失敗した場合はキャッシュがあれば警告してそれを使う
+
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use crate::cache;
+use crate::i18n::tf;
+
+/// 取得したファイルを保存するディレクトリ
+pub const REMOTE_CACHE_DIR: &str = ".json_lang_remote";
+
+/// 1回の取得の制限時間
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// --offlineが指定されたか
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// --offlineを設定する。以降のURLはキャッシュからのみ読む
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+/// URLならキャッシュに取得したファイルのパスを、それ以外はそのまま返す
+pub fn resolve(path_or_url: &str) -> Result<String, String> {
+    if !path_or_url.starts_with("https://") {
+        return Ok(path_or_url.to_string());
+    }
+    let url = path_or_url;
+    // 形式は拡張子で判定するため、URLの最後の拡張子をキャッシュのファイル名にも付ける
+    let file_name = url.split(['?', '#']).next().unwrap_or(url).rsplit('/').next().unwrap_or("");
+    let extension = Path::new(file_name).extension().and_then(|ext| ext.to_str()).unwrap_or("txt");
+    let cached = format!("{}/{}.{}", REMOTE_CACHE_DIR, cache::content_hash(url.as_bytes()), extension);
+    let etag_path = format!("{}.etag", cached);
+    let has_cache = Path::new(&cached).exists();
+
+    if OFFLINE.load(Ordering::Relaxed) {
+        return if has_cache { Ok(cached) } else { Err(tf("remote.offline_missing", &[&url])) };
+    }
+    let etag = if has_cache { fs::read_to_string(&etag_path).ok() } else { None };
+    match fetch(url, etag.as_deref()) {
+        Ok(Fetched::NotModified) => Ok(cached),
+        Ok(Fetched::Body(body, etag)) => {
+            fs::create_dir_all(REMOTE_CACHE_DIR).map_err(|e| tf("remote.cache_write_failed", &[&cached, &e]))?;
+            fs::write(&cached, body).map_err(|e| tf("remote.cache_write_failed", &[&cached, &e]))?;
+            let _ = match etag {
+                Some(etag) => fs::write(&etag_path, etag),
+                None => fs::remove_file(&etag_path),
+            };
+            Ok(cached)
+        }
+        Err(e) if has_cache => {
+            eprintln!("{}", tf("remote.using_cache", &[&url, &e]));
+            Ok(cached)
+        }
+        Err(e) => Err(tf("remote.fetch_failed", &[&url, &e])),
+    }
+}
+
+/// 取得結果
+enum Fetched {
+    /// キャッシュのETagと同じ
+    NotModified,
+    /// 内容とETag
+    Body(Vec<u8>, Option<String>),
+}
+
+fn fetch(url: &str, etag: Option<&str>) -> Result<Fetched, String> {
+    let agent: ureq::Agent = ureq::Agent::config_builder().http_status_as_error(false).timeout_global(Some(FETCH_TIMEOUT)).build().into();
+    let mut request = agent.get(url);
+    if let Some(etag) = etag {
+        request = request.header("If-None-Match", etag);
+    }
+    let mut response = request.call().map_err(|e| e.to_string())?;
+    let status = response.status();
+    if status.as_u16() == 304 {
+        return Ok(Fetched::NotModified);
+    }
+    if !status.is_success() {
+        return Err(status.to_string());
+    }
+    let etag = response.headers().get("etag").and_then(|value| value.to_str().ok()).map(str::to_string);
+    let body = response.body_mut().read_to_vec().map_err(|e| e.to_string())?;
+    Ok(Fetched::Body(body, etag))
+}
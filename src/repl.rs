@@ -0,0 +1,75 @@
+//! 貼り付けた.lang/JSONをその場で変換して表示する対話モード(ファイルは読み書きしない)
+//!
+//! `.` だけの行か入力の終わり(Ctrl+D)までを1つの入力として読み、先頭が `{` ならJSON、それ以外は.langとして変換する
+
+use std::io::{self, BufRead, Write};
+use json_lang::diagnostic::parse_lang_strict;
+use crate::format::{parse_json_map, Format, WriteOptions};
+use crate::i18n::{t, tf};
+
+/// 入力の終わりを表す行
+const END_MARKER: &str = ".";
+
+/// `.` の行までを読む。入力が終わっていればfalseも返す
+fn read_snippet(stdin: &mut impl BufRead) -> (String, bool) {
+    let mut snippet = String::new();
+    loop {
+        let mut line = String::new();
+        match stdin.read_line(&mut line) {
+            Ok(0) | Err(_) => return (snippet, false),
+            Ok(_) => {}
+        }
+        if line.trim_end_matches(['\r', '\n']) == END_MARKER {
+            return (snippet, true);
+        }
+        snippet.push_str(&line);
+    }
+}
+
+/// 1つの入力を変換する。解析できなければ問題のある行を示すメッセージを返す
+fn convert(snippet: &str, options: &WriteOptions) -> Result<Vec<u8>, String> {
+    if snippet.trim_start().starts_with('{') {
+        let lang_map = parse_json_map(snippet.as_bytes()).map_err(|e| {
+            let line = snippet.lines().nth(e.line().saturating_sub(1)).unwrap_or("");
+            format!("{}\n  {}", tf("repl.json_error", &[&e.line(), &e]), line)
+        })?;
+        return Format::Lang.render(&lang_map, options);
+    }
+    let lang_map = parse_lang_strict(snippet.as_bytes()).map_err(|errors| {
+        let lines: Vec<&str> = snippet.lines().collect();
+        let errors: Vec<String> = errors
+            .iter()
+            .map(|error| tf("repl.lang_error", &[&(error.line + 1), &error.message, &lines.get(error.line).copied().unwrap_or("")]))
+            .collect();
+        errors.join("\n")
+    })?;
+    Format::Json.render(&lang_map, options)
+}
+
+/// 入力が終わるまで読み込みと変換を繰り返す
+pub fn run(options: &WriteOptions) {
+    println!("{}", t("repl.intro"));
+    let mut stdin = io::stdin().lock();
+    loop {
+        print!("{} ", t("repl.prompt"));
+        io::stdout().flush().unwrap();
+        let (snippet, more) = read_snippet(&mut stdin);
+        if !snippet.trim().is_empty() {
+            match convert(&snippet, options) {
+                Ok(output) => {
+                    let mut stdout = io::stdout().lock();
+                    let _ = stdout.write_all(&output);
+                    if !output.ends_with(b"\n") {
+                        let _ = stdout.write_all(b"\n");
+                    }
+                    let _ = stdout.flush();
+                }
+                Err(e) => eprintln!("{}\n{}", e, t("repl.retry")),
+            }
+        }
+        if !more {
+            println!();
+            return;
+        }
+    }
+}
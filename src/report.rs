@@ -0,0 +1,163 @@
+//! 一括変換の結果表の表示(色付け、全角文字の幅を考慮した桁揃え)
+
+use std::fs;
+use anstream::println;
+use anstyle::{AnsiColor, Style};
+use serde::Serialize;
+use unicode_width::UnicodeWidthStr;
+use crate::convert::Batch;
+use crate::format::OutputEncoding;
+use crate::i18n::{t, tf};
+use crate::timing::{self, FileTiming};
+
+/// ファイルごとの処理結果
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileStatus {
+    Converted,
+    Skipped,
+    Failed,
+}
+
+impl FileStatus {
+    fn label(self) -> String {
+        match self {
+            FileStatus::Converted => t("status.converted"),
+            FileStatus::Skipped => t("status.skipped"),
+            FileStatus::Failed => t("status.failed"),
+        }
+    }
+
+    pub fn style(self) -> Style {
+        let color = match self {
+            FileStatus::Converted => AnsiColor::Green,
+            FileStatus::Skipped => AnsiColor::Yellow,
+            FileStatus::Failed => AnsiColor::Red,
+        };
+        Style::new().fg_color(Some(color.into()))
+    }
+}
+
+/// 結果表の1行
+#[derive(Serialize)]
+pub struct FileRow {
+    pub file: String,
+    pub status: FileStatus,
+    /// 出力したキーの数(変換しなかった場合はNone)
+    pub keys: Option<usize>,
+    /// keysのうち--with-fallbackで基準のロケールから補ったキーの数
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filled_keys: Option<usize>,
+    pub reason: String,
+}
+
+/// 一時的な失敗を再試行して書き込めた出力ファイル
+#[derive(Serialize)]
+pub struct RetriedWrite {
+    pub output: String,
+    pub retries: u32,
+}
+
+/// 文字列を色付けする。色を出力しない環境ではanstreamが取り除く
+pub fn paint(text: &str, style: Style) -> String {
+    format!("{}{}{}", style.render(), text, style.render_reset())
+}
+
+/// 表示幅がwidthになるよう右側を空白で埋める
+pub fn pad(text: &str, width: usize) -> String {
+    format!("{}{}", text, " ".repeat(width.saturating_sub(text.width())))
+}
+
+/// カンマ・引用符・改行を含むフィールドを引用符で囲む
+pub fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// 結果を「ファイル・状態・キー数・理由」の列に揃えて表示する
+pub fn print_table<'a>(rows: impl IntoIterator<Item = &'a FileRow>) {
+    let rows: Vec<&FileRow> = rows.into_iter().collect();
+    let headers = [t("table.file"), t("table.status"), t("table.keys"), t("table.reason")];
+    let keys: Vec<String> = rows.iter().map(|row| row.keys.map(|n| n.to_string()).unwrap_or_else(|| "-".to_string())).collect();
+    let file_width = rows.iter().map(|row| row.file.width()).chain([headers[0].width()]).max().unwrap_or(0);
+    let status_width = rows.iter().map(|row| row.status.label().width()).chain([headers[1].width()]).max().unwrap_or(0);
+    let keys_width = keys.iter().map(|k| k.width()).chain([headers[2].width()]).max().unwrap_or(0);
+
+    let bold = Style::new().bold();
+    println!(
+        "{}  {}  {}  {}",
+        paint(&pad(&headers[0], file_width), bold),
+        paint(&pad(&headers[1], status_width), bold),
+        paint(&pad(&headers[2], keys_width), bold),
+        paint(&headers[3], bold)
+    );
+    for (row, keys) in rows.iter().zip(&keys) {
+        let line = format!(
+            "{}  {}  {}{}  {}",
+            pad(&row.file, file_width),
+            paint(&pad(&row.status.label(), status_width), row.status.style()),
+            " ".repeat(keys_width - keys.width()),
+            keys,
+            row.reason
+        );
+        println!("{}", line.trim_end());
+    }
+}
+
+/// --reportで書き出すJSONの内容
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    /// 出力ファイルの文字コード(--output-encoding)
+    output_encoding: &'static str,
+    files: &'a [FileRow],
+    timings: &'a [FileTiming],
+    /// 書き込みを再試行した出力ファイル(無ければ空)
+    retried_writes: &'a [RetriedWrite],
+    /// --limit/--start-afterで一部だけを処理した場合の続きの情報
+    #[serde(skip_serializing_if = "Option::is_none")]
+    batch: Option<&'a Batch>,
+    total_load_ms: f64,
+    total_save_ms: f64,
+    total_ms: f64,
+}
+
+/// 結果と処理時間をJSONで書き出す
+pub fn write_json_report(path: &str, rows: &[FileRow], timings: &[FileTiming], retried_writes: &[RetriedWrite], batch: Option<&Batch>, encoding: OutputEncoding) -> Result<(), String> {
+    let (load, save, total) = timing::totals(timings);
+    let report = JsonReport {
+        output_encoding: encoding.name(),
+        files: rows,
+        timings,
+        retried_writes,
+        batch,
+        total_load_ms: timing::millis(load),
+        total_save_ms: timing::millis(save),
+        total_ms: timing::millis(total),
+    };
+    let contents = serde_json::to_string_pretty(&report).map_err(|_| tf("io.write_failed", &[&path]))?;
+    fs::write(path, contents).map_err(|_| tf("io.write_failed", &[&path]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_report_lists_retried_writes() {
+        let path = std::env::temp_dir().join(format!("json_lang_report_test_{}.json", std::process::id()));
+        let path_str = path.to_str().unwrap();
+        let rows = [FileRow { file: "en_us.lang".to_string(), status: FileStatus::Converted, keys: Some(1), filled_keys: None, reason: String::new() }];
+        let retried = [RetriedWrite { output: "./output/en_us.json".to_string(), retries: 2 }];
+        write_json_report(path_str, &rows, &[], &retried, None, OutputEncoding::Utf8).unwrap();
+        let report: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(report["retried_writes"], serde_json::json!([{"output": "./output/en_us.json", "retries": 2}]));
+
+        write_json_report(path_str, &rows, &[], &[], None, OutputEncoding::Utf8).unwrap();
+        let report: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(report["retried_writes"], serde_json::json!([]));
+        fs::remove_file(&path).unwrap();
+    }
+}
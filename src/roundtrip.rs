@@ -0,0 +1,82 @@
+//! .langとJSONの往復変換で内容が失われないかの確認(verifyサブコマンドと同じ検査)
+//!
+//! 書き出しも読み直しもメモリ上で行うため、wasm32-unknown-unknownでも使える
+
+use crate::codec::{parse_lang_map_with, read_json_map, write_encoded, write_json, write_lang, ReadOptions, WriteOptions};
+use crate::lang_map::{DiffResult, LangMap};
+
+/// 往復変換で失われた内容。形式ごとに、元のマップと読み直したマップの違い(失われなかった形式はNone)
+#[derive(Debug)]
+pub struct RoundtripLoss {
+    pub lang: Option<Box<DiffResult>>,
+    pub json: Option<Box<DiffResult>>,
+}
+
+/// 書き出す形式
+#[derive(Clone, Copy)]
+enum Leg {
+    Lang,
+    Json,
+}
+
+/// 指定した形式に書き出して読み直す。書き出しはメモリ上なので失敗しない
+fn round_trip(lang_map: &LangMap, leg: Leg, options: &WriteOptions) -> LangMap {
+    let mut rendered = Vec::new();
+    let _ = write_encoded(&mut rendered, options.encoding, |mut out| match leg {
+        Leg::Lang => write_lang(&mut out, lang_map, options),
+        Leg::Json => write_json(&mut out, lang_map, options),
+    });
+    let parsed = match leg {
+        Leg::Lang => parse_lang_map_with(&String::from_utf8_lossy(&rendered), &options.read_back()),
+        Leg::Json => read_json_map(rendered.as_slice(), &ReadOptions::default()).map(|(loaded, _)| loaded.lang_map).unwrap_or_default(),
+    };
+    LangMap::from(parsed)
+}
+
+/// マップを.langとJSONの両方に書き出して読み直し、キー・値・順序がすべて元と同じか確かめる
+pub fn verify_roundtrip(lang_map: &LangMap, options: &WriteOptions) -> Result<(), RoundtripLoss> {
+    let leg = |leg| Some(lang_map.diff(&round_trip(lang_map, leg, options))).filter(|diff| !diff.is_empty()).map(Box::new);
+    match (leg(Leg::Lang), leg(Leg::Json)) {
+        (None, None) => Ok(()),
+        (lang, json) => Err(RoundtripLoss { lang, json }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use super::*;
+
+    /// 既定の書式の.langでそのまま表せるキーと値か(`=`・先頭の`#`・改行・前後の空白・値の`\r`を含まない)
+    fn representable(key: &str, value: &str) -> bool {
+        !key.contains(['=', '\n']) && !key.starts_with('#') && key.trim() == key && !value.contains(['\n', '\r']) && value.trim() == value
+    }
+
+    /// `=`・`#`・改行・空白・非ASCIIを多く含む文字列(空文字列も含む)
+    fn nasty() -> impl Strategy<Value = String> {
+        prop::collection::vec(prop::sample::select(vec!["=", "#", "\n", "\r", " ", "\t", "\\", "a", ".", "é", "あ", "😀", "\u{3000}", "%s"]), 0..6)
+            .prop_map(|parts| parts.concat())
+    }
+
+    proptest! {
+        #[test]
+        fn roundtrip_loses_only_unrepresentable_entries(entries in prop::collection::vec((nasty(), nasty()), 0..8)) {
+            let lang_map: LangMap = entries.into_iter().collect();
+            let expected = lang_map.iter().all(|(key, value)| representable(key, value));
+            match verify_roundtrip(&lang_map, &WriteOptions::default()) {
+                Ok(()) => prop_assert!(expected, "{:?}", lang_map),
+                Err(loss) => {
+                    prop_assert!(!expected, "{:?}: {:?}", lang_map, loss);
+                    prop_assert!(loss.lang.is_some());
+                    prop_assert!(loss.json.is_none(), "JSON should keep every string: {:?}", loss);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn empty_key_and_value_survive() {
+        let lang_map: LangMap = [(String::new(), String::new()), ("a".to_string(), "x=y # z".to_string())].into_iter().collect();
+        assert!(verify_roundtrip(&lang_map, &WriteOptions::default()).is_ok());
+    }
+}
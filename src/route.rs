@@ -0,0 +1,49 @@
+//! キーの接頭辞ごとに出力ファイルを分ける(--route)
+//!
+//! `gui.*=assets/mymod/lang/{name}.{ext}` のように接頭辞(末尾の `*` は省略できる)と出力ファイル名のテンプレートを組にする。
+//! 複数のルートに当てはまるキーは最も長く一致する接頭辞のルートに、どれにも当てはまらないキーは通常の出力先に書き出す
+
+use indexmap::IndexMap;
+use crate::i18n::tf;
+use crate::output_name::OutputName;
+
+/// 1つのルート
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Route {
+    pub prefix: String,
+    /// 出力先ディレクトリからの相対パスのテンプレート(--output-nameと同じ書式)
+    pub template: OutputName,
+}
+
+impl Route {
+    /// `接頭辞=テンプレート` を解釈する
+    pub fn parse(text: &str) -> Result<Route, String> {
+        let Some((pattern, template)) = text.split_once('=') else {
+            return Err(tf("convert.route.syntax", &[&text]));
+        };
+        let pattern = pattern.trim();
+        let prefix = pattern.strip_suffix('*').unwrap_or(pattern);
+        if prefix.is_empty() || prefix.contains('*') {
+            return Err(tf("convert.route.prefix", &[&text]));
+        }
+        Ok(Route { prefix: prefix.to_string(), template: OutputName::parse(template.trim())? })
+    }
+}
+
+/// キーをルートごとに分ける。戻り値はどのルートにも当てはまらないキーと、ルートと同じ順に並べた各ルートのキー。
+/// どれもファイル内の順序を保つ
+pub fn split(lang_map: IndexMap<String, String>, routes: &[Route]) -> (IndexMap<String, String>, Vec<IndexMap<String, String>>) {
+    let mut routed = vec![IndexMap::new(); routes.len()];
+    if routes.is_empty() {
+        return (lang_map, routed);
+    }
+    let mut rest = IndexMap::new();
+    for (key, value) in lang_map {
+        let best = routes.iter().enumerate().filter(|(_, route)| key.starts_with(&route.prefix)).max_by_key(|(_, route)| route.prefix.len());
+        match best {
+            Some((index, _)) => routed[index].insert(key, value),
+            None => rest.insert(key, value),
+        };
+    }
+    (rest, routed)
+}
@@ -0,0 +1,62 @@
+//! 実行ごとの結果を出力ディレクトリの conversion.log に追記する(--no-run-logで無効)
+//!
+//! 1回の実行は `[日時]` で始まるブロックで、コマンドライン、変換方向、件数、失敗の詳細を書く。
+//! ファイルがLOG_SIZE_LIMITを超えたら conversion.log.1 に移してから書き始める(古い .1 は消える)
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use clap::ValueEnum;
+use crate::convert::{ConvertOptions, Summary};
+use crate::i18n::tf;
+use crate::timestamp;
+
+pub const RUN_LOG_NAME: &str = "conversion.log";
+/// 移したあとの古いログの名前
+pub const ROTATED_LOG_NAME: &str = "conversion.log.1";
+/// これを超えたらログを移す(1MiB)
+const LOG_SIZE_LIMIT: u64 = 1024 * 1024;
+
+/// ログのファイル名か(--clean-allで消さない)
+pub fn is_run_log(path: &Path) -> bool {
+    path.file_name().is_some_and(|name| name == RUN_LOG_NAME || name == ROTATED_LOG_NAME)
+}
+
+/// 1回分のブロック
+fn render(options: &ConvertOptions, summary: &Summary) -> String {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut block = format!("[{}] json_lang {}\n", timestamp::now_utc(), args.join(" "));
+    let mode = options.mode.to_possible_value().map(|value| value.get_name().to_string()).unwrap_or_default();
+    block.push_str(&format!("mode={} input={} output={}\n", mode, options.input_dir, options.output_root()));
+    block.push_str(&format!(
+        "converted={} skipped={} filtered={} failed={}{}\n",
+        summary.converted,
+        summary.skipped,
+        summary.filtered,
+        summary.failed_count(),
+        if summary.interrupted { " interrupted" } else { "" }
+    ));
+    if let Some(batch) = &summary.batch {
+        block.push_str(&format!("last_processed={} remaining={}\n", batch.last_processed.as_deref().unwrap_or("-"), batch.remaining));
+    }
+    for (stage, failures) in [("read", &summary.failed_reads), ("transform", &summary.failed_transforms), ("write", &summary.failed_writes)] {
+        for failure in failures {
+            block.push_str(&format!("failed {}: {}\n", stage, failure));
+        }
+    }
+    block.push('\n');
+    block
+}
+
+/// 結果をログに追記する
+pub fn append(options: &ConvertOptions, summary: &Summary) -> Result<(), String> {
+    let root = Path::new(options.output_root());
+    let path = root.join(RUN_LOG_NAME);
+    let path_str = path.to_string_lossy().to_string();
+    fs::create_dir_all(root).map_err(|_| tf("io.write_failed", &[&path_str]))?;
+    if fs::metadata(&path).is_ok_and(|metadata| metadata.len() > LOG_SIZE_LIMIT) {
+        fs::rename(&path, root.join(ROTATED_LOG_NAME)).map_err(|_| tf("io.write_failed", &[&path_str]))?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(&path).map_err(|_| tf("io.write_failed", &[&path_str]))?;
+    file.write_all(render(options, summary).as_bytes()).map_err(|_| tf("io.write_failed", &[&path_str]))
+}
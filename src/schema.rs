@@ -0,0 +1,84 @@
+//! ユーザーが用意したJSON Schemaによる出力内容の検証(--schema)
+//!
+//! ファイルごとに、加工後のマップ(.langから変換する場合も書き出すJSONと同じ内容)を検証する
+
+use std::fmt;
+use std::fs;
+use std::sync::Arc;
+use jsonschema::Validator;
+use indexmap::IndexMap;
+use serde_json::Value;
+use crate::i18n::tf;
+
+/// 読み込んだスキーマ
+#[derive(Clone)]
+pub struct Schema {
+    path: String,
+    /// スキーマの内容(設定のハッシュに含める)
+    document: Value,
+    validator: Arc<Validator>,
+}
+
+impl fmt::Debug for Schema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Schema({}, {})", self.path, self.document)
+    }
+}
+
+/// スキーマを読み込む(clapのvalue_parserとして使う)。読めないスキーマは変換を始める前にエラーにする
+pub fn parse_schema(path: &str) -> Result<Schema, String> {
+    let contents = fs::read_to_string(path).map_err(|e| tf("convert.schema_unreadable", &[&path, &e]))?;
+    let document: Value = serde_json::from_str(&contents).map_err(|e| tf("convert.schema_not_json", &[&path, &e]))?;
+    let validator = jsonschema::validator_for(&document).map_err(|e| tf("convert.schema_invalid", &[&path, &e]))?;
+    Ok(Schema { path: path.to_string(), document, validator: Arc::new(validator) })
+}
+
+impl Schema {
+    /// 違反を `JSONポインタ: メッセージ` の形で返す
+    pub fn violations(&self, lang_map: &IndexMap<String, String>) -> Vec<String> {
+        let instance = Value::Object(lang_map.iter().map(|(key, value)| (key.clone(), Value::String(value.clone()))).collect());
+        self.validator
+            .iter_errors(&instance)
+            .map(|error| {
+                let pointer = error.instance_path().to_string();
+                format!("{}: {}", if pointer.is_empty() { "/" } else { &pointer }, error)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema_file(name: &str, contents: &str) -> String {
+        let path = std::env::temp_dir().join(format!("json_lang_schema_test_{}_{}.json", std::process::id(), name));
+        fs::write(&path, contents).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn parse_schema_rejects_unreadable_and_invalid_schemas() {
+        let missing = std::env::temp_dir().join("json_lang_schema_test_missing.json");
+        assert!(parse_schema(missing.to_str().unwrap()).unwrap_err().contains("json_lang_schema_test_missing"));
+        for (name, contents) in [("not_json", "{"), ("invalid", r#"{"type": 1}"#)] {
+            let path = schema_file(name, contents);
+            assert!(parse_schema(&path).unwrap_err().contains(&path));
+            fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn violations_point_at_the_offending_value() {
+        let path = schema_file("valid", r#"{"required": ["gui.done"], "additionalProperties": {"maxLength": 3}}"#);
+        let schema = parse_schema(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let ok: IndexMap<String, String> = [("gui.done".to_string(), "OK".to_string())].into_iter().collect();
+        assert!(schema.violations(&ok).is_empty());
+        let bad: IndexMap<String, String> = [("gui.cancel".to_string(), "Cancel".to_string())].into_iter().collect();
+        let violations = schema.violations(&bad);
+        assert_eq!(violations.len(), 2);
+        assert!(violations.iter().any(|violation| violation.starts_with("/: ")));
+        assert!(violations.iter().any(|violation| violation.starts_with("/gui.cancel: ")));
+    }
+}
@@ -0,0 +1,143 @@
+//! ローカルのHTTPサーバーで変換を提供する(Webの翻訳エディターなどからの利用向け)
+//!
+//! - `POST /lang2json` .langのテキストを受け取りJSONを返す
+//! - `POST /json2lang` JSONのテキストを受け取り.langを返す
+//! - `GET /health` 起動しているか確かめる
+//!
+//! 変換できない入力には400と `{"error": "...", "diagnostics": [{"severity": "error", "line": 行, "column": 列, "message": "..."}]}` を返す。
+//! 変換は状態を持たないため、リクエストは複数のスレッドで同時に処理する
+
+use std::io::Read;
+use std::sync::Arc;
+use std::thread;
+use serde::Serialize;
+use tiny_http::{Header, Method, Request, Response, Server};
+use crate::cli::ServeArgs;
+use json_lang::diagnostic::{parse_lang_lossy, Severity};
+use crate::format::{parse_json_map, Format, WriteOptions};
+use crate::i18n::{t, tf};
+
+/// 受け付ける本文の最大サイズ
+const MAX_BODY_BYTES: u64 = 16 * 1024 * 1024;
+
+const CONTENT_TYPE_JSON: &str = "application/json; charset=utf-8";
+const CONTENT_TYPE_TEXT: &str = "text/plain; charset=utf-8";
+
+/// 入力の問題の位置(1始まり)と内容
+#[derive(Serialize)]
+struct Diagnostic {
+    severity: &'static str,
+    line: usize,
+    column: usize,
+    message: String,
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: String,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// 応答のステータス・Content-Type・本文
+struct Reply {
+    status: u16,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Reply {
+    fn ok(content_type: &'static str, body: Vec<u8>) -> Reply {
+        Reply { status: 200, content_type, body }
+    }
+
+    fn error(status: u16, error: impl Into<String>, diagnostics: Vec<Diagnostic>) -> Reply {
+        let body = ErrorBody { error: error.into(), diagnostics };
+        Reply { status, content_type: CONTENT_TYPE_JSON, body: serde_json::to_vec(&body).unwrap_or_default() }
+    }
+}
+
+/// .langを変換する。エラーの問題(区切り文字の無い行など)があれば、警告も含めたすべての問題とともに400にする
+fn lang_to_json(body: &[u8]) -> Reply {
+    let (lang_map, diagnostics) = parse_lang_lossy(body);
+    if diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error) {
+        let diagnostics = diagnostics
+            .into_iter()
+            .map(|diagnostic| Diagnostic {
+                severity: if diagnostic.severity == Severity::Error { "error" } else { "warning" },
+                line: diagnostic.line + 1,
+                column: diagnostic.columns.start + 1,
+                message: diagnostic.message,
+            })
+            .collect();
+        return Reply::error(400, t("serve.lang_parse_failed"), diagnostics);
+    }
+    match Format::Json.render(&lang_map, &WriteOptions::default()) {
+        Ok(json) => Reply::ok(CONTENT_TYPE_JSON, json),
+        Err(e) => Reply::error(500, e, Vec::new()),
+    }
+}
+
+/// JSONを変換する。文字列以外の値はconvertと同じく読み飛ばす
+fn json_to_lang(body: &[u8]) -> Reply {
+    let lang_map = match parse_json_map(body) {
+        Ok(lang_map) => lang_map,
+        Err(e) => {
+            let diagnostic = Diagnostic { severity: "error", line: e.line(), column: e.column(), message: e.to_string() };
+            return Reply::error(400, t("serve.json_parse_failed"), vec![diagnostic]);
+        }
+    };
+    match Format::Lang.render(&lang_map, &WriteOptions::default()) {
+        Ok(lang) => Reply::ok(CONTENT_TYPE_TEXT, lang),
+        Err(e) => Reply::error(500, e, Vec::new()),
+    }
+}
+
+/// パスとメソッドから処理を選ぶ
+fn route(method: &Method, path: &str, body: &[u8]) -> Reply {
+    match (method, path) {
+        (Method::Get, "/health") => Reply::ok(CONTENT_TYPE_JSON, br#"{"status":"ok"}"#.to_vec()),
+        (Method::Post, "/lang2json") => lang_to_json(body),
+        (Method::Post, "/json2lang") => json_to_lang(body),
+        (_, "/health" | "/lang2json" | "/json2lang") => Reply::error(405, t("serve.method_not_allowed"), Vec::new()),
+        _ => Reply::error(404, t("serve.not_found"), Vec::new()),
+    }
+}
+
+fn handle(mut request: Request) {
+    let path = request.url().split('?').next().unwrap_or("").to_string();
+    let mut body = Vec::new();
+    let reply = match request.as_reader().take(MAX_BODY_BYTES + 1).read_to_end(&mut body) {
+        Err(e) => Reply::error(400, tf("serve.body_read_failed", &[&e]), Vec::new()),
+        Ok(_) if body.len() as u64 > MAX_BODY_BYTES => Reply::error(413, tf("serve.body_too_large", &[&MAX_BODY_BYTES]), Vec::new()),
+        Ok(_) => route(request.method(), &path, &body),
+    };
+    let header = Header::from_bytes("Content-Type", reply.content_type).unwrap();
+    let response = Response::from_data(reply.body).with_status_code(reply.status).with_header(header);
+    if let Err(e) = request.respond(response) {
+        eprintln!("{}", tf("serve.respond_failed", &[&e]));
+    }
+}
+
+/// serveサブコマンドの実行。終了するまで戻らない
+pub fn run_serve(args: &ServeArgs) -> Result<bool, String> {
+    let address = format!("{}:{}", args.host, args.port);
+    let server = Server::http(&address).map_err(|e| tf("serve.bind_failed", &[&address, &e]))?;
+    let server = Arc::new(server);
+    eprintln!("{}", tf("serve.listening", &[&address]));
+
+    let workers = thread::available_parallelism().map_or(4, |n| n.get());
+    let handles: Vec<_> = (0..workers)
+        .map(|_| {
+            let server = Arc::clone(&server);
+            thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    handle(request);
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        let _ = handle.join();
+    }
+    Ok(true)
+}
@@ -0,0 +1,105 @@
+use std::fs;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use crate::convert::{ConvertOptions, Mode};
+use crate::format::{WriteOptions, DEFAULT_JSON_INDENT};
+use crate::i18n::tf;
+use crate::transform::sort::SortOrder;
+
+/// 設定を保存するファイル名(実行ファイルと同じディレクトリに置く)
+pub const SETTINGS_FILE_NAME: &str = "settings.json";
+
+/// 対話メニューで変更でき、再起動後も引き継がれる設定
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub input_dir: String,
+    pub output_dir: String,
+    pub sort: SortOrder,
+    pub indent: usize,
+    /// 前回メニューで選んだ変換方向(Mode::numberの1〜3)。次のメニューでEnterだけを押すと同じ変換を行う
+    pub last_mode: Option<u8>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            input_dir: "./input".to_string(),
+            output_dir: "./output".to_string(),
+            sort: SortOrder::None,
+            indent: DEFAULT_JSON_INDENT,
+            last_mode: None,
+        }
+    }
+}
+
+/// 設定ファイルのパス。実行ファイルの場所が分からない場合はカレントディレクトリ
+pub fn settings_path() -> PathBuf {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(SETTINGS_FILE_NAME)))
+        .unwrap_or_else(|| PathBuf::from(SETTINGS_FILE_NAME))
+}
+
+impl Settings {
+    /// 設定を読み込む。存在しない・壊れている場合は既定値を使う
+    pub fn load() -> Settings {
+        let path = settings_path();
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return Settings::default();
+        };
+        serde_json::from_str(&contents).unwrap_or_else(|_| {
+            println!("{}", tf("settings.corrupt", &[&path.display()]));
+            Settings::default()
+        })
+    }
+
+    pub fn save(&self) -> Result<PathBuf, String> {
+        let path = settings_path();
+        let contents = serde_json::to_string_pretty(self).map_err(|_| tf("io.write_failed", &[&path.display()]))?;
+        fs::write(&path, contents).map_err(|_| tf("io.write_failed", &[&path.display()]))?;
+        Ok(path)
+    }
+
+    /// この設定で指定した方向に変換するオプション
+    pub fn to_options(&self, mode: Mode) -> ConvertOptions {
+        ConvertOptions {
+            mode,
+            input_dir: self.input_dir.clone(),
+            output_dir: self.output_dir.clone(),
+            sort: self.sort,
+            write: WriteOptions { json_indent: self.indent, ..WriteOptions::default() },
+            ..ConvertOptions::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn last_mode_survives_a_save_and_load() {
+        let settings = Settings { last_mode: Some(Mode::Json2lang.number()), ..Settings::default() };
+        let json = serde_json::to_string_pretty(&settings).unwrap();
+        let loaded: Settings = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.last_mode.and_then(Mode::from_number), Some(Mode::Json2lang));
+    }
+
+    #[test]
+    fn settings_without_last_mode_use_the_default() {
+        let loaded: Settings = serde_json::from_str(r#"{"input_dir": "./in", "indent": 4}"#).unwrap();
+        assert_eq!(loaded.last_mode, None);
+        assert_eq!(loaded.input_dir, "./in");
+        assert_eq!(loaded.output_dir, Settings::default().output_dir);
+    }
+
+    #[test]
+    fn mode_numbers_round_trip() {
+        for mode in [Mode::Lang2json, Mode::Json2lang, Mode::All] {
+            assert_eq!(Mode::from_number(mode.number()), Some(mode));
+        }
+        assert_eq!(Mode::from_number(0), None);
+        assert_eq!(Mode::from_number(4), None);
+    }
+}
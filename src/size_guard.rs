@@ -0,0 +1,68 @@
+//! 大きすぎる入力ファイルの扱い(--max-file-size / --large-files)
+
+use clap::ValueEnum;
+use crate::i18n::tf;
+
+/// --max-file-sizeの既定値(256MiB)
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 256 * 1024 * 1024;
+
+/// 上限を超えたファイルの扱い
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum LargeFiles {
+    /// 変換せずに省略し、結果に表示する
+    Skip,
+    /// ファイル全体を文字列として読み込まず、少しずつ読みながら変換する
+    Stream,
+}
+
+/// `512K` `256M` `2G` のようなサイズを解釈する(単位なしはバイト、1K = 1024バイト)
+pub fn parse_size(text: &str) -> Result<u64, String> {
+    let text = text.trim();
+    let (number, unit) = match text.char_indices().find(|(_, c)| !c.is_ascii_digit()) {
+        Some((index, _)) => text.split_at(index),
+        None => (text, ""),
+    };
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().trim_end_matches("IB").trim_end_matches('B') {
+        "" => 1,
+        "K" => 1024,
+        "M" => 1024 * 1024,
+        "G" => 1024 * 1024 * 1024,
+        _ => return Err(tf("convert.size.unit", &[&text])),
+    };
+    number
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .ok_or_else(|| tf("convert.size.invalid", &[&text]))
+}
+
+/// 人が読みやすい単位で表示する
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [(&str, u64); 3] = [("GiB", 1024 * 1024 * 1024), ("MiB", 1024 * 1024), ("KiB", 1024)];
+    for (unit, size) in UNITS {
+        if bytes >= size {
+            return format!("{:.1}{}", bytes as f64 / size as f64, unit);
+        }
+    }
+    format!("{}B", bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_size_accepts_binary_units() {
+        assert_eq!(parse_size("512"), Ok(512));
+        assert_eq!(parse_size("512K"), Ok(512 * 1024));
+        assert_eq!(parse_size(" 256mb "), Ok(256 * 1024 * 1024));
+        assert_eq!(parse_size("2GiB"), Ok(2 * 1024 * 1024 * 1024));
+    }
+
+    #[test]
+    fn parse_size_rejects_bad_units_and_overflow() {
+        assert!(parse_size("5T").unwrap_err().contains("5T"));
+        assert!(parse_size("K").is_err());
+        assert!(parse_size("99999999999999999999G").is_err());
+    }
+}
@@ -0,0 +1,34 @@
+//! 拡張子と内容が合っていないファイルの検出(--auto-detect)
+//!
+//! .langの最初のコメントでない行が `{` で始まればJSON、JSONとして読めない.jsonのコメントでない行がすべて `key=value` なら.langとみなす
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use crate::format::Format;
+
+/// .langの判定で読む先頭の大きさ
+const LANG_SNIFF_BYTES: u64 = 64 * 1024;
+
+/// 内容から判断した形式が拡張子の形式と違えばその形式を返す。同じか判断できなければNone
+pub fn mismatch(path: &Path, format: Format) -> Option<Format> {
+    match format {
+        Format::Lang => {
+            let mut head = Vec::new();
+            File::open(path).ok()?.take(LANG_SNIFF_BYTES).read_to_end(&mut head).ok()?;
+            let text = String::from_utf8_lossy(&head);
+            let first = text.trim_start_matches('\u{feff}').lines().map(str::trim).find(|line| !line.is_empty() && !line.starts_with('#'))?;
+            first.starts_with('{').then_some(Format::Json)
+        }
+        Format::Json => {
+            let bytes = std::fs::read(path).ok()?;
+            if serde_json::from_slice::<serde::de::IgnoredAny>(&bytes).is_ok() {
+                return None;
+            }
+            let text = String::from_utf8_lossy(&bytes);
+            let mut lines = text.trim_start_matches('\u{feff}').lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')).peekable();
+            lines.peek()?;
+            lines.all(|line| line.split_once('=').is_some_and(|(key, _)| !key.is_empty() && !key.starts_with('{'))).then_some(Format::Lang)
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use std::fs;
+use std::path::Path;
+use indexmap::IndexMap;
+use crate::cli::SplitArgs;
+use crate::format::{parse_lang_lines, Format, LangLine};
+use crate::i18n::tf;
+use json_lang::LangMap;
+
+/// 接頭辞→出力ファイル名の対応表を読み込む(`接頭辞=ファイル名`の.lang形式、または.json)
+fn load_mapping(path: &str) -> Result<Vec<(String, String)>, String> {
+    if Path::new(path).extension().is_some_and(|e| e == "json") {
+        return Ok(Format::Json.load(path)?.into_iter().collect());
+    }
+    let contents = fs::read_to_string(path).map_err(|_| tf("io.read_failed", &[&path]))?;
+    Ok(parse_lang_lines(&contents)
+        .into_iter()
+        .filter_map(|line| match line {
+            LangLine::Entry(prefix, name) => Some((prefix, name)),
+            _ => None,
+        })
+        .collect())
+}
+
+/// キーの振り分け先のファイル名(拡張子なし)を決める
+/// 対応表があれば最も長く一致する接頭辞、無ければ最初の`.`までの区切りを使う
+fn route<'a>(key: &'a str, mapping: &'a [(String, String)], misc: &'a str) -> &'a str {
+    if mapping.is_empty() {
+        return key.split_once('.').map(|(first, _)| first).filter(|first| !first.is_empty()).unwrap_or(misc);
+    }
+    mapping
+        .iter()
+        .filter(|(prefix, _)| key.starts_with(prefix.as_str()))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, name)| name.as_str())
+        .unwrap_or(misc)
+}
+
+/// splitサブコマンドの実行
+pub fn run_split(args: &SplitArgs) -> Result<bool, String> {
+    let input_format = Format::from_path(Path::new(&args.input))
+        .ok_or_else(|| tf("io.unsupported_extension", &[&args.input]))?;
+    let output_format = args.format.unwrap_or(input_format);
+    let mapping = match &args.mapping {
+        Some(path) => load_mapping(path)?,
+        None => Vec::new(),
+    };
+
+    let lang_map = LangMap::from(input_format.load(&args.input)?);
+    let mut parts: IndexMap<String, LangMap> = IndexMap::new();
+    for (key, value) in lang_map {
+        let name = route(&key, &mapping, &args.misc).to_string();
+        parts.entry(name).or_default().insert(key, value);
+    }
+
+    for (name, part) in &parts {
+        let file_name = if Format::from_path(Path::new(name)).is_some() {
+            name.clone()
+        } else {
+            format!("{}.{}", name, output_format.extension())
+        };
+        let output_path = format!("{}/{}", args.out_dir, file_name);
+        let format = Format::from_path(Path::new(&output_path)).unwrap_or(output_format);
+        format.save(&output_path, part)?;
+        println!("{}", tf("io.wrote_keys", &[&args.input, &output_path, &part.len()]));
+    }
+    Ok(true)
+}
@@ -0,0 +1,60 @@
+//! --transactionalの一時ディレクトリ。すべての変換が成功した場合だけ出力先へ移動する
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::format::retry_transient;
+use crate::i18n::tf;
+
+/// 出力ルートの中に作る一時ディレクトリの名前(同じファイルシステム上なので移動は1ファイルずつ不可分に行える)
+pub const STAGING_DIR_NAME: &str = ".json_lang_staging";
+
+/// --transactionalの結果
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Transaction {
+    /// 指定した数のファイルを出力先へ移動した
+    Committed(usize),
+    /// 失敗があったため出力先を変更しなかった
+    RolledBack,
+}
+
+/// 一時ディレクトリに書いたファイルと、その移動先
+pub struct Staging {
+    root: PathBuf,
+    dir: PathBuf,
+    files: Vec<(String, String)>,
+}
+
+impl Staging {
+    /// 前回の中断で残った一時ディレクトリがあれば削除してから始める
+    pub fn new(output_root: &str) -> Staging {
+        let dir = Path::new(output_root).join(STAGING_DIR_NAME);
+        let _ = fs::remove_dir_all(&dir);
+        Staging { root: PathBuf::from(output_root), dir, files: Vec::new() }
+    }
+
+    /// 出力先に対応する一時ファイルのパスを記録して返す
+    pub fn stage(&mut self, output_path: &str) -> String {
+        let relative = Path::new(output_path).strip_prefix(&self.root).unwrap_or(Path::new(output_path));
+        let staged = self.dir.join(relative).to_string_lossy().into_owned();
+        self.files.push((staged.clone(), output_path.to_string()));
+        staged
+    }
+
+    /// 一時ファイルを出力先へ移動する。戻り値は移動したファイル数
+    /// 出力先を他のプロセスが開いている間の一時的な失敗は、ファイルの作成と同じく待って再試行する
+    pub fn commit(self) -> Result<usize, String> {
+        for (staged, output_path) in &self.files {
+            if let Some(parent) = Path::new(output_path).parent() {
+                fs::create_dir_all(parent).map_err(|_| tf("io.mkdir_failed", &[&output_path]))?;
+            }
+            retry_transient(|| fs::rename(staged, output_path)).0.map_err(|e| tf("staging.move_failed", &[staged, output_path, &e]))?;
+        }
+        let _ = fs::remove_dir_all(&self.dir);
+        Ok(self.files.len())
+    }
+
+    /// 一時ディレクトリを削除し、出力先は変更しない
+    pub fn rollback(self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
@@ -0,0 +1,35 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 現在のUNIX時刻(秒)
+pub fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// UNIX時刻(秒)をUTCの(年, 月, 日)に変換する
+fn civil_from_secs(secs: u64) -> (i64, u32, u32) {
+    // Howard Hinnantのcivil_from_daysアルゴリズム
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// 今日の日付(UTC, YYYY-MM-DD)
+pub fn today() -> String {
+    let (year, month, day) = civil_from_secs(now_secs());
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// 現在の日時(UTC, YYYY-MM-DDTHH:MM:SSZ)
+pub fn now_utc() -> String {
+    let secs = now_secs();
+    let (year, month, day) = civil_from_secs(secs);
+    let time = secs % 86_400;
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, time / 3_600, time % 3_600 / 60, time % 60)
+}
@@ -0,0 +1,72 @@
+//! ファイルごとの処理時間の計測と、時間のかかったファイルの表示
+
+use std::time::Duration;
+use anstream::println;
+use anstyle::Style;
+use serde::Serialize;
+use unicode_width::UnicodeWidthStr;
+use crate::i18n::{t, tf};
+use crate::report::{pad, paint};
+
+/// --timingsの件数を省略したとき(--verboseを含む)に表示する件数
+pub const DEFAULT_SLOWEST: usize = 10;
+
+/// 1ファイルの処理時間
+#[derive(Clone, Debug, Serialize)]
+pub struct FileTiming {
+    pub file: String,
+    /// 読み込みと加工
+    #[serde(rename = "load_ms", serialize_with = "as_millis")]
+    pub load: Duration,
+    /// 書き込み(--dry-runでは0)
+    #[serde(rename = "save_ms", serialize_with = "as_millis")]
+    pub save: Duration,
+    #[serde(rename = "total_ms", serialize_with = "as_millis")]
+    pub total: Duration,
+}
+
+fn as_millis<S: serde::Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(millis(*duration))
+}
+
+pub fn millis(duration: Duration) -> f64 {
+    (duration.as_secs_f64() * 1000.0 * 10.0).round() / 10.0
+}
+
+/// すべてのファイルの合計
+pub fn totals(timings: &[FileTiming]) -> (Duration, Duration, Duration) {
+    timings.iter().fold((Duration::ZERO, Duration::ZERO, Duration::ZERO), |(load, save, total), timing| {
+        (load + timing.load, save + timing.save, total + timing.total)
+    })
+}
+
+/// 処理時間の長い順に上位count件の表と合計を表示する
+pub fn print_slowest(timings: &[FileTiming], count: usize) {
+    let mut slowest: Vec<&FileTiming> = timings.iter().collect();
+    slowest.sort_by_key(|timing| std::cmp::Reverse(timing.total));
+    slowest.truncate(count);
+
+    println!("\n{}", tf("timing.slowest", &[&slowest.len()]));
+    let headers = [t("table.file"), t("timing.load"), t("timing.save"), t("timing.total")];
+    let cells: Vec<[String; 3]> = slowest
+        .iter()
+        .map(|timing| [timing.load, timing.save, timing.total].map(|d| format!("{:.1}", millis(d))))
+        .collect();
+    let file_width = slowest.iter().map(|timing| timing.file.width()).chain([headers[0].width()]).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..3).map(|i| cells.iter().map(|c| c[i].width()).chain([headers[i + 1].width()]).max().unwrap_or(0)).collect();
+
+    let bold = Style::new().bold();
+    println!(
+        "{}  {}  {}  {}",
+        paint(&pad(&headers[0], file_width), bold),
+        paint(&pad(&headers[1], widths[0]), bold),
+        paint(&pad(&headers[2], widths[1]), bold),
+        paint(&headers[3], bold)
+    );
+    for (timing, cells) in slowest.iter().zip(&cells) {
+        let numbers: Vec<String> = cells.iter().zip(&widths).map(|(cell, width)| format!("{:>width$}", cell, width = width)).collect();
+        println!("{}  {}", pad(&timing.file, file_width), numbers.join("  "));
+    }
+    let (load, save, total) = totals(timings);
+    println!("{}", tf("timing.totals", &[&format!("{:.1}", millis(load)), &format!("{:.1}", millis(save)), &format!("{:.1}", millis(total))]));
+}
@@ -0,0 +1,22 @@
+//! tracingによる構造化ログ(--trace-format)。指定しない場合は何も出力しない
+
+use std::io::{self, IsTerminal};
+use clap::ValueEnum;
+
+/// ログの書式
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum TraceFormat {
+    /// 人が読むための1行ずつのテキスト
+    Text,
+    /// 1行に1つのJSONオブジェクト(NDJSON)
+    Json,
+}
+
+/// 標準エラー出力にログを書き出すよう設定する
+pub fn init(format: TraceFormat, no_color: bool) {
+    let builder = tracing_subscriber::fmt().with_writer(io::stderr).with_target(false);
+    match format {
+        TraceFormat::Text => builder.with_ansi(!no_color && io::stderr().is_terminal()).init(),
+        TraceFormat::Json => builder.json().with_span_list(false).init(),
+    }
+}
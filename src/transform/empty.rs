@@ -0,0 +1,93 @@
+//! 空のキー(`=value`、JSONの `""`)と空の値(`key=`)の扱い(--empty-keys/--empty-values)
+//!
+//! 空のキーは1つのファイルに1つしか残らず、空の値はゲーム内で見えない文字列になるため、既定では報告だけする
+
+use clap::ValueEnum;
+use indexmap::IndexMap;
+use crate::i18n::tf;
+
+/// 空のキー・空の値の扱い
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum EmptyPolicy {
+    /// ファイルの変換を失敗にする
+    Error,
+    /// 取り除いて報告する
+    Skip,
+    /// 残して報告する
+    Warn,
+    /// 報告せずに残す
+    Keep,
+}
+
+/// 見つかった空のキーと空の値の数
+#[derive(Debug)]
+pub struct Empties {
+    pub keys: usize,
+    pub values: usize,
+}
+
+/// 空のキーと空の値を数え、ポリシーに従って取り除くかエラーにする。
+/// 空のキーの数は読み込み時に数えた回数(key_occurrences)を使う。マップには重複をまとめた1つしか残らないため
+pub fn apply(lang_map: &mut IndexMap<String, String>, key_occurrences: usize, keys: EmptyPolicy, values: EmptyPolicy, notes: &mut Vec<String>) -> Result<Empties, String> {
+    // 読み込み後に補ったキー(--with-fallback)で空のキーが加わった場合も1件と数える
+    let empty_keys = if lang_map.contains_key("") { key_occurrences.max(1) } else { 0 };
+    let empty_values: Vec<String> = lang_map.iter().filter(|(key, value)| !key.is_empty() && value.is_empty()).map(|(key, _)| key.clone()).collect();
+    if empty_keys > 0 {
+        match keys {
+            EmptyPolicy::Error => return Err(tf("transform.empty_key_error", &[&empty_keys])),
+            EmptyPolicy::Skip => {
+                lang_map.shift_remove("");
+                notes.push(tf("transform.empty_key_removed", &[&empty_keys]));
+            }
+            EmptyPolicy::Warn => notes.push(tf("transform.empty_key", &[&empty_keys])),
+            EmptyPolicy::Keep => {}
+        }
+    }
+    if !empty_values.is_empty() {
+        let listed = empty_values.join(", ");
+        match values {
+            EmptyPolicy::Error => return Err(tf("transform.empty_value_error", &[&listed])),
+            EmptyPolicy::Skip => {
+                lang_map.retain(|key, value| key.is_empty() || !value.is_empty());
+                notes.push(tf("transform.empty_values_removed", &[&empty_values.len(), &listed]));
+            }
+            EmptyPolicy::Warn => notes.push(tf("transform.empty_values", &[&empty_values.len(), &listed])),
+            EmptyPolicy::Keep => {}
+        }
+    }
+    Ok(Empties { keys: empty_keys, values: empty_values.len() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(entries: &[(&str, &str)]) -> IndexMap<String, String> {
+        entries.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn empty_keys_use_the_occurrences_counted_while_loading() {
+        let mut lang_map = map(&[("", "last"), ("a", "1")]);
+        let mut notes = Vec::new();
+        let empties = apply(&mut lang_map, 3, EmptyPolicy::Warn, EmptyPolicy::Keep, &mut notes).unwrap();
+        assert_eq!(empties.keys, 3);
+        assert!(notes[0].contains('3'));
+        // 読み込み後に加わった空のキーも数える
+        assert_eq!(apply(&mut lang_map, 0, EmptyPolicy::Keep, EmptyPolicy::Keep, &mut notes).unwrap().keys, 1);
+        // 取り除かれたなどでマップに無ければ数えない
+        assert_eq!(apply(&mut map(&[("a", "1")]), 2, EmptyPolicy::Keep, EmptyPolicy::Keep, &mut notes).unwrap().keys, 0);
+    }
+
+    #[test]
+    fn policies_remove_or_reject_empties() {
+        let mut notes = Vec::new();
+        let mut lang_map = map(&[("", "x"), ("a", ""), ("b", "1")]);
+        let empties = apply(&mut lang_map, 2, EmptyPolicy::Skip, EmptyPolicy::Skip, &mut notes).unwrap();
+        assert_eq!((empties.keys, empties.values), (2, 1));
+        assert_eq!(lang_map, map(&[("b", "1")]));
+        assert_eq!(notes.len(), 2);
+        assert!(apply(&mut map(&[("", "x")]), 1, EmptyPolicy::Error, EmptyPolicy::Keep, &mut notes).is_err());
+        assert!(apply(&mut map(&[("a", "")]), 0, EmptyPolicy::Keep, EmptyPolicy::Error, &mut notes).unwrap_err().contains('a'));
+    }
+}
@@ -0,0 +1,146 @@
+//! 保存時の値のエスケープ(--escape-profile)と、読み込み時のエスケープの解除(--unescape-profile)
+//!
+//! 既に文字参照になっている `&` はそのまま残すため、同じファイルを繰り返し変換しても二重にエスケープされない
+
+use clap::ValueEnum;
+use indexmap::IndexMap;
+use crate::i18n::tf;
+
+/// エスケープの方式。新しい方式は置き換える文字と文字参照の表を返すだけで追加できる
+pub trait EscapeProfile {
+    /// エスケープする文字と、その文字参照
+    fn entities(&self) -> &'static [(char, &'static str)];
+
+    /// 値をエスケープする。既にある文字参照はそのまま残し、その数も返す
+    fn escape(&self, value: &str) -> (String, usize) {
+        let mut escaped = String::with_capacity(value.len());
+        let mut existing = 0;
+        for (index, c) in value.char_indices() {
+            if c == '&' && entity_len(&value[index..], self.entities()).is_some() {
+                existing += 1;
+                escaped.push(c);
+                continue;
+            }
+            match self.entities().iter().find(|(from, _)| *from == c) {
+                Some((_, entity)) => escaped.push_str(entity),
+                None => escaped.push(c),
+            }
+        }
+        (escaped, existing)
+    }
+
+    /// 文字参照を元の文字に戻す。`&amp;lt;` は `&lt;` になる(一度だけ戻す)
+    fn unescape(&self, value: &str) -> String {
+        let mut unescaped = String::with_capacity(value.len());
+        let mut rest = value;
+        while let Some(start) = rest.find('&') {
+            unescaped.push_str(&rest[..start]);
+            rest = &rest[start..];
+            match entity_len(rest, self.entities()).and_then(|len| decode(&rest[..len], self.entities()).map(|c| (len, c))) {
+                Some((len, c)) => {
+                    unescaped.push(c);
+                    rest = &rest[len..];
+                }
+                None => {
+                    unescaped.push('&');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        unescaped.push_str(rest);
+        unescaped
+    }
+}
+
+/// HTML向け(`& < > "`)
+pub struct Html;
+
+impl EscapeProfile for Html {
+    fn entities(&self) -> &'static [(char, &'static str)] {
+        &[('&', "&amp;"), ('<', "&lt;"), ('>', "&gt;"), ('"', "&quot;")]
+    }
+}
+
+/// XML向け(`& < > " '`)
+pub struct Xml;
+
+impl EscapeProfile for Xml {
+    fn entities(&self) -> &'static [(char, &'static str)] {
+        &[('&', "&amp;"), ('<', "&lt;"), ('>', "&gt;"), ('"', "&quot;"), ('\'', "&apos;")]
+    }
+}
+
+/// --escape-profile/--unescape-profileで選べる方式
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Escape {
+    /// 何もしない
+    None,
+    Html,
+    Xml,
+}
+
+impl Escape {
+    /// 方式の実装。Noneなら何もしない
+    pub fn profile(self) -> Option<&'static dyn EscapeProfile> {
+        match self {
+            Escape::None => None,
+            Escape::Html => Some(&Html),
+            Escape::Xml => Some(&Xml),
+        }
+    }
+}
+
+/// `&` で始まるテキストが、表にある文字参照か数値文字参照(`&#60;`/`&#x3C;`)で始まればその長さ
+fn entity_len(text: &str, entities: &[(char, &str)]) -> Option<usize> {
+    if let Some((_, entity)) = entities.iter().find(|(_, entity)| text.starts_with(entity)) {
+        return Some(entity.len());
+    }
+    let body = text.strip_prefix("&#")?;
+    let (digits, hex) = match body.strip_prefix(['x', 'X']) {
+        Some(digits) => (digits, true),
+        None => (body, false),
+    };
+    let count = digits.chars().take_while(|c| if hex { c.is_ascii_hexdigit() } else { c.is_ascii_digit() }).count();
+    (count > 0 && digits[count..].starts_with(';')).then(|| text.len() - digits.len() + count + 1)
+}
+
+/// 文字参照1つを文字に戻す
+fn decode(entity: &str, entities: &[(char, &str)]) -> Option<char> {
+    if let Some((c, _)) = entities.iter().find(|(_, known)| *known == entity) {
+        return Some(*c);
+    }
+    let number = entity.strip_prefix("&#")?.strip_suffix(';')?;
+    let code = match number.strip_prefix(['x', 'X']) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+        None => number.parse().ok()?,
+    };
+    char::from_u32(code)
+}
+
+/// すべての値をエスケープする。既に文字参照を含む値があれば報告する
+pub fn escape_values(lang_map: &mut IndexMap<String, String>, escape: Escape, notes: &mut Vec<String>) {
+    let Some(profile) = escape.profile() else {
+        return;
+    };
+    let mut already = Vec::new();
+    for (key, value) in lang_map.iter_mut() {
+        let (escaped, existing) = profile.escape(value);
+        if existing > 0 {
+            already.push(key.clone());
+        }
+        *value = escaped;
+    }
+    if !already.is_empty() {
+        notes.push(tf("transform.already_escaped", &[&already.len(), &already.join(", ")]));
+    }
+}
+
+/// すべての値のエスケープを解除する
+pub fn unescape_values(lang_map: &mut IndexMap<String, String>, escape: Escape) {
+    let Some(profile) = escape.profile() else {
+        return;
+    };
+    for value in lang_map.values_mut() {
+        *value = profile.unescape(value);
+    }
+}
@@ -0,0 +1,142 @@
+//! --transform-cmdで指定した外部コマンドによる値の加工
+//!
+//! 1ファイルごとにコマンドを1回実行し、標準入力に `キー<TAB>値` の行を渡して、標準出力の同じ形式の行を新しい値として使う。
+//! 値の中の `\` ・タブ・改行・CRは `\\` `\t` `\n` `\r` と書く(出力も同じ規則で読む)。
+//! 出力はすべてのキーを入力と同じ順に含む必要があり、行数やキーが違う・終了コードが0でない・時間切れの場合はそのファイルの変換を失敗にする
+
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use indexmap::IndexMap;
+use crate::i18n::tf;
+
+/// --transform-timeoutの既定の秒数
+pub const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+/// 値に含まれる区切りの文字をエスケープする
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// escapeの逆。未知のエスケープはそのまま残す
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => out.push('\\'),
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// 実行しているOSのシェルでコマンドを組み立てる
+fn shell(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.args(["/C", command]);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.args(["-c", command]);
+        shell
+    }
+}
+
+/// コマンドを実行し、出力の値で置き換える。置き換えて値が変わったキーと元の値を返す
+pub fn apply(command: &str, timeout: Duration, lang_map: &mut IndexMap<String, String>) -> Result<Vec<(String, String)>, String> {
+    if lang_map.is_empty() {
+        return Ok(Vec::new());
+    }
+    let input: String = lang_map.iter().map(|(key, value)| format!("{}\t{}\n", escape(key), escape(value))).collect();
+    let output = run(command, timeout, input.into_bytes())?;
+    let output = String::from_utf8(output).map_err(|_| tf("transform_cmd.not_utf8", &[&command]))?;
+
+    let lines: Vec<&str> = output.lines().collect();
+    if lines.len() != lang_map.len() {
+        return Err(tf("transform_cmd.line_count", &[&lines.len(), &lang_map.len()]));
+    }
+    let mut changed = Vec::new();
+    for ((key, value), line) in lang_map.iter_mut().zip(lines) {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        let Some((output_key, output_value)) = line.split_once('\t') else {
+            return Err(tf("transform_cmd.not_tab_separated", &[&line]));
+        };
+        if unescape(output_key) != *key {
+            return Err(tf("transform_cmd.key_order", &[&output_key, &key]));
+        }
+        let new_value = unescape(output_value);
+        if new_value != *value {
+            changed.push((key.clone(), std::mem::replace(value, new_value)));
+        }
+    }
+    Ok(changed)
+}
+
+/// コマンドに標準入力を渡して実行し、時間内に終了コード0で終わったら標準出力を返す
+fn run(command: &str, timeout: Duration, input: Vec<u8>) -> Result<Vec<u8>, String> {
+    let mut child = shell(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| tf("transform_cmd.spawn_failed", &[&command, &e]))?;
+    // 出力が多いとパイプが詰まるため、書き込みと読み込みは別スレッドで行う
+    let mut stdin = child.stdin.take().unwrap();
+    thread::spawn(move || stdin.write_all(&input));
+    let mut stdout = child.stdout.take().unwrap();
+    let stdout = thread::spawn(move || {
+        let mut buffer = Vec::new();
+        stdout.read_to_end(&mut buffer).map(|_| buffer)
+    });
+    let mut stderr = child.stderr.take().unwrap();
+    let stderr = thread::spawn(move || {
+        let mut buffer = String::new();
+        let _ = stderr.read_to_string(&mut buffer);
+        buffer
+    });
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(tf("transform_cmd.timeout", &[&timeout.as_secs()]));
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(10)),
+            Err(e) => return Err(tf("transform_cmd.wait_failed", &[&e])),
+        }
+    };
+    let output = stdout.join().unwrap().map_err(|e| tf("transform_cmd.read_failed", &[&e]))?;
+    if !status.success() {
+        let stderr = stderr.join().unwrap_or_default();
+        let message = stderr.lines().last().unwrap_or("").trim();
+        return Err(tf("transform_cmd.failed", &[&status, &message]));
+    }
+    Ok(output)
+}
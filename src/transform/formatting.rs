@@ -0,0 +1,50 @@
+//! 値に含まれる `§` 書式コード(`§a`, `§l` など)の除去・変換・検出
+
+use clap::ValueEnum;
+
+/// 書式コードの扱い
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum FormattingCodes {
+    /// `§` と続く1文字を取り除く
+    Strip,
+    /// `§` を `&` (Bukkit形式) に置き換える
+    Ampersand,
+    /// 値は変更せず、書式コードを含むキーを報告する
+    Validate,
+}
+
+const SECTION: char = '§';
+
+/// `§` と続く1文字を取り除く。末尾の単独の `§` も取り除く
+pub fn strip(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == SECTION {
+            chars.next();
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// `§` を `&` に置き換える
+pub fn to_ampersand(value: &str) -> String {
+    value.replace(SECTION, "&")
+}
+
+/// 値に含まれる書式コードを出現順に返す(末尾の単独の `§` はそのまま `§` として返す)
+pub fn find_codes(value: &str) -> Vec<String> {
+    let mut codes = Vec::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == SECTION {
+            match chars.next() {
+                Some(code) => codes.push(format!("{}{}", SECTION, code)),
+                None => codes.push(SECTION.to_string()),
+            }
+        }
+    }
+    codes
+}
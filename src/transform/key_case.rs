@@ -0,0 +1,79 @@
+//! キーの大文字・小文字をそろえる(--key-case/--lowercase-keys)
+//!
+//! 絞り込みや並べ替えがそろえた後のキーを対象にするよう、ほかの加工より先に行う
+
+use clap::ValueEnum;
+use indexmap::IndexMap;
+use crate::concat::DuplicatePolicy;
+use super::Renamed;
+
+/// キーの大文字・小文字の扱い
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum KeyCase {
+    /// 入力のまま
+    #[default]
+    Preserve,
+    /// すべて小文字にする
+    Lower,
+}
+
+impl KeyCase {
+    /// 変わる場合だけ新しいキーを返す
+    fn apply(self, key: &str) -> Option<String> {
+        match self {
+            KeyCase::Preserve => None,
+            KeyCase::Lower => {
+                let lower = key.to_lowercase();
+                (lower != key).then_some(lower)
+            }
+        }
+    }
+}
+
+/// キーをそろえる。同じキーになった組(先のキーと後のキー)はcollisionsに記録し、
+/// 値はpolicyのFirstWins/LastWinsに従って選ぶ(位置は先のキーのまま)。Errorでは先の値を残す
+pub fn apply(lang_map: IndexMap<String, String>, case: KeyCase, policy: DuplicatePolicy) -> Renamed {
+    let mut renamed = IndexMap::with_capacity(lang_map.len());
+    let mut origins: IndexMap<String, String> = IndexMap::with_capacity(lang_map.len());
+    let mut collisions = Vec::new();
+    for (key, value) in lang_map {
+        let new_key = case.apply(&key).unwrap_or_else(|| key.clone());
+        if let Some(first) = origins.get(&new_key) {
+            collisions.push((first.clone(), key));
+            if policy == DuplicatePolicy::LastWins {
+                renamed.insert(new_key, value);
+            }
+            continue;
+        }
+        origins.insert(new_key.clone(), key);
+        renamed.insert(new_key, value);
+    }
+    Renamed { lang_map: renamed, collisions }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(entries: &[(&str, &str)]) -> IndexMap<String, String> {
+        entries.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn lower_keeps_the_first_position_and_records_collisions() {
+        let lang_map = map(&[("Item.Apple", "1"), ("other", "2"), ("item.apple", "3")]);
+        for (policy, value) in [(DuplicatePolicy::Error, "1"), (DuplicatePolicy::FirstWins, "1"), (DuplicatePolicy::LastWins, "3")] {
+            let renamed = apply(lang_map.clone(), KeyCase::Lower, policy);
+            assert_eq!(renamed.lang_map, map(&[("item.apple", value), ("other", "2")]));
+            assert_eq!(renamed.collisions, vec![("Item.Apple".to_string(), "item.apple".to_string())]);
+        }
+    }
+
+    #[test]
+    fn preserve_changes_nothing() {
+        let lang_map = map(&[("A", "1"), ("a", "2")]);
+        let renamed = apply(lang_map.clone(), KeyCase::Preserve, DuplicatePolicy::Error);
+        assert_eq!(renamed.lang_map, lang_map);
+        assert!(renamed.collisions.is_empty());
+    }
+}
@@ -0,0 +1,25 @@
+//! 正規表現によるキーの絞り込み
+
+use regex::Regex;
+use crate::i18n::tf;
+use json_lang::lang_map::LangMap;
+
+/// 残すキーと除外するキーの正規表現。includeを先に適用し、残ったキーにexcludeを適用する
+#[derive(Clone, Debug, Default)]
+pub struct KeyFilter {
+    pub include: Option<Regex>,
+    pub exclude: Option<Regex>,
+}
+
+/// コマンドライン引数の正規表現を解釈する
+pub fn parse_regex(pattern: &str) -> Result<Regex, String> {
+    Regex::new(pattern).map_err(|e| tf("convert.invalid_regex", &[&pattern, &e]))
+}
+
+impl KeyFilter {
+    /// 条件に合わないキーを取り除き、取り除いた件数を返す
+    pub fn apply(&self, lang_map: &mut LangMap) -> usize {
+        let included = self.include.as_ref().map_or(0, |re| lang_map.retain_matching(re));
+        included + self.exclude.as_ref().map_or(0, |re| lang_map.remove_matching(re))
+    }
+}
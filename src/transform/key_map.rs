@@ -0,0 +1,120 @@
+//! Java版と統合版(Bedrock)のキーの対応表による変換(--key-map)
+//!
+//! 対応表は1行に `Java版のキー,統合版のキー` の2列を書いたファイル(区切りはカンマかタブ、`#` で始まる行と空行は無視する)。
+//! 同じキーが1つの列に2回現れる対応表は、逆向きに変換できないためエラーにする。
+//! `bedrock-keys` フィーチャーを有効にしてビルドすると、`--key-map builtin` で同梱の対応表を使える
+
+use std::fs;
+use clap::ValueEnum;
+use indexmap::IndexMap;
+use crate::i18n::tf;
+use super::{rename_keys, Renamed};
+
+/// 同梱の対応表を使うときに--key-mapに指定する名前
+pub const BUILTIN: &str = "builtin";
+
+#[cfg(feature = "bedrock-keys")]
+const BUILTIN_TABLE: &str = include_str!("java_bedrock.csv");
+
+/// 変換の向き
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum KeyMapDirection {
+    /// Java版のキーを統合版のキーにする
+    JavaToBedrock,
+    /// 統合版のキーをJava版のキーにする
+    BedrockToJava,
+}
+
+/// 対応表に無いキーの扱い
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum UnmappedKeys {
+    /// そのままのキーで残す
+    Keep,
+    /// 出力しない
+    Drop,
+}
+
+/// --key-mapで読み込んだ対応表(Java版のキーと統合版のキーの組、ファイルの順)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyMap(pub Vec<(String, String)>);
+
+/// 対応表を読み込む(clapのvalue_parserとして使う)
+pub fn parse_key_map(path: &str) -> Result<KeyMap, String> {
+    if path == BUILTIN {
+        return builtin();
+    }
+    let contents = fs::read_to_string(path).map_err(|e| tf("key_map.read_failed", &[&path, &e]))?;
+    parse_table(&contents).map_err(|e| tf("key_map.table_error", &[&path, &e]))
+}
+
+#[cfg(feature = "bedrock-keys")]
+fn builtin() -> Result<KeyMap, String> {
+    parse_table(BUILTIN_TABLE).map_err(|e| tf("key_map.builtin_error", &[&e]))
+}
+
+#[cfg(not(feature = "bedrock-keys"))]
+fn builtin() -> Result<KeyMap, String> {
+    Err(crate::i18n::t("key_map.builtin_unavailable"))
+}
+
+fn parse_table(contents: &str) -> Result<KeyMap, String> {
+    let mut pairs = Vec::new();
+    let mut java_lines: IndexMap<String, usize> = IndexMap::new();
+    let mut bedrock_lines: IndexMap<String, usize> = IndexMap::new();
+    for (index, line) in contents.lines().enumerate() {
+        let number = index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((java, bedrock)) = line.split_once([',', '\t']) else {
+            return Err(tf("key_map.bad_line", &[&number, &line]));
+        };
+        let (java, bedrock) = (java.trim(), bedrock.trim());
+        if java.is_empty() || bedrock.is_empty() || bedrock.contains([',', '\t']) {
+            return Err(tf("key_map.bad_line", &[&number, &line]));
+        }
+        for (key, lines) in [(java, &mut java_lines), (bedrock, &mut bedrock_lines)] {
+            if let Some(first) = lines.insert(key.to_string(), number) {
+                return Err(tf("key_map.duplicate", &[&number, &key, &first]));
+            }
+        }
+        pairs.push((java.to_string(), bedrock.to_string()));
+    }
+    Ok(KeyMap(pairs))
+}
+
+/// 対応表による変換の結果
+pub struct Mapped {
+    pub renamed: Renamed,
+    /// 対応表で変換したキーの件数
+    pub mapped: usize,
+    /// 対応表に無かったキー(ファイルの順)
+    pub unmapped: Vec<String>,
+}
+
+impl KeyMap {
+    /// キーを変換する。変換後に同じキーになった組はrenamed.collisionsに記録する
+    pub fn apply(&self, lang_map: IndexMap<String, String>, direction: KeyMapDirection, unmapped_keys: UnmappedKeys) -> Mapped {
+        let lookup: IndexMap<&str, &str> = self
+            .0
+            .iter()
+            .map(|(java, bedrock)| match direction {
+                KeyMapDirection::JavaToBedrock => (java.as_str(), bedrock.as_str()),
+                KeyMapDirection::BedrockToJava => (bedrock.as_str(), java.as_str()),
+            })
+            .collect();
+        let mut unmapped = Vec::new();
+        let mut lang_map = lang_map;
+        lang_map.retain(|key, _| {
+            if lookup.contains_key(key.as_str()) {
+                return true;
+            }
+            unmapped.push(key.clone());
+            unmapped_keys == UnmappedKeys::Keep
+        });
+        let mapped = lang_map.len() - if unmapped_keys == UnmappedKeys::Keep { unmapped.len() } else { 0 };
+        let renamed = rename_keys(lang_map, |key| lookup.get(key).map(|target| target.to_string()));
+        Mapped { renamed, mapped, unmapped }
+    }
+}
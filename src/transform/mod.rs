@@ -0,0 +1,237 @@
+pub mod empty;
+pub mod escape;
+pub mod external;
+pub mod formatting;
+pub mod key_case;
+pub mod key_filter;
+pub mod key_map;
+pub mod normalize;
+pub mod placeholder;
+pub mod prefix;
+pub mod sort;
+pub mod vars;
+
+use std::collections::BTreeSet;
+use indexmap::IndexMap;
+use crate::check::mojibake;
+use crate::concat::DuplicatePolicy;
+use crate::convert::ConvertOptions;
+use crate::i18n::{t, tf};
+use json_lang::lang_map::LangMap;
+use formatting::FormattingCodes;
+use empty::Empties;
+use key_case::KeyCase;
+use key_map::UnmappedKeys;
+use vars::MissingVars;
+
+/// 加工後のマップと、ファイルごとに表示する報告
+pub struct Applied {
+    pub lang_map: IndexMap<String, String>,
+    pub notes: Vec<String>,
+    /// 接頭辞の付与・除去でキー名が変わった件数
+    pub renamed_keys: usize,
+    /// --key-include/--key-excludeで取り除いた件数
+    pub dropped_keys: usize,
+    /// --varsの変数のうち値の置き換えに使ったもの
+    pub used_vars: BTreeSet<String>,
+    /// --with-fallbackで基準のロケールから補ったキーの数(読み込み時に設定する)
+    pub filled_keys: Option<usize>,
+    /// 空のキーと空の値の数(--empty-keys/--empty-valuesで取り除いたものを含む)
+    pub empties: Empties,
+}
+
+/// --dry-runで表示する外部コマンドによる変更の件数
+const PREVIEW_LIMIT: usize = 10;
+
+/// 読み込み後・保存前のマップに変換オプションで指定された加工を適用する。empty_keysは読み込み時に空のキーが現れた回数
+pub fn apply(lang_map: IndexMap<String, String>, empty_keys: usize, options: &ConvertOptions) -> Result<Applied, String> {
+    let mut notes = Vec::new();
+    let mut lang_map = lang_map;
+    escape::unescape_values(&mut lang_map, options.unescape_profile);
+    let empties = empty::apply(&mut lang_map, empty_keys, options.empty_keys, options.empty_values, &mut notes)?;
+    let mut lang_map = LangMap::from(lang_map);
+    if options.key_case != KeyCase::Preserve {
+        let renamed = key_case::apply(lang_map.into(), options.key_case, options.key_case_duplicates);
+        let pairs: Vec<String> = renamed.collisions.iter().map(|(a, b)| format!("{} / {}", a, b)).collect();
+        match options.key_case_duplicates {
+            DuplicatePolicy::Error => check_collisions(&renamed.collisions, "transform.step.key_case")?,
+            DuplicatePolicy::FirstWins if !pairs.is_empty() => notes.push(tf("transform.key_case_first_wins", &[&pairs.join(", ")])),
+            DuplicatePolicy::LastWins if !pairs.is_empty() => notes.push(tf("transform.key_case_last_wins", &[&pairs.join(", ")])),
+            _ => {}
+        }
+        lang_map = renamed.lang_map.into();
+    }
+
+    // 絞り込みのパターンが変換後のエディションのキーを対象にするよう、絞り込みより先に変換する
+    if let Some(key_map) = &options.key_map {
+        let result = key_map.apply(lang_map.into(), options.key_map_direction, options.unmapped_keys);
+        check_collisions(&result.renamed.collisions, "transform.step.key_map")?;
+        lang_map = result.renamed.lang_map.into();
+        if result.mapped > 0 {
+            notes.push(tf("transform.key_mapped", &[&result.mapped]));
+        }
+        if !result.unmapped.is_empty() {
+            let mut listed = result.unmapped.iter().take(PREVIEW_LIMIT).cloned().collect::<Vec<_>>().join(", ");
+            if result.unmapped.len() > PREVIEW_LIMIT {
+                listed.push_str(&format!(" {}", tf("transform.more", &[&(result.unmapped.len() - PREVIEW_LIMIT)])));
+            }
+            let action = match options.unmapped_keys {
+                UnmappedKeys::Keep => t("transform.unmapped.keep"),
+                UnmappedKeys::Drop => t("transform.unmapped.drop"),
+            };
+            notes.push(tf("transform.unmapped", &[&action, &result.unmapped.len(), &listed]));
+        }
+    }
+
+    let dropped_keys = options.keys.apply(&mut lang_map);
+    if dropped_keys > 0 {
+        notes.push(tf("transform.dropped_keys", &[&dropped_keys]));
+    }
+
+    if options.fix_double_encoding {
+        let mut fixed = Vec::new();
+        for (key, value) in lang_map.iter_mut() {
+            if let Some(repaired) = mojibake::fix_double_encoding(value) {
+                fixed.push((key.clone(), std::mem::replace(value, repaired)));
+            }
+        }
+        if !fixed.is_empty() {
+            notes.push(tf("transform.fixed_double_encoding", &[&fixed.len()]));
+        }
+        if options.dry_run {
+            for (key, old) in fixed.iter().take(PREVIEW_LIMIT) {
+                notes.push(format!("{}: {:?} => {:?}", key, old, lang_map[key]));
+            }
+            if fixed.len() > PREVIEW_LIMIT {
+                notes.push(tf("transform.more", &[&(fixed.len() - PREVIEW_LIMIT)]));
+            }
+        }
+    }
+
+    // プレースホルダーの変換や検査が置き換え後の文字列を対象にするよう、最初に置き換える
+    let mut used_vars = BTreeSet::new();
+    if let Some(vars) = &options.vars {
+        let mut missing = BTreeSet::new();
+        for value in lang_map.values_mut() {
+            let substituted = vars.substitute(value);
+            *value = substituted.value;
+            used_vars.extend(substituted.used);
+            missing.extend(substituted.missing);
+        }
+        if !missing.is_empty() {
+            let names = missing.into_iter().collect::<Vec<_>>().join(", ");
+            match options.missing_vars {
+                MissingVars::Error => return Err(tf("transform.missing_vars_error", &[&names])),
+                MissingVars::Warn => notes.push(tf("transform.missing_vars", &[&names])),
+            }
+        }
+    }
+
+    // キーの重複検出の前に正規化し、見た目が同じキーを衝突として扱う
+    if let Some(form) = options.normalize {
+        let renamed = rename_keys(lang_map.into(), |key| {
+            let normalized = normalize::apply(key, form);
+            (normalized != key).then_some(normalized)
+        });
+        check_collisions(&renamed.collisions, "transform.step.normalize")?;
+        lang_map = renamed.lang_map.into();
+        for value in lang_map.values_mut() {
+            *value = normalize::apply(value, form);
+        }
+    }
+
+    let renames_keys = options.strip_prefix.is_some() || options.add_prefix.is_some();
+    let original_keys: Vec<String> = if renames_keys { lang_map.keys().cloned().collect() } else { Vec::new() };
+
+    if let Some(strip) = &options.strip_prefix {
+        let renamed = rename_keys(lang_map.into(), |key| prefix::strip_prefix(key, strip));
+        check_collisions(&renamed.collisions, "transform.step.strip_prefix")?;
+        lang_map = renamed.lang_map.into();
+    }
+    if let Some(add) = &options.add_prefix {
+        let only_matching = options.prefix_match.as_deref();
+        let renamed = rename_keys(lang_map.into(), |key| prefix::add_prefix(key, add, only_matching));
+        check_collisions(&renamed.collisions, "transform.step.add_prefix")?;
+        lang_map = renamed.lang_map.into();
+    }
+    // 重複が無ければキー名の変換で順序と件数は変わらないため、位置ごとに比較できる
+    let renamed_keys = original_keys.iter().zip(lang_map.keys()).filter(|(a, b)| a != b).count();
+    if renamed_keys > 0 {
+        notes.push(tf("transform.renamed_keys", &[&renamed_keys]));
+    }
+
+    if let Some(style) = options.placeholder_style {
+        for value in lang_map.values_mut() {
+            *value = placeholder::convert(value, style);
+        }
+    }
+    if let Some(mode) = options.formatting_codes {
+        for (key, value) in lang_map.iter_mut() {
+            match mode {
+                FormattingCodes::Strip => *value = formatting::strip(value),
+                FormattingCodes::Ampersand => *value = formatting::to_ampersand(value),
+                FormattingCodes::Validate => {
+                    let codes = formatting::find_codes(value);
+                    if !codes.is_empty() {
+                        notes.push(tf("transform.formatting_codes", &[key, &codes.join(", ")]));
+                    }
+                }
+            }
+        }
+    }
+    if let Some(command) = &options.transform_cmd {
+        let changed = external::apply(command, options.transform_timeout, &mut lang_map)?;
+        if !changed.is_empty() {
+            notes.push(tf("transform.external_changed", &[&changed.len()]));
+        }
+        // --dry-runでは変更の一部を見せて、書き込む前に確かめられるようにする
+        if options.dry_run {
+            for (key, old) in changed.iter().take(PREVIEW_LIMIT) {
+                notes.push(format!("{}: {:?} => {:?}", key, old, lang_map[key]));
+            }
+            if changed.len() > PREVIEW_LIMIT {
+                notes.push(tf("transform.more", &[&(changed.len() - PREVIEW_LIMIT)]));
+            }
+        }
+    }
+    lang_map.sort_keys(options.sort, options.sort_ignore_case);
+    let mut lang_map: IndexMap<String, String> = lang_map.into();
+    // 保存する直前にエスケープし、ほかの加工がエスケープ後の値を見ないようにする
+    escape::escape_values(&mut lang_map, options.escape_profile, &mut notes);
+    Ok(Applied { lang_map, notes, renamed_keys, dropped_keys, used_vars, filled_keys: None, empties })
+}
+
+/// キー名の変換結果。変換後に同じキーになったものはcollisionsに元のキーの組で記録する
+pub struct Renamed {
+    pub lang_map: IndexMap<String, String>,
+    pub collisions: Vec<(String, String)>,
+}
+
+/// 各キーに`rename`を適用する。Noneを返したキーはそのまま残す
+pub fn rename_keys<F>(lang_map: IndexMap<String, String>, rename: F) -> Renamed
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut renamed = IndexMap::with_capacity(lang_map.len());
+    let mut origins: IndexMap<String, String> = IndexMap::with_capacity(lang_map.len());
+    let mut collisions = Vec::new();
+    for (key, value) in lang_map {
+        let new_key = rename(&key).unwrap_or_else(|| key.clone());
+        if let Some(first) = origins.get(&new_key) {
+            collisions.push((first.clone(), key));
+            continue;
+        }
+        origins.insert(new_key.clone(), key);
+        renamed.insert(new_key, value);
+    }
+    Renamed { lang_map: renamed, collisions }
+}
+
+/// キー名の変換で同じキーになった組があればエラーにする。stepは変換の名前のメッセージのキー
+fn check_collisions(collisions: &[(String, String)], step: &str) -> Result<(), String> {
+    if collisions.is_empty() {
+        return Ok(());
+    }
+    let pairs: Vec<String> = collisions.iter().map(|(a, b)| format!("{} / {}", a, b)).collect();
+    Err(tf("transform.collision", &[&t(step), &pairs.join(", ")]))
+}
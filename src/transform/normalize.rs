@@ -0,0 +1,15 @@
+use clap::ValueEnum;
+use unicode_normalization::UnicodeNormalization;
+
+/// Unicode正規化の形式
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Normalization {
+    /// 正準等価性による合成(NFC)
+    Nfc,
+}
+
+pub fn apply(text: &str, form: Normalization) -> String {
+    match form {
+        Normalization::Nfc => text.nfc().collect(),
+    }
+}
@@ -0,0 +1,213 @@
+//! printf形式(`%s`, `%1$s`)とMessageFormat形式(`{0}`)のプレースホルダー相互変換
+//!
+//! エスケープ規則:
+//! - printf形式では `%%` が文字としての `%`。`%s`/`%d`/`%n$s`/`%n$d` 以外の `%` はそのまま残す
+//! - MessageFormat形式では `'{'` `'}'` が文字としての波括弧、`''` が文字としての `'`。
+//!   それ以外の単独の `'` は文字として扱う
+//! - MessageFormat形式の `{x}` のように数字だけでない波括弧はプレースホルダーとみなさず、文字として残す
+//!
+//! `%d` は `{0}` に変換すると変換文字が失われるため、逆変換では `%1$s` になる
+
+use clap::ValueEnum;
+
+/// 変換先のプレースホルダー形式
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum PlaceholderStyle {
+    /// `%s` / `%1$s` を `{0}` 形式に変換する
+    Brace,
+    /// `{0}` を `%1$s` 形式に変換する
+    Printf,
+}
+
+/// 値を指定した形式に変換する
+pub fn convert(value: &str, style: PlaceholderStyle) -> String {
+    match style {
+        PlaceholderStyle::Brace => printf_to_brace(value),
+        PlaceholderStyle::Printf => brace_to_printf(value),
+    }
+}
+
+/// `%s`, `%d`, `%1$s` を `{0}` 形式に変換する
+pub fn printf_to_brace(value: &str) -> String {
+    let chars: Vec<char> = value.chars().collect();
+    let mut out = String::with_capacity(value.len());
+    let mut next_index = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '%' => {
+                if chars.get(i + 1) == Some(&'%') {
+                    out.push('%');
+                    i += 2;
+                    continue;
+                }
+                if let Some((index, len)) = parse_printf_spec(&chars[i + 1..]) {
+                    let index = index.unwrap_or_else(|| {
+                        next_index += 1;
+                        next_index - 1
+                    });
+                    out.push_str(&format!("{{{}}}", index));
+                    i += 1 + len;
+                    continue;
+                }
+                out.push('%');
+            }
+            '{' => out.push_str("'{'"),
+            '}' => out.push_str("'}'"),
+            '\'' => {
+                // 後続の文字と組み合わさってエスケープと解釈されないよう二重にする
+                if matches!(chars.get(i + 1), Some('\'') | Some('{') | Some('}')) {
+                    out.push_str("''");
+                } else {
+                    out.push('\'');
+                }
+            }
+            c => out.push(c),
+        }
+        i += 1;
+    }
+    out
+}
+
+/// `%` の直後から書式指定を読み取り、(明示された0始まりの番号, 消費した文字数)を返す
+fn parse_printf_spec(rest: &[char]) -> Option<(Option<usize>, usize)> {
+    match rest.first() {
+        Some('s') | Some('d') => Some((None, 1)),
+        Some(c) if c.is_ascii_digit() => {
+            let digits: String = rest.iter().take_while(|c| c.is_ascii_digit()).collect();
+            let n: usize = digits.parse().ok()?;
+            if n == 0 || rest.get(digits.len()) != Some(&'$') {
+                return None;
+            }
+            match rest.get(digits.len() + 1) {
+                Some('s') | Some('d') => Some((Some(n - 1), digits.len() + 2)),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// `{0}` を `%1$s` 形式に変換する
+pub fn brace_to_printf(value: &str) -> String {
+    let chars: Vec<char> = value.chars().collect();
+    let mut out = String::with_capacity(value.len());
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\'' => {
+                let next = chars.get(i + 1);
+                if next == Some(&'\'') {
+                    out.push('\'');
+                    i += 2;
+                    continue;
+                }
+                if matches!(next, Some('{') | Some('}')) && chars.get(i + 2) == Some(&'\'') {
+                    out.push(chars[i + 1]);
+                    i += 3;
+                    continue;
+                }
+                out.push('\'');
+            }
+            '{' => {
+                let digits: String = chars[i + 1..].iter().take_while(|c| c.is_ascii_digit()).collect();
+                if !digits.is_empty() && chars.get(i + 1 + digits.len()) == Some(&'}') {
+                    if let Ok(n) = digits.parse::<usize>() {
+                        out.push_str(&format!("%{}$s", n + 1));
+                        i += digits.len() + 2;
+                        continue;
+                    }
+                }
+                out.push('{');
+            }
+            '%' => out.push_str("%%"),
+            c => out.push(c),
+        }
+        i += 1;
+    }
+    out
+}
+
+/// 値の中のプレースホルダー(`%s`, `%d`, `%1$s`, `{0}`, `{{name}}`)のバイト範囲を出現順に返す
+pub fn find_placeholders(value: &str) -> Vec<std::ops::Range<usize>> {
+    let bytes = value.as_bytes();
+    let mut ranges = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let len = match bytes[i] {
+            b'%' if bytes.get(i + 1) == Some(&b'%') => {
+                i += 2;
+                continue;
+            }
+            b'%' => {
+                let chars: Vec<char> = value[i + 1..].chars().take(12).collect();
+                parse_printf_spec(&chars).map(|(_, len)| len + 1)
+            }
+            b'{' if bytes.get(i + 1) == Some(&b'{') => value[i + 2..].find("}}").map(|end| end + 4),
+            b'{' => {
+                let digits = bytes[i + 1..].iter().take_while(|b| b.is_ascii_digit()).count();
+                (digits > 0 && bytes.get(i + 1 + digits) == Some(&b'}')).then_some(digits + 2)
+            }
+            _ => None,
+        };
+        match len {
+            Some(len) => {
+                ranges.push(i..i + len);
+                i += len;
+            }
+            None => i += 1,
+        }
+    }
+    ranges
+}
+
+/// 値からプレースホルダーを取り除く
+pub fn strip_placeholders(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut last = 0;
+    for range in find_placeholders(value) {
+        out.push_str(&value[last..range.start]);
+        last = range.end;
+    }
+    out.push_str(&value[last..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn printf_becomes_brace_in_encounter_order() {
+        assert_eq!(printf_to_brace("%s and %d"), "{0} and {1}");
+        assert_eq!(printf_to_brace("%2$s before %1$s"), "{1} before {0}");
+    }
+
+    #[test]
+    fn brace_becomes_numbered_printf() {
+        assert_eq!(brace_to_printf("{0} and {1}"), "%1$s and %2$s");
+        assert_eq!(brace_to_printf("{1} before {0}"), "%2$s before %1$s");
+    }
+
+    #[test]
+    fn literal_percent_is_escaped_both_ways() {
+        assert_eq!(printf_to_brace("100%% done"), "100% done");
+        assert_eq!(printf_to_brace("50% off"), "50% off");
+        assert_eq!(brace_to_printf("100% done"), "100%% done");
+    }
+
+    #[test]
+    fn non_numeric_braces_are_kept_as_text() {
+        assert_eq!(brace_to_printf("at {x}, {y}"), "at {x}, {y}");
+        assert_eq!(printf_to_brace("at {x}"), "at '{'x'}'");
+        assert_eq!(brace_to_printf("'{'0'}' is {0}"), "{0} is %1$s");
+    }
+
+    #[test]
+    fn quotes_survive_a_round_trip() {
+        for value in ["it's %s", "''", "'{", "%s'{'x"] {
+            let brace = printf_to_brace(value);
+            assert_eq!(brace_to_printf(&brace), value.replace("%s", "%1$s"), "{}", value);
+        }
+    }
+}
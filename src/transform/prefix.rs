@@ -0,0 +1,15 @@
+//! キーの接頭辞の付与・除去
+
+/// 接頭辞を取り除く(接頭辞で始まらないキーは変更しない)
+pub fn strip_prefix(key: &str, prefix: &str) -> Option<String> {
+    key.strip_prefix(prefix).map(|rest| rest.to_string())
+}
+
+/// 接頭辞を付与する。`only_matching`が指定されていればそれで始まるキーだけを対象とし、
+/// すでに接頭辞で始まるキーは変更しない
+pub fn add_prefix(key: &str, prefix: &str, only_matching: Option<&str>) -> Option<String> {
+    if key.starts_with(prefix) || only_matching.is_some_and(|m| !key.starts_with(m)) {
+        return None;
+    }
+    Some(format!("{}{}", prefix, key))
+}
@@ -0,0 +1,9 @@
+//! 出力するキーの並べ替え
+//!
+//! 値の順(`--sort values`)は似た文字列を見比べるための確認用で、既定では出力ファイル名に `.byvalue` を付ける。
+//! 並び順と比べ方はLangMapの並べ替え(json_lang::lang_map)と共通
+
+pub use json_lang::lang_map::SortOrder;
+
+/// --sort valuesの出力ファイル名で拡張子の前に付ける文字列
+pub const BY_VALUE_SUFFIX: &str = "byvalue";
@@ -0,0 +1,79 @@
+//! 値の中の `{{name}}` を変数ファイル(キーと文字列の値のJSONオブジェクト)の値に置き換える
+//!
+//! - 変数名の前後の空白は無視する(`{{ brand }}` は `{{brand}}` と同じ)
+//! - `\{{` は置き換えず、文字としての `{{` になる
+//! - `}}` で閉じていない `{{` はそのまま残す
+
+use std::collections::BTreeSet;
+use std::fs;
+use clap::ValueEnum;
+use indexmap::IndexMap;
+use crate::i18n::tf;
+
+/// 変数ファイルに無い変数が値に含まれていたときの扱い
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum MissingVars {
+    /// ファイルの変換を失敗にする
+    Error,
+    /// 報告して `{{name}}` のまま残す
+    Warn,
+}
+
+/// --varsで読み込んだ変数
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Vars(pub IndexMap<String, String>);
+
+/// 変数ファイルを読み込む(clapのvalue_parserとして使う)
+pub fn parse_vars(path: &str) -> Result<Vars, String> {
+    let contents = fs::read_to_string(path).map_err(|e| tf("vars.read_failed", &[&path, &e]))?;
+    let vars: IndexMap<String, String> =
+        serde_json::from_str(&contents).map_err(|e| tf("vars.not_object", &[&path, &e]))?;
+    Ok(Vars(vars))
+}
+
+/// 1つの値の置き換え結果
+pub struct Substituted {
+    pub value: String,
+    /// 使った変数
+    pub used: BTreeSet<String>,
+    /// 変数ファイルに無かった変数
+    pub missing: BTreeSet<String>,
+}
+
+impl Vars {
+    /// 値の中の `{{name}}` を置き換える。無い変数はそのまま残す
+    pub fn substitute(&self, value: &str) -> Substituted {
+        let mut out = String::with_capacity(value.len());
+        let mut used = BTreeSet::new();
+        let mut missing = BTreeSet::new();
+        let mut rest = value;
+        while let Some(start) = rest.find("{{") {
+            if rest[..start].ends_with('\\') {
+                out.push_str(&rest[..start - 1]);
+                out.push_str("{{");
+                rest = &rest[start + 2..];
+                continue;
+            }
+            out.push_str(&rest[..start]);
+            let Some(end) = rest[start + 2..].find("}}") else {
+                rest = &rest[start..];
+                break;
+            };
+            let placeholder = &rest[start..start + 2 + end + 2];
+            let name = rest[start + 2..start + 2 + end].trim();
+            match self.0.get(name) {
+                Some(replacement) => {
+                    out.push_str(replacement);
+                    used.insert(name.to_string());
+                }
+                None => {
+                    out.push_str(placeholder);
+                    missing.insert(name.to_string());
+                }
+            }
+            rest = &rest[start + placeholder.len()..];
+        }
+        out.push_str(rest);
+        Substituted { value: out, used, missing }
+    }
+}
@@ -0,0 +1,188 @@
+use std::fs;
+use std::path::Path;
+use indexmap::IndexSet;
+use crate::cli::VerifyArgs;
+use crate::convert::files_or_input_dir;
+use crate::i18n::{t, tf};
+use json_lang::lang_map::{DiffResult, LangMap};
+use json_lang::roundtrip::{verify_roundtrip, RoundtripLoss};
+use crate::format::{parse_lang_map, read_json_entries, Format, JsonValue, WriteOptions};
+
+/// 往復変換の違いを報告の文にする
+fn describe(diff: &DiffResult) -> Vec<String> {
+    let mut losses: Vec<String> = diff.removed.iter().map(|key| tf("verify.key_removed", &[key])).collect();
+    for (key, value, other) in &diff.changed {
+        losses.push(tf("verify.value_changed", &[key, &format!("{:?}", value), &format!("{:?}", other)]));
+    }
+    losses.extend(diff.added.iter().map(|key| tf("verify.key_added", &[key])));
+    if diff.reordered {
+        losses.push(t("verify.reordered"));
+    }
+    losses
+}
+
+/// 2つの内容が最初に異なる行番号(1始まり)
+fn first_different_line(a: &[u8], b: &[u8]) -> usize {
+    let position = a.iter().zip(b).position(|(x, y)| x != y).unwrap_or(a.len().min(b.len()));
+    a[..position].iter().filter(|byte| **byte == b'\n').count() + 1
+}
+
+/// .lang → JSON → .lang の往復で失われる内容
+fn verify_lang(contents: &[u8]) -> Result<Vec<String>, String> {
+    let text = std::str::from_utf8(contents).map_err(|_| t("verify.not_utf8"))?;
+    let mut losses = Vec::new();
+    let mut seen = IndexSet::new();
+    for (index, line) in text.lines().enumerate() {
+        let number = index + 1;
+        if line.trim().is_empty() {
+            losses.push(tf("verify.blank_line", &[&number]));
+        } else if line.starts_with('#') {
+            losses.push(tf("verify.comment", &[&number]));
+        } else if let Some((key, value)) = line.split_once('=') {
+            if key.trim() != key || value.trim() != value {
+                losses.push(tf("verify.padding", &[&number]));
+            }
+            if !seen.insert(key.trim()) {
+                losses.push(tf("verify.lang_duplicate", &[&number, &key.trim()]));
+            }
+        } else {
+            losses.push(tf("verify.not_entry", &[&number]));
+        }
+    }
+
+    let lang_map = LangMap::from(parse_lang_map(text));
+    if let Err(RoundtripLoss { json: Some(diff), .. }) = verify_roundtrip(&lang_map, &WriteOptions::default()) {
+        losses.extend(describe(&diff));
+    }
+    let rendered = Format::Lang.render(&lang_map, &WriteOptions::default())?;
+    if losses.is_empty() && rendered != contents {
+        losses.push(tf("verify.lang_not_identical", &[&first_different_line(contents, &rendered)]));
+    }
+    Ok(losses)
+}
+
+/// JSON → .lang → JSON の往復で失われる内容
+fn verify_json(contents: &[u8]) -> Result<Vec<String>, String> {
+    let mut losses = Vec::new();
+    let mut seen = IndexSet::new();
+    let mut lang_map = LangMap::default();
+    let top_level = read_json_entries(contents, |key, value| {
+        if !seen.insert(key.clone()) {
+            losses.push(tf("verify.json_duplicate", &[&key]));
+        }
+        match value {
+            JsonValue::String(value) => {
+                lang_map.insert(key, value);
+            }
+            other => {
+                losses.push(tf("verify.not_string", &[&key, &other.type_name()]));
+                lang_map.shift_remove(&key);
+            }
+        }
+    })
+    .map_err(|e| tf("verify.json_parse_failed", &[&e]))?;
+    if top_level != "object" {
+        losses.push(t("verify.not_object"));
+    }
+
+    for (key, value) in &lang_map {
+        if key.contains('=') || key.starts_with('#') || key.trim() != key || value.trim() != value || key.contains('\n') || value.contains('\n') {
+            losses.push(tf("verify.unrepresentable", &[key]));
+        }
+    }
+    if losses.is_empty() {
+        if let Err(RoundtripLoss { lang: Some(diff), .. }) = verify_roundtrip(&lang_map, &WriteOptions::default()) {
+            losses.extend(describe(&diff));
+        }
+    }
+    let rendered = Format::Json.render(&lang_map, &WriteOptions::default())?;
+    if losses.is_empty() && rendered != contents {
+        losses.push(tf("verify.json_not_identical", &[&first_different_line(contents, &rendered)]));
+    }
+    Ok(losses)
+}
+
+/// verifyサブコマンドの実行。ファイルは書き込まない
+pub fn run_verify(args: &VerifyArgs) -> Result<bool, String> {
+    let mut lossless = true;
+    for path in files_or_input_dir(&args.files, &args.input)? {
+        let path_str = path.to_string_lossy().to_string();
+        let format = Format::from_path(Path::new(&path)).ok_or_else(|| tf("io.unsupported_extension", &[&path_str]))?;
+        let contents = fs::read(&path).map_err(|_| tf("io.read_failed", &[&path_str]))?;
+        let losses = match format {
+            Format::Lang => verify_lang(&contents)?,
+            Format::Json => verify_json(&contents)?,
+        };
+        if losses.is_empty() {
+            println!("{}", tf("verify.lossless", &[&path_str]));
+            continue;
+        }
+        lossless = false;
+        println!("{}", tf("verify.losses", &[&path_str, &losses.len()]));
+        for loss in &losses {
+            println!("  - {}", loss);
+        }
+    }
+    Ok(lossless)
+}
+
+#[cfg(test)]
+mod tests {
+    use indexmap::IndexMap;
+    use crate::format::parse_json_map;
+    use super::*;
+
+    const LANG: &str = "block.minecraft.stone=Stone\nitem.minecraft.apple=Apple %s\ngui.done=Done\n";
+    const JSON: &str = "{\n  \"block.minecraft.stone\": \"Stone\",\n  \"item.minecraft.apple\": \"Apple %s\",\n  \"gui.done\": \"Done\"\n}";
+
+    fn render(format: Format, lang_map: &IndexMap<String, String>) -> Vec<u8> {
+        format.render(lang_map, &WriteOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn lang_json_lang_is_byte_identical() {
+        let json = render(Format::Json, &parse_lang_map(LANG));
+        let lang = render(Format::Lang, &parse_json_map(json.as_slice()).unwrap());
+        assert_eq!(String::from_utf8(lang).unwrap(), LANG);
+    }
+
+    #[test]
+    fn json_lang_json_is_byte_identical() {
+        let lang = render(Format::Lang, &parse_json_map(JSON.as_bytes()).unwrap());
+        let json = render(Format::Json, &parse_lang_map(&String::from_utf8(lang).unwrap()));
+        assert_eq!(String::from_utf8(json).unwrap(), JSON);
+    }
+
+    #[test]
+    fn well_formed_files_have_no_losses() {
+        assert!(verify_lang(LANG.as_bytes()).unwrap().is_empty());
+        assert!(verify_json(JSON.as_bytes()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn lang_losses_are_reported_per_line() {
+        let losses = verify_lang(b"# comment\na=1\n\nb = 2\na=3\nbroken\n").unwrap();
+        assert_eq!(losses.len(), 5, "{:?}", losses);
+        for (loss, line) in losses.iter().zip(["1", "3", "4", "5", "6"]) {
+            assert!(loss.contains(line), "{} / {}", loss, line);
+        }
+    }
+
+    #[test]
+    fn json_duplicates_and_non_strings_are_reported() {
+        let losses = verify_json(br#"{"a": "1", "a": "2", "b": 3, "c": null}"#).unwrap();
+        assert_eq!(losses.len(), 3, "{:?}", losses);
+        assert!(losses[0].contains('a') && losses[1].contains('b') && losses[2].contains('c'));
+    }
+
+    #[test]
+    fn json_keys_that_lang_cannot_hold_are_reported() {
+        let losses = verify_json(br#"{"a=b": "x", "c": " padded "}"#).unwrap();
+        assert_eq!(losses.len(), 2, "{:?}", losses);
+    }
+
+    #[test]
+    fn non_object_top_level_loses_everything() {
+        assert_eq!(verify_json(b"[1, 2]").unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,16 @@
+//! JavaScriptから呼ぶ変換関数(既定の書式で出力する)
+
+use wasm_bindgen::prelude::*;
+use crate::codec::{self, WriteOptions};
+
+/// .langのテキストを整形したJSONに変換する
+#[wasm_bindgen]
+pub fn lang_to_json(text: &str) -> Result<String, JsValue> {
+    codec::lang_to_json(text, &WriteOptions::default()).map_err(|e| JsValue::from_str(&e))
+}
+
+/// JSONのテキストを.langに変換する
+#[wasm_bindgen]
+pub fn json_to_lang(text: &str) -> Result<String, JsValue> {
+    codec::json_to_lang(text, &WriteOptions::default()).map_err(|e| JsValue::from_str(&e))
+}